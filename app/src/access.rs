@@ -0,0 +1,241 @@
+//! Sharing the projects with other users and checking their roles
+//!
+//! The owner of a project is `Project.owner`. Other users are getting a role by accepting
+//! an invitation from the owner. Users without a role should not know that the project
+//! exists, so they are getting `ProjectNotFound` instead of `Forbidden`.
+
+use serde::Serialize;
+use surrealdb::{sql::Thing, Connection, Surreal};
+
+use crate::model::{thing, Project, ProjectInvitation, ProjectMember, Role, User};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AccessError {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+    #[error("project not exists or not shared with the user")]
+    ProjectNotFound,
+    #[error("{required:?} role is required for this operation")]
+    Forbidden { required: Role },
+    #[error("invitation not exists or already responded")]
+    InvitationNotFound,
+    #[error("user is not a member of the project")]
+    MemberNotFound,
+    #[error("user is already a member of the project")]
+    AlreadyMember,
+    #[error("owner role can not be given to a member")]
+    OwnerRole,
+}
+
+/// A user who can access the project
+#[derive(Serialize, Clone, Debug)]
+pub struct ProjectAccess {
+    pub user_id: String,
+    pub role: Role,
+}
+
+async fn membership<D: Connection>(
+    db: &Surreal<D>,
+    project: &Thing,
+    user: &Thing,
+) -> Result<Option<ProjectMember>, surrealdb::Error> {
+    let mut members_res = db
+        .query("SELECT * FROM type::table($table) WHERE project = $project AND user = $user LIMIT 1")
+        .bind(("table", ProjectMember::TABLE))
+        .bind(("project", project.clone()))
+        .bind(("user", user.clone()))
+        .await?;
+    members_res.take(0)
+}
+
+/// Role of the user in the project. `None` if the project not shared with the user
+pub async fn project_role<D: Connection>(
+    db: &Surreal<D>,
+    project: &Project,
+    user_id: &str,
+) -> Result<Option<Role>, surrealdb::Error> {
+    let user = thing(User::TABLE, user_id);
+    if project.owner == user {
+        return Ok(Some(Role::Owner));
+    }
+    let member = membership(db, project.id.as_ref().unwrap(), &user).await?;
+    Ok(member.map(|member| member.role))
+}
+
+/// Finding the project and checking that the user has at least the required role
+pub async fn require_role<D: Connection>(
+    db: &Surreal<D>,
+    project_id: &str,
+    user_id: &str,
+    required: Role,
+) -> Result<(Project, Role), AccessError> {
+    let project: Option<Project> = db.select((Project::TABLE, project_id)).await?;
+    let project = project.ok_or(AccessError::ProjectNotFound)?;
+    match project_role(db, &project, user_id).await? {
+        None => Err(AccessError::ProjectNotFound),
+        Some(role) if role < required => Err(AccessError::Forbidden { required }),
+        Some(role) => Ok((project, role)),
+    }
+}
+
+/// Listing the owner and the members of the project
+pub async fn project_members<D: Connection>(
+    db: &Surreal<D>,
+    project_id: &str,
+    user_id: &str,
+) -> Result<Vec<ProjectAccess>, AccessError> {
+    let (project, _) = require_role(db, project_id, user_id, Role::Viewer).await?;
+    let mut members_res = db
+        .query("SELECT * FROM type::table($table) WHERE project = $project ORDER BY created_at")
+        .bind(("table", ProjectMember::TABLE))
+        .bind(("project", project.id.clone().unwrap()))
+        .await?;
+    let members: Vec<ProjectMember> = members_res.take(0)?;
+
+    let mut accesses = vec![ProjectAccess {
+        user_id: project.owner.id.to_raw(),
+        role: Role::Owner,
+    }];
+    accesses.extend(members.into_iter().map(|member| ProjectAccess {
+        user_id: member.user.id.to_raw(),
+        role: member.role,
+    }));
+    Ok(accesses)
+}
+
+/// Inviting a user to the project. Replacing the pending invitation of the same user
+pub async fn invite_to_project<D: Connection>(
+    db: &Surreal<D>,
+    project_id: &str,
+    owner_id: &str,
+    invitee_id: &str,
+    role: Role,
+) -> Result<ProjectInvitation, AccessError> {
+    if role == Role::Owner {
+        return Err(AccessError::OwnerRole);
+    }
+    let (project, _) = require_role(db, project_id, owner_id, Role::Owner).await?;
+    if project_role(db, &project, invitee_id).await?.is_some() {
+        return Err(AccessError::AlreadyMember);
+    }
+
+    let project_thing = project.id.unwrap();
+    let invitee = thing(User::TABLE, invitee_id);
+    let mut pending_res = db
+        .query("DELETE type::table($table) WHERE project = $project AND user = $user AND responded_at IS none")
+        .bind(("table", ProjectInvitation::TABLE))
+        .bind(("project", project_thing.clone()))
+        .bind(("user", invitee.clone()))
+        .await?;
+    let _pending: Vec<ProjectInvitation> = pending_res.take(0)?;
+
+    let invitation =
+        ProjectInvitation::new(project_thing, invitee, role, thing(User::TABLE, owner_id));
+    let mut created: Vec<ProjectInvitation> = db
+        .create(ProjectInvitation::TABLE)
+        .content(invitation)
+        .await?;
+    Ok(created.pop().unwrap())
+}
+
+/// Listing the invitations that the user not responded yet
+pub async fn pending_invitations<D: Connection>(
+    db: &Surreal<D>,
+    user_id: &str,
+) -> Result<Vec<ProjectInvitation>, surrealdb::Error> {
+    let mut invitations_res = db
+        .query("SELECT * FROM type::table($table) WHERE user = $user AND responded_at IS none ORDER BY created_at DESC")
+        .bind(("table", ProjectInvitation::TABLE))
+        .bind(("user", thing(User::TABLE, user_id)))
+        .await?;
+    invitations_res.take(0)
+}
+
+/// Accepting or declining an invitation. Accepting is adding the user as a member
+pub async fn respond_to_invitation<D: Connection>(
+    db: &Surreal<D>,
+    invitation_id: &str,
+    user_id: &str,
+    accept: bool,
+) -> Result<(), AccessError> {
+    let invitation: Option<ProjectInvitation> = db
+        .select((ProjectInvitation::TABLE, invitation_id))
+        .await?;
+    let mut invitation = match invitation {
+        Some(invitation)
+            if invitation.user == thing(User::TABLE, user_id)
+                && invitation.responded_at.is_none() =>
+        {
+            invitation
+        }
+        _ => return Err(AccessError::InvitationNotFound),
+    };
+
+    invitation.respond(accept);
+    let _updated: Option<ProjectInvitation> = db
+        .update(invitation.id.clone().unwrap())
+        .content(invitation.clone())
+        .await?;
+
+    if accept {
+        let user = thing(User::TABLE, user_id);
+        match membership(db, &invitation.project, &user).await? {
+            Some(mut member) => {
+                member.role = invitation.role;
+                let _updated: Option<ProjectMember> = db
+                    .update(member.id.clone().unwrap())
+                    .content(member)
+                    .await?;
+            }
+            None => {
+                let member = ProjectMember::new(invitation.project, user, invitation.role);
+                let _created: Vec<ProjectMember> =
+                    db.create(ProjectMember::TABLE).content(member).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Changing the role of a member. Only the owner can change the roles
+pub async fn change_member_role<D: Connection>(
+    db: &Surreal<D>,
+    project_id: &str,
+    owner_id: &str,
+    member_id: &str,
+    role: Role,
+) -> Result<(), AccessError> {
+    if role == Role::Owner {
+        return Err(AccessError::OwnerRole);
+    }
+    let (project, _) = require_role(db, project_id, owner_id, Role::Owner).await?;
+    let member = membership(db, project.id.as_ref().unwrap(), &thing(User::TABLE, member_id))
+        .await?;
+    let mut member = member.ok_or(AccessError::MemberNotFound)?;
+    member.role = role;
+    let _updated: Option<ProjectMember> = db
+        .update(member.id.clone().unwrap())
+        .content(member)
+        .await?;
+    Ok(())
+}
+
+/// Removing a member from the project. The owner can remove anyone, and members can leave
+pub async fn remove_member<D: Connection>(
+    db: &Surreal<D>,
+    project_id: &str,
+    user_id: &str,
+    member_id: &str,
+) -> Result<(), AccessError> {
+    let required = if user_id == member_id {
+        Role::Viewer
+    } else {
+        Role::Owner
+    };
+    let (project, _) = require_role(db, project_id, user_id, required).await?;
+    let member = membership(db, project.id.as_ref().unwrap(), &thing(User::TABLE, member_id))
+        .await?;
+    let member = member.ok_or(AccessError::MemberNotFound)?;
+    let _removed: Option<ProjectMember> = db.delete(member.id.unwrap()).await?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Append-only log of the security and content related events
+//!
+//! Events are only created and queried. Nothing in the app updates or removes them.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use surrealdb::{sql::Thing, Connection, Surreal};
+
+use crate::model::{thing, AuditAction, AuditEvent, User};
+
+/// Maximum count of events in a page
+pub const MAX_PER_PAGE: usize = 200;
+
+/// Filters to query the events. Missing filters are matching any event
+#[derive(Default, Clone, Debug)]
+pub struct AuditFilter {
+    /// Id of the user who did the action
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub target: Option<Thing>,
+    /// Events created at or after this time. Eg:- `2023-04-01T00:00:00Z`
+    pub from: Option<String>,
+    /// Events created before this time
+    pub to: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditPage {
+    pub events: Vec<AuditEvent>,
+    /// Count of all events that matched the filters
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+#[derive(Deserialize)]
+struct Count {
+    count: usize,
+}
+
+/// Saving an event. Failures are logged without failing the action that already happened
+pub async fn record_event<D: Connection>(db: &Surreal<D>, event: AuditEvent) {
+    let action = event.action;
+    let created: Result<Vec<AuditEvent>, surrealdb::Error> =
+        db.create(AuditEvent::TABLE).content(event).await;
+    if let Err(e) = created {
+        warn!("Failed to record the audit event {:?}:- {:?}", action, e);
+    }
+}
+
+/// Events that matched the filters. Latest events first
+///
+/// `page` is starting from 1.
+pub async fn query_events<D: Connection>(
+    db: &Surreal<D>,
+    filter: AuditFilter,
+    page: usize,
+    per_page: usize,
+) -> Result<AuditPage, surrealdb::Error> {
+    let page = page.max(1);
+    let per_page = per_page.clamp(1, MAX_PER_PAGE);
+
+    let mut conditions = vec!["true"];
+    if filter.actor.is_some() {
+        conditions.push("actor = $actor");
+    }
+    if filter.action.is_some() {
+        conditions.push("action = $action");
+    }
+    if filter.target.is_some() {
+        conditions.push("target = $target");
+    }
+    if filter.from.is_some() {
+        conditions.push("created_at >= type::datetime($from)");
+    }
+    if filter.to.is_some() {
+        conditions.push("created_at < type::datetime($to)");
+    }
+    let condition = conditions.join(" AND ");
+
+    let mut events_res = db
+        .query(format!(
+            "SELECT * FROM type::table($table) WHERE {} ORDER BY created_at DESC LIMIT {} START {}",
+            condition,
+            per_page,
+            (page - 1) * per_page
+        ))
+        .query(format!(
+            "SELECT count() FROM type::table($table) WHERE {} GROUP ALL",
+            condition
+        ))
+        .bind(("table", AuditEvent::TABLE))
+        .bind(("actor", filter.actor.map(|actor| thing(User::TABLE, actor))))
+        .bind(("action", filter.action))
+        .bind(("target", filter.target))
+        .bind(("from", filter.from))
+        .bind(("to", filter.to))
+        .await?;
+    let events: Vec<AuditEvent> = events_res.take(0)?;
+    let count: Option<Count> = events_res.take(1)?;
+
+    Ok(AuditPage {
+        events,
+        total: count.map(|count| count.count).unwrap_or(0),
+        page,
+        per_page,
+    })
+}
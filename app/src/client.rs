@@ -1,7 +1,7 @@
-use std::{fmt::Debug, marker::PhantomData};
+use std::{fmt::{Debug, Display}, marker::PhantomData};
 
 use futures::{Stream, Sink};
-use transport::{ui::UIMessage, app::{ApplicationMessage, ErrorMessage, TabCreatedMessage, PongMessage}, Client as InternalClient, ReceiveError, SendError, vo::Screen};
+use transport::{ui::UIMessage, app::{ApplicationMessage, ErrorCode, ErrorMessage, TabCreatedMessage, PongMessage, ScreensChangedMessage, TabRenamedMessage, CommitCreatedMessage, SessionClosingMessage, ResumeTicketMessage, SessionResumedMessage, PreviousSessionFoundMessage}, rpc::{RpcRequest, RpcResponse}, Client as InternalClient, ReceiveError, SendError, vo::ScreenPatch};
 
 /// Trait constraints to internal transport of the `Client`
 pub trait ClientTransport<E: Debug + Send>:
@@ -14,6 +14,31 @@ impl<E: Debug + Send, T> ClientTransport<E> for T where
 {
 }
 
+/// Errors that should be reported to the UI
+///
+/// The `Display` output is used as the human readable message.
+pub trait ReportableError: Display {
+    /// Kind of the error to identify it in the UI
+    fn code(&self) -> ErrorCode;
+
+    /// Whether the UI can send the same request again
+    fn retryable(&self) -> bool {
+        false
+    }
+
+    /// Converting to the message that sent to the UI
+    fn report(&self) -> ErrorMessage {
+        ErrorMessage::new(self.code(), self.to_string(), None, self.retryable())
+    }
+
+    /// Converting to the message that sent to the UI as the answer of the failed request
+    fn report_for(&self, request: RpcRequest) -> ErrorMessage {
+        let mut message = self.report();
+        message.request = Some(UIMessage::Rpc(request));
+        message
+    }
+}
+
 /// Main transport media between UI and application logics
 pub struct Client<E: Debug + Send, T: ClientTransport<E>> {
     internal: InternalClient<UIMessage, ApplicationMessage, E, T>,
@@ -32,15 +57,52 @@ impl<E: Debug + Send, T: ClientTransport<E>> Client<E, T> {
         self.internal.receive_raw().await
     }
 
-    pub async fn error<NE: Debug>(&mut self, err: NE)  -> Result<(), SendError<E>> {
-        self.internal.send(ErrorMessage::new(format!("{:?}", err))).await
-    }
-
-    pub async fn tab_created(&mut self, tab_name: String, tab_id: String, zoom: f64, screens: Vec<Screen>) -> Result<(), SendError<E>> {
-        self.internal.send(TabCreatedMessage::new(tab_name, tab_id, screens, zoom)).await
+    /// Answering to a typed request
+    pub async fn respond(&mut self, response: RpcResponse) -> Result<(), SendError<E>> {
+        self.internal.send(response).await
     }
 
     pub async fn pong(&mut self) -> Result<() , SendError<E>> {
         self.internal.send(PongMessage::new()).await
     }
+
+    /// Pushing changed screens of a tab to the UI
+    pub async fn screens_changed(&mut self, tab_id: String, patches: Vec<ScreenPatch>) -> Result<(), SendError<E>> {
+        self.internal.send(ScreensChangedMessage::new(tab_id, patches)).await
+    }
+
+    /// Pushing the new name of a tab to the UI
+    pub async fn tab_renamed(&mut self, tab_id: String, tab_name: String) -> Result<(), SendError<E>> {
+        self.internal.send(TabRenamedMessage::new(tab_id, tab_name)).await
+    }
+
+    /// Notifying the UI that the changes of a tab were committed
+    pub async fn commit_created(&mut self, tab_id: String, commit_id: String, message: String) -> Result<(), SendError<E>> {
+        self.internal.send(CommitCreatedMessage::new(tab_id, commit_id, message)).await
+    }
+
+    /// Sending the ticket to reattach to the session after a connection drop
+    pub async fn resume_ticket(&mut self, ticket: String) -> Result<(), SendError<E>> {
+        self.internal.send(ResumeTicketMessage::new(ticket)).await
+    }
+
+    /// Sending the tabs of a reattached session
+    pub async fn session_resumed(&mut self, tabs: Vec<TabCreatedMessage>, current_tab: Option<String>) -> Result<(), SendError<E>> {
+        self.internal.send(SessionResumedMessage::new(tabs, current_tab)).await
+    }
+
+    /// Notifying the UI that the session is going to close
+    pub async fn session_closing(&mut self, reason: String) -> Result<(), SendError<E>> {
+        self.internal.send(SessionClosingMessage::new(reason)).await
+    }
+
+    /// Closing the connection with the UI. The session data is kept
+    pub async fn close(&mut self) -> Result<(), SendError<E>> {
+        self.internal.close().await
+    }
+
+    /// Offering the user to restore the tabs of an unclosed previous session
+    pub async fn previous_session_found(&mut self, session_id: String, tabs: Vec<String>, unsaved_actions: usize) -> Result<(), SendError<E>> {
+        self.internal.send(PreviousSessionFoundMessage::new(session_id, tabs, unsaved_actions)).await
+    }
 }
@@ -0,0 +1,56 @@
+//! Changes to the projects that every session with an opened tab should know
+//!
+//! A project can be opened by many sessions at the same time. Eg:- the owner renamed a
+//! project from the dashboard while a viewer is looking at it in the editor. Changes are
+//! published here and each session pushes them to its own UI.
+
+use log::trace;
+use surrealdb::sql::Thing;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+/// Count of events that can wait for a slow session before it starts missing them
+const EVENTS_CAPACITY: usize = 256;
+
+#[derive(Clone, Debug)]
+pub enum DocumentEvent {
+    /// The project of the branch renamed
+    ProjectRenamed { branch: Thing, name: String },
+    /// A new commit added to the branch
+    CommitCreated {
+        branch: Thing,
+        commit: Thing,
+        message: String,
+    },
+}
+
+/// Publishing the document events to all the sessions of an app
+#[derive(Clone)]
+pub struct DocumentEvents {
+    sender: Sender<DocumentEvent>,
+}
+
+impl DocumentEvents {
+    pub fn new() -> DocumentEvents {
+        let (sender, _) = broadcast::channel(EVENTS_CAPACITY);
+        DocumentEvents { sender }
+    }
+
+    /// Sending the event to all the sessions that are currently subscribed
+    pub fn publish(&self, event: DocumentEvent) {
+        // Failing only when no any session is running
+        if let Err(e) = self.sender.send(event) {
+            trace!("No session to receive the event:- {:?}", e.0);
+        }
+    }
+
+    /// Receiving the events that published after this call
+    pub fn subscribe(&self) -> Receiver<DocumentEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for DocumentEvents {
+    fn default() -> DocumentEvents {
+        DocumentEvents::new()
+    }
+}
@@ -19,9 +19,14 @@ use tokio_util::compat::{FuturesAsyncReadCompatExt, FuturesAsyncWriteCompatExt};
 
 use crate::{
     asset::{detect_asset_type_by_ext, GetAssets, ReplaceAsset},
+    audit::record_event,
+    events::{DocumentEvent, DocumentEvents},
     helpers::remove_symbols_and_extra_spaces,
-    model::{thing, Branch, Commit, Project, Session, Snapshot, Tab, User},
+    model::{
+        thing, AuditAction, AuditEvent, Branch, Commit, Project, Session, Snapshot, Tab, User,
+    },
     oxd::OxdXml,
+    projects::save_with_unique_slug,
     storage::{Storage, StorageId},
     DEFAULT_BRANCH,
 };
@@ -54,6 +59,8 @@ pub enum CreateProjectUsingExistingFileError<SE: Debug + std::error::Error + Sen
 }
 
 /// Create a new project using an existing oxd file
+///
+/// The initial commit is published through `events`.
 pub async fn create_project_using_existing_file<
     D: Connection,
     SE: Debug + std::error::Error + Send + Sync,
@@ -63,6 +70,7 @@ pub async fn create_project_using_existing_file<
 >(
     db: Arc<Surreal<D>>,
     storage: Arc<S>,
+    events: &DocumentEvents,
     content: R,
     project_name: String,
     user_id: String,
@@ -144,20 +152,42 @@ pub async fn create_project_using_existing_file<
                 None,
                 created_snapshot.id.unwrap(),
             );
-            let _created_commit: Vec<Commit> = db.create(Commit::TABLE).content(commit).await?;
+            let mut created_commit: Vec<Commit> = db.create(Commit::TABLE).content(commit).await?;
+            let created_commit = created_commit.pop().unwrap();
 
             let file_name_without_sym_spc = remove_symbols_and_extra_spaces(project_name.clone());
-            let slug = file_name_without_sym_spc.to_lowercase().replace("", "-");
+            let owner = thing(User::TABLE, user_id);
 
             let project = Project::new(
                 thing(Project::TABLE, project_id),
                 String::from(file_name_without_sym_spc),
-                slug,
+                String::new(),
                 created_branch.id.unwrap(),
-                thing(User::TABLE, user_id),
+                owner,
             );
-            let mut created_project: Vec<Project> = db.create(Project::TABLE).content(project).await?;
-            let created_project = created_project.pop().unwrap();
+            let created_project = save_with_unique_slug(&db, project, |project| {
+                let db = db.clone();
+                async move {
+                    let mut created_project: Vec<Project> =
+                        db.create(Project::TABLE).content(project).await?;
+                    Ok(created_project.pop().unwrap())
+                }
+            })
+            .await?;
+
+            let commit_id = created_commit.id.unwrap();
+            record_event(
+                &db,
+                AuditEvent::new(AuditAction::CommitCreated, Some(created_commit.user))
+                    .target(commit_id.clone())
+                    .detail("project", created_project.id.as_ref().unwrap()),
+            )
+            .await;
+            events.publish(DocumentEvent::CommitCreated {
+                branch: created_commit.branch,
+                commit: commit_id,
+                message: created_commit.message,
+            });
 
             return Ok(created_project);
         }
@@ -1,9 +1,116 @@
+use deunicode::deunicode;
 use regex::Regex;
+use surrealdb::error::{Api, Db};
 
+/// Maximum count of characters in a slug. Suffixes for uniqueness are added after this
+pub const MAX_SLUG_LENGTH: usize = 60;
+
+/// Slugs that are used as path segments in the project URLs
+const RESERVED_SLUGS: [&str; 2] = ["members", "invitations"];
+
+/// Whether the DB rejected a write because of a `UNIQUE` index
+///
+/// Remote engines are sending only the message of the database error as a query error.
+pub fn is_unique_violation(error: &surrealdb::Error) -> bool {
+    match error {
+        surrealdb::Error::Db(Db::IndexExists { .. }) => true,
+        surrealdb::Error::Api(Api::Query(message)) => {
+            message.starts_with("Database index") && message.contains("already contains")
+        }
+        _ => false,
+    }
+}
+
+/// Removing the symbols other than dashes and underscores, and collapsing the spaces
 pub fn remove_symbols_and_extra_spaces(org: String) -> String {
-    let symbol_rgx = Regex::new("^[a-zA-Z0-9\\s]").unwrap();
-    let org_without_symbols = symbol_rgx.replace(&org, "");
+    let symbol_rgx = Regex::new("[^\\p{L}\\p{N}\\s_-]").unwrap();
+    let org_without_symbols = symbol_rgx.replace_all(&org, "");
     let multi_space_rgx = Regex::new("\\s+").unwrap();
 
-    multi_space_rgx.replace(&org_without_symbols, " ").to_string()
+    multi_space_rgx
+        .replace_all(&org_without_symbols, " ")
+        .trim()
+        .to_string()
+}
+
+/// URL friendly word for a name. Eg:- `Café Menu (v2)` => `cafe-menu-v2`
+///
+/// Unicode characters are transliterated to ASCII and any other characters are collapsed
+/// into a single dash. Returning an empty string when nothing left.
+pub fn slugify(name: &str) -> String {
+    let ascii = deunicode(name).to_lowercase();
+    let mut slug = String::with_capacity(ascii.len());
+    let mut separated = false;
+    for c in ascii.chars() {
+        if c.is_ascii_alphanumeric() {
+            if separated && !slug.is_empty() {
+                slug.push('-');
+            }
+            separated = false;
+            slug.push(c);
+        } else if c != '\'' {
+            // Apostrophes are removed without a separator. Eg:- `Don't` => `dont`
+            separated = true;
+        }
+    }
+
+    if slug.len() > MAX_SLUG_LENGTH {
+        slug.truncate(MAX_SLUG_LENGTH);
+        let trimmed_len = slug.trim_end_matches('-').len();
+        slug.truncate(trimmed_len);
+    }
+    slug
+}
+
+/// Slug for a project name before making it unique for the owner
+pub fn project_slug(name: &str) -> String {
+    let slug = slugify(name);
+    if slug.is_empty() {
+        String::from("project")
+    } else if RESERVED_SLUGS.contains(&slug.as_str()) {
+        format!("{}-project", slug)
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_symbols_and_extra_spaces() {
+        assert_eq!(
+            remove_symbols_and_extra_spaces(String::from("  My  Project!! (v2)\t")),
+            "My Project v2"
+        );
+        assert_eq!(
+            remove_symbols_and_extra_spaces(String::from("Café-Menu_draft")),
+            "Café-Menu_draft"
+        );
+    }
+
+    #[test]
+    fn slugifies_names() {
+        assert_eq!(slugify("My Project"), "my-project");
+        assert_eq!(slugify("  --My   Project!!--  "), "my-project");
+        assert_eq!(slugify("Café Menu (v2)"), "cafe-menu-v2");
+        assert_eq!(slugify("Straße"), "strasse");
+        assert_eq!(slugify("Don't Panic"), "dont-panic");
+        assert_eq!(slugify("!!!"), "");
+    }
+
+    #[test]
+    fn limits_slug_length() {
+        let slug = slugify(&"word ".repeat(30));
+        assert!(slug.len() <= MAX_SLUG_LENGTH);
+        assert!(!slug.ends_with('-'));
+    }
+
+    #[test]
+    fn project_slug_is_never_empty_or_reserved() {
+        assert_eq!(project_slug("???"), "project");
+        assert_eq!(project_slug("Members"), "members-project");
+        assert_eq!(project_slug("Landing Page"), "landing-page");
+    }
 }
@@ -1,25 +1,45 @@
+use access::{require_role, AccessError};
+use audit::record_event;
 use asset::{GetAssets, ReplaceAsset};
-use client::{Client, ClientTransport};
+use client::{Client, ClientTransport, ReportableError};
+use events::{DocumentEvent, DocumentEvents};
 use helpers::remove_symbols_and_extra_spaces;
+use live::{LiveSession, LiveSessions};
 use log::{warn, info};
-use oxd::OxdXml;
+use oxd::{screen_patches, OxdXml};
+use projects::save_with_unique_slug;
+use reaper::{remove_tab_with_data, RemoveTabError};
 use std::error::Error as StdError;
-use std::{collections::HashMap, fmt::Debug, marker::PhantomData, sync::Arc};
+use std::{collections::HashMap, fmt::Debug, marker::PhantomData, sync::Arc, time::Duration};
 use storage::{Storage, StorageId};
-use surrealdb::{sql::Id, Connection, Surreal};
-use transport::{ui::UIMessage, ReceiveError};
+use surrealdb::{sql::{Id, Thing}, Connection, Surreal};
+use async_trait::async_trait;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use transport::{
+    app::{ErrorCode, ErrorMessage, TabCreatedMessage},
+    rpc::{RpcHandler, RpcRequest},
+    ui::UIMessage,
+    ReceiveError, SendError,
+};
 
+pub mod access;
+pub mod audit;
 pub mod action;
 mod asset;
 mod client;
+pub mod events;
 pub mod external;
 pub mod helpers;
+mod live;
 pub mod model;
 pub mod oxd;
+pub mod projects;
+pub mod reaper;
 pub mod storage;
 
 use model::{
-    thing, Branch, Commit, Project, Session as SessionModel, Snapshot, Tab, TabAction, User,
+    thing, AuditAction, AuditEvent, Branch, Commit, Project, Role, Session as SessionModel,
+    Snapshot, Tab, TabAction, User,
 };
 
 pub static OXD_VERSION: &str = "0.0.1";
@@ -27,11 +47,33 @@ pub static DEFAULT_BRANCH: &str = "main";
 
 pub struct App<D: Connection> {
     db: Arc<Surreal<D>>,
+    events: DocumentEvents,
+    live: LiveSessions,
 }
 
 impl<D: Connection> App<D> {
     pub fn new(db: Arc<Surreal<D>>) -> App<D> {
-        App { db }
+        App {
+            db,
+            events: DocumentEvents::new(),
+            live: LiveSessions::default(),
+        }
+    }
+
+    /// Saving a new session of the user without connecting it
+    ///
+    /// Use `attach_session` to connect. Eg:- when the connection should be closed with a
+    /// reason on failures.
+    pub async fn new_session_data(
+        &self,
+        user_id: String,
+    ) -> Result<SessionModel, surrealdb::Error> {
+        let mut session_data: Vec<SessionModel> = self
+            .db
+            .create(SessionModel::TABLE)
+            .content(SessionModel::create(thing(User::TABLE, user_id)))
+            .await?;
+        Ok(session_data.pop().unwrap())
     }
 
     /// Creating a user session in editor
@@ -42,23 +84,68 @@ impl<D: Connection> App<D> {
         TE: Debug + Send,
         T: ClientTransport<TE>,
     >(
-        &mut self,
+        &self,
         user_id: String,
         internal_client: T,
         storage: Arc<S>,
     ) -> Result<Session<SE, SI, S, TE, T, D>, surrealdb::Error> {
-        let mut session_data: Vec<SessionModel> = self
-            .db
-            .create(SessionModel::TABLE)
-            .content(SessionModel::create(thing(User::TABLE, user_id.clone())))
-            .await?;
-        let session_data = session_data.pop().unwrap();
+        let session_data = self.new_session_data(user_id.clone()).await?;
+        let live = self
+            .live
+            .register(session_data.id.as_ref().unwrap())
+            .expect("New session is already live");
+        Ok(Session::new(
+            session_data,
+            internal_client,
+            user_id,
+            self.db.clone(),
+            storage,
+            self.events.clone(),
+            live,
+        ))
+    }
+
+    /// Finding an unclosed session of the user to reattach a new connection
+    pub async fn resumable_session(
+        &self,
+        session: Thing,
+        user_id: String,
+    ) -> Result<Option<SessionModel>, surrealdb::Error> {
+        let session_data: Option<SessionModel> = self.db.select(session).await?;
+        Ok(session_data.filter(|s| {
+            s.closed_at.is_none() && s.user == thing(User::TABLE, user_id.clone())
+        }))
+    }
+
+    /// Attaching a connection to an existing session
+    ///
+    /// Call `Session::replay_tabs` after attached to send the opened tabs to the UI. The
+    /// connection is returned back if the session is already attached to another connection.
+    pub fn attach_session<
+        SE: Debug + StdError,
+        SI: StorageId,
+        S: Storage<SE, SI>,
+        TE: Debug + Send,
+        T: ClientTransport<TE>,
+    >(
+        &self,
+        session_data: SessionModel,
+        internal_client: T,
+        storage: Arc<S>,
+    ) -> Result<Session<SE, SI, S, TE, T, D>, T> {
+        let live = match self.live.register(session_data.id.as_ref().unwrap()) {
+            Some(live) => live,
+            None => return Err(internal_client),
+        };
+        let user_id = session_data.user.id.to_raw();
         Ok(Session::new(
             session_data,
             internal_client,
-            user_id.clone(),
+            user_id,
             self.db.clone(),
             storage,
+            self.events.clone(),
+            live,
         ))
     }
 
@@ -66,6 +153,23 @@ impl<D: Connection> App<D> {
     pub fn database(&self) -> Arc<Surreal<D>> {
         self.db.clone()
     }
+
+    /// Publisher of the project changes that made outside of the sessions. Eg:- REST APIs
+    pub fn events(&self) -> DocumentEvents {
+        self.events.clone()
+    }
+
+    /// Closing the sessions without any activity for `idle_for` duration
+    ///
+    /// Sessions that are attached to a connection in this app are not closed. Returning the
+    /// count of closed sessions.
+    pub async fn reap_abandoned_sessions<SE: Debug + StdError, SI: StorageId, S: Storage<SE, SI>>(
+        &self,
+        storage: Arc<S>,
+        idle_for: Duration,
+    ) -> Result<usize, RemoveTabError<SE>> {
+        reaper::reap_abandoned_sessions(self.db.clone(), storage, idle_for, &self.live).await
+    }
 }
 
 /// A user session in a designer.
@@ -87,24 +191,29 @@ pub struct Session<
     data: SessionModel,
     user_id: String,
     storage: Arc<S>,
+    events: DocumentEvents,
+    events_receiver: Receiver<DocumentEvent>,
+    live: LiveSession,
     _phantom: PhantomData<(SE, SI)>,
 }
 
 impl<
-        SE: Debug + StdError,
+        SE: Debug + StdError + Send + Sync,
         SI: StorageId,
-        S: Storage<SE, SI>,
+        S: Storage<SE, SI> + Send + Sync,
         TE: Debug + Send,
         T: ClientTransport<TE>,
         D: Connection,
     > Session<SE, SI, S, TE, T, D>
 {
-    pub fn new(
+    pub(crate) fn new(
         data: SessionModel,
         internal_client: T,
         user_id: String,
         db: Arc<Surreal<D>>,
         storage: Arc<S>,
+        events: DocumentEvents,
+        live: LiveSession,
     ) -> Session<SE, SI, S, TE, T, D> {
         Session {
             client: Client::new(internal_client),
@@ -112,39 +221,388 @@ impl<
             user_id,
             db,
             storage,
+            events_receiver: events.subscribe(),
+            events,
+            live,
             _phantom: PhantomData,
         }
     }
 
+    /// Database id of the session
+    pub fn id(&self) -> Thing {
+        self.data.id.clone().unwrap()
+    }
+
+    /// Raw id of the user that owns the session
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Sending the ticket that UI should use to reattach after a connection drop
+    pub async fn send_resume_ticket(&mut self, ticket: String) -> Result<(), SessionSyncError> {
+        self.client.resume_ticket(ticket).await.map_err(|e| {
+            warn!("Failed to send the resume ticket:- {:?}", e);
+            SessionSyncError::Disconnected
+        })
+    }
+
+    /// Sending all the tabs opened in this session to the UI
+    pub async fn replay_tabs(&mut self) -> Result<(), SessionSyncError> {
+        let mut tabs_res = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE session = $session AND exited_at IS none ORDER BY created_at")
+            .bind(("table", Tab::TABLE))
+            .bind(("session", self.id()))
+            .await?;
+        let tabs: Vec<Tab> = tabs_res.take(0)?;
+
+        let zoom: f64 = 0.5;
+        let mut tab_messages = vec![];
+        for tab in tabs {
+            let snapshot: Option<Snapshot<SI>> = self.db.select(tab.snapshot).await?;
+            let screens = snapshot
+                .map(|snapshot| snapshot.oxd.vo_screens())
+                .unwrap_or_default();
+            tab_messages.push(TabCreatedMessage::new(
+                tab.name,
+                tab.id.unwrap().id.to_string(),
+                screens,
+                zoom,
+                tab.read_only,
+            ));
+        }
+        let current_tab = self.data.current_tab.clone().map(|t| t.id.to_string());
+
+        self.client
+            .session_resumed(tab_messages, current_tab)
+            .await
+            .map_err(|e| {
+                warn!("Failed to send the resumed tabs:- {:?}", e);
+                SessionSyncError::Disconnected
+            })
+    }
+
+    /// Whether the session is driven by another connection. Eg:- editor opened in two windows
+    fn is_live(&self, session: &SessionModel) -> bool {
+        self.live.sessions().contains(session.id.as_ref().unwrap())
+    }
+
+    /// Finding the last unclosed session of the user other than this session
+    ///
+    /// Sessions that are still attached to a connection are not previous sessions.
+    async fn previous_session(&self) -> Result<Option<SessionModel>, surrealdb::Error> {
+        let mut sessions_res = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE user = $user AND closed_at IS none AND id != $session ORDER BY last_activity DESC")
+            .bind(("table", SessionModel::TABLE))
+            .bind(("user", thing(User::TABLE, self.user_id.clone())))
+            .bind(("session", self.id()))
+            .await?;
+        let sessions: Vec<SessionModel> = sessions_res.take(0)?;
+        Ok(sessions.into_iter().find(|s| !self.is_live(s)))
+    }
+
+    /// Opened tabs of an another session
+    async fn tabs_of(&self, session: Thing) -> Result<Vec<Tab>, surrealdb::Error> {
+        let mut tabs_res = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE session = $session AND exited_at IS none ORDER BY created_at")
+            .bind(("table", Tab::TABLE))
+            .bind(("session", session))
+            .await?;
+        tabs_res.take(0)
+    }
+
+    /// Offering the user to restore the tabs of the previous session if the user
+    /// left the editor without closing the session.
+    ///
+    /// Previous sessions without any tab are closed silently.
+    pub async fn offer_previous_session(&mut self) -> Result<(), SessionSyncError> {
+        let previous = match self.previous_session().await? {
+            Some(previous) => previous,
+            None => return Ok(()),
+        };
+        let previous_id = previous.id.clone().unwrap();
+        let tabs = self.tabs_of(previous_id.clone()).await?;
+
+        if tabs.is_empty() {
+            let mut previous = previous;
+            previous.mark_closed();
+            let _: Option<SessionModel> = self.db.update(previous_id).content(previous).await?;
+            return Ok(());
+        }
+
+        let tab_ids: Vec<Thing> = tabs.iter().map(|t| t.id.clone().unwrap()).collect();
+        let mut actions_res = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE tab INSIDE $tabs")
+            .bind(("table", TabAction::TABLE))
+            .bind(("tabs", tab_ids))
+            .await?;
+        let actions: Vec<TabAction> = actions_res.take(0)?;
+
+        self.client
+            .previous_session_found(
+                previous_id.id.to_string(),
+                tabs.into_iter().map(|t| t.name).collect(),
+                actions.len(),
+            )
+            .await
+            .map_err(|e| {
+                warn!("Failed to offer the previous session:- {:?}", e);
+                SessionSyncError::Disconnected
+            })
+    }
+
+    /// Selecting a previous session that can be restored or discarded by this session
+    async fn previous_session_by_id(
+        &self,
+        session_id: String,
+    ) -> Result<SessionModel, RestoreSessionError> {
+        let previous: Option<SessionModel> = self
+            .db
+            .select(thing(SessionModel::TABLE, session_id))
+            .await?;
+        previous
+            .filter(|s| {
+                s.closed_at.is_none()
+                    && s.user == thing(User::TABLE, self.user_id.clone())
+                    && s.id != self.data.id
+                    && !self.is_live(s)
+            })
+            .ok_or(RestoreSessionError::SessionNotFound)
+    }
+
+    /// Moving the tabs of a previous session to this session
+    ///
+    /// Uncommitted actions are belongs to the tabs. So they are also restored.
+    pub async fn restore_session(&mut self, session_id: String) -> Result<(), RestoreSessionError> {
+        let mut previous = self.previous_session_by_id(session_id).await?;
+        let previous_id = previous.id.clone().unwrap();
+
+        for mut tab in self.tabs_of(previous_id.clone()).await? {
+            tab.session = self.id();
+            let _: Option<Tab> = self.db.update(tab.id.clone().unwrap()).content(tab).await?;
+        }
+
+        if self.data.current_tab.is_none() {
+            if let Some(current_tab) = previous.current_tab.clone() {
+                let mut updated_session = self.data.clone();
+                updated_session.set_current_tab(current_tab);
+                self.data = updated_session.clone();
+                let _: Option<SessionModel> = self
+                    .db
+                    .update(self.id())
+                    .content(updated_session)
+                    .await?;
+            }
+        }
+
+        previous.mark_closed();
+        let _: Option<SessionModel> = self.db.update(previous_id).content(previous).await?;
+
+        self.replay_tabs().await?;
+        Ok(())
+    }
+
+    /// Closing a previous session and removing its tabs
+    pub async fn discard_session(&mut self, session_id: String) -> Result<(), RestoreSessionError> {
+        let mut previous = self.previous_session_by_id(session_id).await?;
+        let previous_id = previous.id.clone().unwrap();
+
+        for tab in self.tabs_of(previous_id.clone()).await? {
+            self.remove_tab(tab.id.unwrap().id.to_string()).await;
+        }
+
+        previous.mark_closed();
+        let _: Option<SessionModel> = self.db.update(previous_id).content(previous).await?;
+        Ok(())
+    }
+
+    /// Waiting for the next message from the UI
+    ///
+    /// Document events published meanwhile are pushed to the UI before returning.
     pub async fn receive_message(&mut self) -> Result<UIMessage, ReceiveError> {
-        self.client.receive().await
+        loop {
+            tokio::select! {
+                message = self.client.receive() => return message,
+                event = self.events_receiver.recv() => match event {
+                    Ok(event) => self.push_event(event).await,
+                    Err(RecvError::Lagged(count)) => {
+                        warn!("Session {} missed {} document events", self.id(), count);
+                    }
+                    // Never closed since the session itself holding a sender
+                    Err(RecvError::Closed) => return self.client.receive().await,
+                },
+            }
+        }
+    }
+
+    /// Pushing a change of a project to the UI, if it is opened in this session
+    async fn push_event(&mut self, event: DocumentEvent) {
+        let result = match event {
+            DocumentEvent::ProjectRenamed { branch, name } => {
+                self.push_tab_renamed(branch, name).await
+            }
+            DocumentEvent::CommitCreated {
+                branch,
+                commit,
+                message,
+            } => self.push_commit_created(branch, commit, message).await,
+        };
+        if let Err(e) = result {
+            warn!("Failed to push the document event:- {:?}", e);
+        }
+    }
+
+    /// Opened tabs of this session on the branch
+    async fn tabs_on_branch(&self, branch: Thing) -> Result<Vec<Tab>, surrealdb::Error> {
+        let mut tabs_res = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE session = $session AND branch = $branch AND exited_at IS none ORDER BY created_at")
+            .bind(("table", Tab::TABLE))
+            .bind(("session", self.id()))
+            .bind(("branch", branch))
+            .await?;
+        tabs_res.take(0)
+    }
+
+    /// Tab names are already updated with the project. So only notifying the UI
+    async fn push_tab_renamed(
+        &mut self,
+        branch: Thing,
+        name: String,
+    ) -> Result<(), PushEventError<SE, TE>> {
+        for tab in self.tabs_on_branch(branch).await? {
+            self.client
+                .tab_renamed(tab.id.unwrap().id.to_string(), name.clone())
+                .await
+                .map_err(PushEventError::Send)?;
+        }
+        Ok(())
+    }
+
+    async fn push_commit_created(
+        &mut self,
+        branch: Thing,
+        commit: Thing,
+        message: String,
+    ) -> Result<(), PushEventError<SE, TE>> {
+        for tab in self.tabs_on_branch(branch).await? {
+            let tab_id = tab.id.clone().unwrap();
+            if tab.read_only && tab.head != commit {
+                self.follow_commit(tab, commit.clone()).await?;
+            }
+            self.client
+                .commit_created(tab_id.id.to_string(), commit.id.to_string(), message.clone())
+                .await
+                .map_err(PushEventError::Send)?;
+        }
+        Ok(())
+    }
+
+    /// Moving a read only tab to the commit and pushing the changed screens
+    ///
+    /// Viewers can not change their tabs. So those tabs are always showing the latest commit.
+    async fn follow_commit(
+        &mut self,
+        tab: Tab,
+        commit: Thing,
+    ) -> Result<(), PushEventError<SE, TE>> {
+        let commit_data: Option<Commit> = self.db.select(commit.clone()).await?;
+        let commit_data = match commit_data {
+            Some(commit_data) => commit_data,
+            None => return Ok(()),
+        };
+        let tab_id = tab.id.clone().unwrap();
+        let old_snapshot: Option<Snapshot<SI>> = self.db.select(tab.snapshot.clone()).await?;
+        let old_screens = old_snapshot
+            .map(|snapshot| snapshot.oxd.vo_screens())
+            .unwrap_or_default();
+
+        let new_snapshot = self.copy_snapshot(commit_data.snapshot).await?;
+        let new_screens = new_snapshot.oxd.vo_screens();
+        let mut updated_res = self
+            .db
+            .query("UPDATE $tab SET head = $head, snapshot = $snapshot")
+            .bind(("tab", tab_id.clone()))
+            .bind(("head", commit))
+            .bind(("snapshot", new_snapshot.id.unwrap()))
+            .await?;
+        let _updated: Vec<Tab> = updated_res.take(0)?;
+        self.remove_snapshot(tab.snapshot).await?;
+
+        let patches = screen_patches(&old_screens, &new_screens);
+        if !patches.is_empty() {
+            self.client
+                .screens_changed(tab_id.id.to_string(), patches)
+                .await
+                .map_err(PushEventError::Send)?;
+        }
+        Ok(())
+    }
+
+    /// Copying a snapshot with its assets to use in a tab
+    async fn copy_snapshot(&self, snapshot: Thing) -> Result<Snapshot<SI>, SnapshotError<SE>> {
+        let snapshot: Option<Snapshot<SI>> = self.db.select(snapshot).await?;
+        let oxd = snapshot.ok_or(SnapshotError::NotFound)?.oxd;
+        let mut replaced_assets: HashMap<SI, SI> = HashMap::new();
+        for asset in oxd.get_assets() {
+            let duplicated = self
+                .storage
+                .duplicate(asset.clone())
+                .await
+                .map_err(SnapshotError::Storage)?;
+            replaced_assets.insert(asset, duplicated);
+        }
+
+        let replaced_oxd = oxd.replace_asset(&mut replaced_assets);
+        let mut created_snapshot: Vec<Snapshot<SI>> = self
+            .db
+            .create(Snapshot::<SI>::TABLE)
+            .content(Snapshot::new(replaced_oxd))
+            .await?;
+        Ok(created_snapshot.pop().unwrap())
+    }
+
+    /// Removing a snapshot copied by `copy_snapshot` with its assets
+    async fn remove_snapshot(&self, snapshot: Thing) -> Result<(), SnapshotError<SE>> {
+        let snapshot: Option<Snapshot<SI>> = self.db.delete(snapshot).await?;
+        if let Some(snapshot) = snapshot {
+            for asset in snapshot.oxd.get_assets() {
+                self.storage
+                    .delete(asset)
+                    .await
+                    .map_err(SnapshotError::Storage)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Updating the last activity time of the session
+    ///
+    /// Only the activity field is updated. So a session that the reaper closed meanwhile is
+    /// not reopened by writing back the stale session data.
+    async fn touch(&mut self) -> Result<(), surrealdb::Error> {
+        self.data.touch();
+        let mut touched_res = self
+            .db
+            .query("UPDATE $session SET last_activity = time::now() WHERE closed_at IS none")
+            .bind(("session", self.id()))
+            .await?;
+        let _touched: Vec<SessionModel> = touched_res.take(0)?;
+        Ok(())
     }
 
     /// Starting the session
     pub async fn handle_message(&mut self, message: UIMessage) {
+        if let Err(e) = self.touch().await {
+            warn!("Failed to update the session activity:- {:?}", e);
+        }
         match message {
-            UIMessage::OpenFile(message) => match self.add_tab_with_project(message).await {
-                Ok(_) => {}
-                Err(e) => {
-                    warn!("Failed to add opened project as a tab:- {:?}", e);
-                    self.client.error(e).await.unwrap();
-                }
-            },
-            UIMessage::NewProject(message) => {
-                let project_created = self.create_project(message).await;
-                match project_created {
-                    Ok(project_id) => match self.add_tab_with_project(project_id).await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            warn!("Failed to add created project as a tab:- {:?}", e);
-                            self.client.error(e).await.unwrap();
-                        }
-                    },
-                    Err(err) => {
-                        warn!("Failed to create the project:- {:?}", err);
-                        self.client.error(err).await.unwrap();
-                    }
-                }
+            UIMessage::Rpc(request) => {
+                let response = self.dispatch(request).await;
+                self.client.respond(response).await.unwrap();
             }
             UIMessage::Ping => {
                 self.client.pong().await.unwrap();
@@ -152,9 +610,6 @@ impl<
             UIMessage::Resize(width, height) => {
                 self.resize(width, height);
             }
-            UIMessage::CloseTab(tab_id) => {
-                self.remove_tab(tab_id).await;
-            }
             _ => {}
         }
     }
@@ -184,36 +639,58 @@ impl<
             None,
             created_oxd.id.unwrap(),
         );
-        let _created_commit: Vec<Commit> = self.db.create(Commit::TABLE).content(commit).await?;
+        let mut created_commit: Vec<Commit> = self.db.create(Commit::TABLE).content(commit).await?;
+        let created_commit = created_commit.pop().unwrap();
 
         let file_name_without_sym_spc = remove_symbols_and_extra_spaces(project_name.clone());
-        let slug = file_name_without_sym_spc.to_lowercase().replace("", "-");
+        let owner = thing(User::TABLE, self.user_id.clone());
 
         let project = Project::new(
             thing(Project::TABLE, Id::rand()),
             String::from(file_name_without_sym_spc),
-            slug,
+            String::new(),
             created_branch.id.unwrap(),
-            thing(User::TABLE, self.user_id.clone()),
+            owner,
         );
-        let mut created_project: Vec<Project> = self.db.create(Project::TABLE).content(project).await?;
-        let created_project = created_project.pop().unwrap();
+        let db = &self.db;
+        let created_project = save_with_unique_slug(db, project, |project| async move {
+            let mut created_project: Vec<Project> =
+                db.create(Project::TABLE).content(project).await?;
+            Ok(created_project.pop().unwrap())
+        })
+        .await?;
+        let project_id = created_project.id.unwrap();
+
+        let commit_id = created_commit.id.unwrap();
+        record_event(
+            &self.db,
+            AuditEvent::new(AuditAction::CommitCreated, Some(created_commit.user))
+                .target(commit_id.clone())
+                .detail("project", &project_id),
+        )
+        .await;
+        self.events.publish(DocumentEvent::CommitCreated {
+            branch: created_commit.branch,
+            commit: commit_id,
+            message: created_commit.message,
+        });
 
-        Ok(created_project.id.unwrap().id.to_string())
+        Ok(project_id.id.to_string())
     }
 
     pub async fn add_tab_with_project(
         &mut self,
         project_id: String,
-    ) -> Result<(), AddTabError<SE>> {
-        let project: Option<Project> = self.db.select((Project::TABLE, project_id)).await?;
-
-        if let None = project {
-            return Err(AddTabError::ProjectNotFound);
-        }
-
-        let project = project.unwrap();
+    ) -> Result<TabCreatedMessage, AddTabError<SE>> {
+        // Users without a role should not know that the project exists
+        let (project, role) = require_role(&self.db, &project_id, &self.user_id, Role::Viewer)
+            .await
+            .map_err(|e| match e {
+                AccessError::Db(e) => AddTabError::Db(e),
+                _ => AddTabError::ProjectNotFound,
+            })?;
 
+        let project_thing = project.id.clone().unwrap();
         let default_branch: Option<Branch> = self.db.select(project.default_branch).await?;
         let default_branch = default_branch.unwrap();
 
@@ -224,29 +701,8 @@ impl<
         let commit: Option<Commit> = commit_res.take(0)?;
         let commit = commit.unwrap();
 
-        let snapshot: Option<Snapshot<SI>> = self.db.select(commit.snapshot).await?;
-        let mut snapshot = snapshot.unwrap();
-        snapshot.id = None;
-        let oxd = snapshot.oxd;
-        let assets = oxd.get_assets();
-        let mut replaced_assets: HashMap<SI, SI> = HashMap::new();
-        for asset in assets {
-            let duplicated = self
-                .storage
-                .duplicate(asset.clone())
-                .await
-                .map_err(AddTabError::Storage)?;
-            replaced_assets.insert(asset, duplicated);
-        }
-
-        let replaced_oxd = oxd.replace_asset(&mut replaced_assets);
-        let replaced_snapshot = Snapshot::new(replaced_oxd);
-        let mut created_snapshot: Vec<Snapshot<SI>> = self
-            .db
-            .create(Snapshot::<SI>::TABLE)
-            .content(replaced_snapshot)
-            .await?;
-        let created_snapshot = created_snapshot.pop().unwrap();
+        let created_snapshot = self.copy_snapshot(commit.snapshot).await?;
+        let screens = created_snapshot.oxd.vo_screens();
 
         let tab = Tab::new::<SI>(
             project.name,
@@ -254,10 +710,19 @@ impl<
             commit.id.unwrap(),
             default_branch.id.unwrap(),
             created_snapshot.id.unwrap(),
+            !role.can_edit(),
         );
         let mut created_tab: Vec<Tab> = self.db.create(Tab::TABLE).content(tab).await?;
         let created_tab = created_tab.pop().unwrap();
 
+        // Used to list the recently opened projects
+        let mut opened_res = self
+            .db
+            .query("UPDATE $project SET last_opened_at = time::now()")
+            .bind(("project", project_thing))
+            .await?;
+        let _opened: Vec<Project> = opened_res.take(0)?;
+
         let mut updated_session = self.data.clone();
         updated_session.set_current_tab(created_tab.id.clone().unwrap());
         self.data = updated_session.clone();
@@ -270,16 +735,72 @@ impl<
 
         let zoom: f64 = 0.5;
 
-        self.client
-            .tab_created(
-                created_tab.name,
-                created_tab.id.unwrap().id.to_string(),
-                zoom,
-                vec![],
-            )
-            .await
-            .unwrap();
+        Ok(TabCreatedMessage::new(
+            created_tab.name,
+            created_tab.id.unwrap().id.to_string(),
+            screens,
+            zoom,
+            created_tab.read_only,
+        ))
+    }
+
+    /// Committing the current snapshot of the tab to its branch
+    ///
+    /// Sessions that opened the same branch are notified through the document events. Their
+    /// read only tabs are moved to the new commit.
+    pub async fn commit_tab(
+        &mut self,
+        tab_id: String,
+        message: String,
+    ) -> Result<(), CommitTabError<SE>> {
+        let tab: Option<Tab> = self.db.select(thing(Tab::TABLE, &tab_id)).await?;
+        let tab = tab
+            .filter(|tab| tab.session == self.id() && tab.exited_at.is_none())
+            .ok_or(CommitTabError::TabNotFound)?;
+        if tab.read_only {
+            return Err(CommitTabError::ReadOnly);
+        }
+
+        // The tab keeps changing its own snapshot. So the commit needs a copy of it
+        let snapshot = self.copy_snapshot(tab.snapshot.clone()).await?;
+        let commit = Commit::new::<SI>(
+            message,
+            tab.branch.clone(),
+            thing(User::TABLE, self.user_id.clone()),
+            Some(tab.head.clone()),
+            snapshot.id.unwrap(),
+        );
+        let mut created_commit: Vec<Commit> = self.db.create(Commit::TABLE).content(commit).await?;
+        let created_commit = created_commit.pop().unwrap();
+        let commit_id = created_commit.id.unwrap();
+
+        // Actions of the tab are taken to the commit
+        let mut committed_res = self
+            .db
+            .query("UPDATE $tab SET head = $commit")
+            .query("UPDATE $branch SET head = $commit")
+            .query("DELETE type::table($action_table) WHERE tab = $tab")
+            .bind(("tab", tab.id.clone().unwrap()))
+            .bind(("branch", tab.branch.clone()))
+            .bind(("commit", commit_id.clone()))
+            .bind(("action_table", TabAction::TABLE))
+            .await?;
+        let _tabs: Vec<Tab> = committed_res.take(0)?;
+        let _branches: Vec<Branch> = committed_res.take(1)?;
+        let _actions: Vec<TabAction> = committed_res.take(2)?;
 
+        record_event(
+            &self.db,
+            AuditEvent::new(AuditAction::CommitCreated, Some(created_commit.user))
+                .target(commit_id.clone())
+                .detail("tab", tab.id.as_ref().unwrap()),
+        )
+        .await;
+        self.events.publish(DocumentEvent::CommitCreated {
+            branch: created_commit.branch,
+            commit: commit_id,
+            message: created_commit.message,
+        });
         Ok(())
     }
 
@@ -294,36 +815,134 @@ impl<
             .unwrap();
     }
 
+    /// Notifying the UI with the reason and closing the session
+    ///
+    /// Used when the session is closed by the server instead of the user. Eg:- after the
+    /// user signed out from all devices.
+    pub async fn close_with_reason(&mut self, reason: String) {
+        if let Err(e) = self.client.session_closing(reason).await {
+            warn!("Failed to notify the session closing:- {:?}", e);
+        }
+        self.close().await;
+    }
+
+    /// Notifying the UI with the reason and closing the connection
+    ///
+    /// The session is kept open. So the UI can reattach later with the resume ticket.
+    pub async fn disconnect(&mut self, reason: String) {
+        if let Err(e) = self.client.session_closing(reason).await {
+            warn!("Failed to notify the disconnection:- {:?}", e);
+        }
+        if let Err(e) = self.client.close().await {
+            warn!("Failed to close the connection:- {:?}", e);
+        }
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         self.data.change_size(width, height);
     }
 
     pub async fn remove_tab(&mut self, tab_id: String) {
-        let mut changes_res = self
-            .db
-            .query("SELECT * FROM type::table($tab_action_table) WHERE tab=type::thing($tab)")
-            .bind(("tab_action_table", TabAction::TABLE))
-            .bind(("tab", &tab_id))
-            .await
-            .unwrap();
-        let changes: Vec<TabAction> = changes_res.take(0).unwrap();
-
-        for change in changes {
-            // Remove added assets
-            let _deleted: Option<TabAction> = self.db.delete(change.id.unwrap()).await.unwrap();
+        let tab: Option<Tab> = self.db.select(thing(Tab::TABLE, &tab_id)).await.unwrap();
+        if let Some(tab) = tab {
+            if let Err(e) = remove_tab_with_data(&self.db, self.storage.as_ref(), tab).await {
+                warn!("Failed to remove the tab {}:- {:?}", &tab_id, e);
+                return;
+            }
         }
-
-        let _deleted_tab: Option<Tab> = self.db.delete(thing(Tab::TABLE, &tab_id)).await.unwrap();
         info!("Removed tab:- {}", &tab_id);
     }
 }
 
+#[async_trait]
+impl<
+        SE: Debug + StdError + Send + Sync,
+        SI: StorageId,
+        S: Storage<SE, SI> + Send + Sync,
+        TE: Debug + Send,
+        T: ClientTransport<TE>,
+        D: Connection,
+    > RpcHandler for Session<SE, SI, S, TE, T, D>
+{
+    async fn open_file(&mut self, project_id: String) -> Result<TabCreatedMessage, ErrorMessage> {
+        let request = RpcRequest::OpenFile {
+            project_id: project_id.clone(),
+        };
+        self.add_tab_with_project(project_id).await.map_err(|e| {
+            warn!("Failed to add opened project as a tab:- {:?}", e);
+            e.report_for(request)
+        })
+    }
+
+    async fn new_project(&mut self, project_name: String) -> Result<TabCreatedMessage, ErrorMessage> {
+        let request = RpcRequest::NewProject {
+            project_name: project_name.clone(),
+        };
+        let project_id = self.create_project(project_name).await.map_err(|e| {
+            warn!("Failed to create the project:- {:?}", e);
+            e.report_for(request.clone())
+        })?;
+        self.add_tab_with_project(project_id).await.map_err(|e| {
+            warn!("Failed to add created project as a tab:- {:?}", e);
+            e.report_for(request)
+        })
+    }
+
+    async fn close_tab(&mut self, tab_id: String) -> Result<(), ErrorMessage> {
+        self.remove_tab(tab_id).await;
+        Ok(())
+    }
+
+    async fn commit_tab(&mut self, tab_id: String, message: String) -> Result<(), ErrorMessage> {
+        let request = RpcRequest::CommitTab {
+            tab_id: tab_id.clone(),
+            message: message.clone(),
+        };
+        Session::commit_tab(self, tab_id, message).await.map_err(|e| {
+            warn!("Failed to commit the tab:- {:?}", e);
+            e.report_for(request)
+        })
+    }
+
+    async fn restore_session(&mut self, session_id: String) -> Result<(), ErrorMessage> {
+        let request = RpcRequest::RestoreSession {
+            session_id: session_id.clone(),
+        };
+        Session::restore_session(self, session_id).await.map_err(|e| {
+            warn!("Failed to restore the previous session:- {:?}", e);
+            e.report_for(request)
+        })
+    }
+
+    async fn discard_session(&mut self, session_id: String) -> Result<(), ErrorMessage> {
+        let request = RpcRequest::DiscardSession {
+            session_id: session_id.clone(),
+        };
+        Session::discard_session(self, session_id).await.map_err(|e| {
+            warn!("Failed to discard the previous session:- {:?}", e);
+            e.report_for(request)
+        })
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum CreateProjectError {
     #[error("could not read/write the data from database")]
     Db(#[from] surrealdb::Error),
 }
 
+impl ReportableError for CreateProjectError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            CreateProjectError::Db(_) => ErrorCode::Database,
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        true
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum AddTabError<SE: Debug> {
     #[error("could not read/write the data fromd database")]
@@ -335,3 +954,144 @@ pub enum AddTabError<SE: Debug> {
     #[error("asset upload/download error")]
     Storage(SE),
 }
+
+impl<SE: Debug> ReportableError for AddTabError<SE> {
+    fn code(&self) -> ErrorCode {
+        match self {
+            AddTabError::Db(_) => ErrorCode::Database,
+            AddTabError::ProjectNotFound => ErrorCode::ProjectNotFound,
+            AddTabError::Storage(_) => ErrorCode::Storage,
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            AddTabError::Db(_) | AddTabError::Storage(_) => true,
+            AddTabError::ProjectNotFound => false,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError<SE: Debug> {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+
+    #[error("snapshot not exists")]
+    NotFound,
+
+    #[error("asset upload/download error")]
+    Storage(SE),
+}
+
+impl<SE: Debug> From<SnapshotError<SE>> for AddTabError<SE> {
+    fn from(value: SnapshotError<SE>) -> Self {
+        match value {
+            SnapshotError::Db(e) => AddTabError::Db(e),
+            SnapshotError::NotFound => AddTabError::ProjectNotFound,
+            SnapshotError::Storage(e) => AddTabError::Storage(e),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CommitTabError<SE: Debug> {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+
+    #[error("tab not exists or not opened in the session")]
+    TabNotFound,
+
+    #[error("tab opened by a user who can not edit the project")]
+    ReadOnly,
+
+    #[error("asset upload/download error")]
+    Storage(SE),
+}
+
+impl<SE: Debug> From<SnapshotError<SE>> for CommitTabError<SE> {
+    fn from(value: SnapshotError<SE>) -> Self {
+        match value {
+            SnapshotError::Db(e) => CommitTabError::Db(e),
+            SnapshotError::NotFound => CommitTabError::TabNotFound,
+            SnapshotError::Storage(e) => CommitTabError::Storage(e),
+        }
+    }
+}
+
+impl<SE: Debug> ReportableError for CommitTabError<SE> {
+    fn code(&self) -> ErrorCode {
+        match self {
+            CommitTabError::Db(_) => ErrorCode::Database,
+            CommitTabError::TabNotFound => ErrorCode::TabNotFound,
+            CommitTabError::ReadOnly => ErrorCode::ReadOnlyTab,
+            CommitTabError::Storage(_) => ErrorCode::Storage,
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            CommitTabError::Db(_) | CommitTabError::Storage(_) => true,
+            CommitTabError::TabNotFound | CommitTabError::ReadOnly => false,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PushEventError<SE: Debug, TE: Debug> {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError<SE>),
+
+    #[error("could not send the message to the UI")]
+    Send(SendError<TE>),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionSyncError {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+
+    #[error("could not send the session to the UI")]
+    Disconnected,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestoreSessionError {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+
+    #[error("previous session not exists or already closed")]
+    SessionNotFound,
+
+    #[error("could not send the session to the UI")]
+    Disconnected,
+}
+
+impl From<SessionSyncError> for RestoreSessionError {
+    fn from(value: SessionSyncError) -> Self {
+        match value {
+            SessionSyncError::Db(e) => RestoreSessionError::Db(e),
+            SessionSyncError::Disconnected => RestoreSessionError::Disconnected,
+        }
+    }
+}
+
+impl ReportableError for RestoreSessionError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            RestoreSessionError::Db(_) => ErrorCode::Database,
+            RestoreSessionError::SessionNotFound => ErrorCode::SessionNotFound,
+            RestoreSessionError::Disconnected => ErrorCode::Internal,
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            RestoreSessionError::Db(_) | RestoreSessionError::Disconnected => true,
+            RestoreSessionError::SessionNotFound => false,
+        }
+    }
+}
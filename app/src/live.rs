@@ -0,0 +1,54 @@
+//! Sessions that are attached to a connection in this app
+//!
+//! A session can be reattached after its connection dropped. But only one connection
+//! should drive a session at a time.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use surrealdb::sql::Thing;
+
+#[derive(Clone, Default)]
+pub(crate) struct LiveSessions {
+    sessions: Arc<Mutex<HashSet<String>>>,
+}
+
+/// A session marked as live. Unmarked when dropped
+pub(crate) struct LiveSession {
+    sessions: LiveSessions,
+    session: String,
+}
+
+impl LiveSessions {
+    /// Marking the session as live. Returning `None` if it is already live
+    pub(crate) fn register(&self, session: &Thing) -> Option<LiveSession> {
+        let session = session.to_string();
+        if !self.sessions.lock().unwrap().insert(session.clone()) {
+            return None;
+        }
+        Some(LiveSession {
+            sessions: self.clone(),
+            session,
+        })
+    }
+
+    /// Whether the session is attached to a connection in this app
+    pub(crate) fn contains(&self, session: &Thing) -> bool {
+        self.sessions.lock().unwrap().contains(&session.to_string())
+    }
+}
+
+impl LiveSession {
+    /// Registry that this session is marked in
+    pub(crate) fn sessions(&self) -> &LiveSessions {
+        &self.sessions
+    }
+}
+
+impl Drop for LiveSession {
+    fn drop(&mut self) {
+        self.sessions.sessions.lock().unwrap().remove(&self.session);
+    }
+}
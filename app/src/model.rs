@@ -1,5 +1,8 @@
 //! Data structures that using to save data in database
 
+use std::collections::BTreeMap;
+
+pub use rest::{audit::AuditAction, projects::Role};
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::{Datetime, Id, Thing};
 
@@ -62,6 +65,11 @@ impl Session {
         self.current_tab = Some(tab);
     }
 
+    /// Recording that the user did something in this session
+    pub fn touch(&mut self) {
+        self.last_activity = Datetime::default();
+    }
+
     pub fn mark_closed(&mut self) {
         self.closed_at = Some(Datetime::default());
     }
@@ -91,6 +99,9 @@ pub struct Tab {
     /// Current snapshot of the tab. This should be updated with the user
     /// actions
     pub snapshot: Thing,
+    /// Opened by a user who can not edit the project
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 impl Tab {
@@ -102,6 +113,7 @@ impl Tab {
         head: Thing,
         branch: Thing,
         snapshot: Thing,
+        read_only: bool,
     ) -> Tab {
         Tab {
             id: None,
@@ -112,6 +124,7 @@ impl Tab {
             head,
             branch,
             snapshot,
+            read_only,
         }
     }
 }
@@ -172,6 +185,9 @@ pub struct Project {
     pub default_branch: Thing,
     /// The user who owned the proejct
     pub owner: Thing,
+    /// Last time that the project opened as a tab
+    #[serde(default)]
+    pub last_opened_at: Option<Datetime>,
 }
 
 impl Project {
@@ -185,8 +201,115 @@ impl Project {
             created_at: Datetime::default(),
             default_branch: branch,
             owner: user,
+            last_opened_at: None,
+        }
+    }
+}
+
+/// A user that the project shared with
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectMember {
+    pub id: Option<Thing>,
+    pub project: Thing,
+    pub user: Thing,
+    pub role: Role,
+    pub created_at: Datetime,
+}
+
+impl ProjectMember {
+    pub const TABLE: &str = "projectmembers";
+
+    pub fn new(project: Thing, user: Thing, role: Role) -> ProjectMember {
+        ProjectMember {
+            id: None,
+            project,
+            user,
+            role,
+            created_at: Datetime::default(),
+        }
+    }
+}
+
+/// An invitation to join a project. The invited user becomes a member after accepting it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectInvitation {
+    pub id: Option<Thing>,
+    pub project: Thing,
+    /// Invited user
+    pub user: Thing,
+    pub role: Role,
+    pub invited_by: Thing,
+    pub created_at: Datetime,
+    pub responded_at: Option<Datetime>,
+    pub accepted: Option<bool>,
+}
+
+impl ProjectInvitation {
+    pub const TABLE: &str = "projectinvitations";
+
+    pub fn new(project: Thing, user: Thing, role: Role, invited_by: Thing) -> ProjectInvitation {
+        ProjectInvitation {
+            id: None,
+            project,
+            user,
+            role,
+            invited_by,
+            created_at: Datetime::default(),
+            responded_at: None,
+            accepted: None,
+        }
+    }
+
+    pub fn respond(&mut self, accepted: bool) {
+        self.responded_at = Some(Datetime::default());
+        self.accepted = Some(accepted);
+    }
+}
+
+/// Security and content related event. Only appended, never updated or removed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditEvent {
+    pub id: Option<Thing>,
+    pub action: AuditAction,
+    /// User who did the action. Not known for the failed logins
+    pub actor: Option<Thing>,
+    /// Record that the action is done on. Eg:- the project or the snapshot
+    pub target: Option<Thing>,
+    /// Address of the client if the action is done with a request
+    pub ip: Option<String>,
+    pub details: BTreeMap<String, String>,
+    pub created_at: Datetime,
+}
+
+impl AuditEvent {
+    pub const TABLE: &str = "auditevents";
+
+    pub fn new(action: AuditAction, actor: Option<Thing>) -> AuditEvent {
+        AuditEvent {
+            id: None,
+            action,
+            actor,
+            target: None,
+            ip: None,
+            details: BTreeMap::new(),
+            created_at: Datetime::default(),
         }
     }
+
+    pub fn target(mut self, target: Thing) -> AuditEvent {
+        self.target = Some(target);
+        self
+    }
+
+    pub fn ip(mut self, ip: String) -> AuditEvent {
+        self.ip = Some(ip);
+        self
+    }
+
+    pub fn detail<V: ToString>(mut self, key: &str, value: V) -> AuditEvent {
+        self.details.insert(key.to_string(), value.to_string());
+        self
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
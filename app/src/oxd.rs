@@ -3,6 +3,7 @@
 use std::marker::PhantomData;
 
 use serde::{Deserialize, Serialize};
+use transport::vo::{Point2D, Rect2D, Screen as ScreenVo, ScreenKind, ScreenPatch};
 
 use crate::{
     asset::{GetAssets, ReplaceAsset},
@@ -43,6 +44,17 @@ impl<A: StorageId, B: StorageId> ReplaceAsset<B> for OxdXml<A> {
     }
 }
 
+impl<A: StorageIdWithoutSerde> OxdXml<A> {
+    /// Screens in the format that the UI is rendering
+    pub fn vo_screens(&self) -> Vec<ScreenVo> {
+        self.screens
+            .iter()
+            .enumerate()
+            .map(|(index, screen)| screen.to_vo(index))
+            .collect()
+    }
+}
+
 impl<A: StorageId> GetAssets<A> for OxdXml<A> {
     fn get_assets(&self) -> Vec<A> {
         return vec![];
@@ -51,9 +63,41 @@ impl<A: StorageId> GetAssets<A> for OxdXml<A> {
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Screen<A: StorageIdWithoutSerde> {
+    #[serde(default)]
+    pub name: String,
+    /// Position and the size in the canvas
+    #[serde(default)]
+    pub x: u32,
+    #[serde(default)]
+    pub y: u32,
+    #[serde(default)]
+    pub width: u32,
+    #[serde(default)]
+    pub height: u32,
     _phantom: PhantomData<A>,
 }
 
+impl<A: StorageIdWithoutSerde> Screen<A> {
+    /// Screen to send to the UI. `index` is the position of the screen in the oxd
+    pub fn to_vo(&self, index: usize) -> ScreenVo {
+        ScreenVo {
+            kind: ScreenKind::Full,
+            rect: Rect2D {
+                min: Point2D {
+                    x: self.x,
+                    y: self.y,
+                },
+                max: Point2D {
+                    x: self.x.saturating_add(self.width),
+                    y: self.y.saturating_add(self.height),
+                },
+            },
+            name: self.name.clone(),
+            index,
+        }
+    }
+}
+
 impl<A: StorageId, B: StorageId> ReplaceAsset<B> for Screen<A> {
     type From = A;
 
@@ -61,11 +105,31 @@ impl<A: StorageId, B: StorageId> ReplaceAsset<B> for Screen<A> {
 
     fn replace_asset<'a>(self, assets: &'a mut std::collections::HashMap<A, B>) -> Self::Output {
         Screen {
+            name: self.name,
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
             _phantom: PhantomData
         }
     }
 }
 
+/// Changes that turn the `old` screens to the `new` screens. Screens are matched by the index
+pub fn screen_patches(old: &[ScreenVo], new: &[ScreenVo]) -> Vec<ScreenPatch> {
+    let mut patches = vec![];
+    for index in 0..old.len().max(new.len()) {
+        match (old.get(index), new.get(index)) {
+            (Some(old_screen), Some(new_screen)) if old_screen != new_screen => {
+                patches.push(ScreenPatch::Updated(new_screen.clone()));
+            }
+            (None, Some(new_screen)) => patches.push(ScreenPatch::Added(new_screen.clone())),
+            (Some(_), None) => patches.push(ScreenPatch::Removed(index)),
+            _ => {}
+        }
+    }
+    patches
+}
 
 pub struct ScreenSize {
     model: String,
@@ -0,0 +1,335 @@
+//! Browsing and managing the projects that a user can access
+
+use std::{error::Error as StdError, fmt::Debug, future::Future};
+
+use serde::{Deserialize, Serialize};
+use surrealdb::{
+    sql::{Datetime, Thing},
+    Connection, Surreal,
+};
+
+use crate::{
+    access::{project_role, require_role, AccessError},
+    asset::GetAssets,
+    audit::record_event,
+    events::{DocumentEvent, DocumentEvents},
+    helpers::{is_unique_violation, project_slug, remove_symbols_and_extra_spaces},
+    model::{
+        thing, AuditAction, AuditEvent, Branch, Commit, Project, ProjectInvitation,
+        ProjectMember, Role, Snapshot, Tab, User,
+    },
+    storage::{Storage, StorageId},
+};
+
+/// Maximum count of projects in a page
+pub const MAX_PER_PAGE: usize = 100;
+
+/// Count of times to try the next slug when another project of the owner took the slug
+const SLUG_CONFLICT_RETRIES: usize = 5;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectSummary {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+    pub owner: String,
+    pub role: Role,
+    pub created_at: Datetime,
+    pub last_opened_at: Option<Datetime>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectPage {
+    pub projects: Vec<ProjectSummary>,
+    /// Count of all projects that matched the search
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+impl ProjectSummary {
+    pub fn new(project: Project, role: Role) -> ProjectSummary {
+        ProjectSummary {
+            id: project.id.unwrap().id.to_raw(),
+            name: project.name,
+            slug: project.slug,
+            owner: project.owner.id.to_raw(),
+            role,
+            created_at: project.created_at,
+            last_opened_at: project.last_opened_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Count {
+    count: usize,
+}
+
+/// Listing the projects that owned by or shared with the user. Recently opened projects first
+///
+/// `page` is starting from 1. `search` is matched with the name and the slug.
+pub async fn list_projects<D: Connection>(
+    db: &Surreal<D>,
+    user_id: &str,
+    search: Option<String>,
+    page: usize,
+    per_page: usize,
+) -> Result<ProjectPage, surrealdb::Error> {
+    let page = page.max(1);
+    let per_page = per_page.clamp(1, MAX_PER_PAGE);
+    let search = search
+        .map(|search| search.trim().to_lowercase())
+        .unwrap_or_default();
+
+    let condition = "(owner = $user OR id IN (SELECT VALUE project FROM type::table($member_table) WHERE user = $user)) AND ($search = '' OR string::lowercase(name) CONTAINS $search OR slug CONTAINS $search)";
+    let mut projects_res = db
+        .query(format!(
+            "SELECT * FROM type::table($table) WHERE {} ORDER BY last_opened_at DESC, created_at DESC LIMIT {} START {}",
+            condition,
+            per_page,
+            (page - 1) * per_page
+        ))
+        .query(format!(
+            "SELECT count() FROM type::table($table) WHERE {} GROUP ALL",
+            condition
+        ))
+        .bind(("table", Project::TABLE))
+        .bind(("member_table", ProjectMember::TABLE))
+        .bind(("user", thing(User::TABLE, user_id)))
+        .bind(("search", search))
+        .await?;
+    let projects: Vec<Project> = projects_res.take(0)?;
+    let count: Option<Count> = projects_res.take(1)?;
+
+    let mut summaries = vec![];
+    for project in projects {
+        let role = project_role(db, &project, user_id).await?.unwrap_or(Role::Viewer);
+        summaries.push(ProjectSummary::new(project, role));
+    }
+
+    Ok(ProjectPage {
+        projects: summaries,
+        total: count.map(|count| count.count).unwrap_or(0),
+        page,
+        per_page,
+    })
+}
+
+/// Slug for the project name that not used by the other projects of the owner
+///
+/// A numeric suffix is added on collisions. Eg:- `landing-page-2`. `project` is the project
+/// that the slug is generated for, so its current slug is not a collision.
+pub async fn unique_project_slug<D: Connection>(
+    db: &Surreal<D>,
+    owner: &Thing,
+    name: &str,
+    project: Option<Thing>,
+) -> Result<String, surrealdb::Error> {
+    let base = project_slug(name);
+    let mut slugs_res = db
+        .query("SELECT VALUE slug FROM type::table($table) WHERE owner = $owner AND (slug = $base OR string::startsWith(slug, $prefix)) AND id != $project")
+        .bind(("table", Project::TABLE))
+        .bind(("owner", owner.clone()))
+        .bind(("base", base.clone()))
+        .bind(("prefix", format!("{}-", base)))
+        .bind(("project", project))
+        .await?;
+    let taken: Vec<String> = slugs_res.take(0)?;
+
+    if !taken.contains(&base) {
+        return Ok(base);
+    }
+    let mut suffix: usize = 2;
+    loop {
+        let slug = format!("{}-{}", base, suffix);
+        if !taken.contains(&slug) {
+            return Ok(slug);
+        }
+        suffix += 1;
+    }
+}
+
+/// Making sure that a slug is used once for an owner even when two projects are saved at once
+pub async fn define_project_indexes<D: Connection>(
+    db: &Surreal<D>,
+) -> Result<(), surrealdb::Error> {
+    db.query(format!(
+        "DEFINE INDEX project_owner_slug ON TABLE {} COLUMNS owner, slug UNIQUE",
+        Project::TABLE
+    ))
+    .await?
+    .check()?;
+    Ok(())
+}
+
+/// Saving the project using `save` with a slug that not used by the other projects of the owner
+///
+/// When another project took the slug after it was generated, the unique index is rejecting
+/// the write. Then the slug is generated again, which is picking the next suffix.
+pub async fn save_with_unique_slug<D, F, Fut>(
+    db: &Surreal<D>,
+    mut project: Project,
+    save: F,
+) -> Result<Project, surrealdb::Error>
+where
+    D: Connection,
+    F: Fn(Project) -> Fut,
+    Fut: Future<Output = Result<Project, surrealdb::Error>>,
+{
+    let mut retries = 0;
+    loop {
+        project.slug =
+            unique_project_slug(db, &project.owner, &project.name, project.id.clone()).await?;
+        match save(project.clone()).await {
+            Err(e) if is_unique_violation(&e) && retries < SLUG_CONFLICT_RETRIES => retries += 1,
+            saved => return saved,
+        }
+    }
+}
+
+/// Finding a project by the owner and the slug. Eg:- `/api/projects/{owner}/{slug}`
+pub async fn find_project_by_slug<D: Connection>(
+    db: &Surreal<D>,
+    owner_id: &str,
+    slug: &str,
+    user_id: &str,
+) -> Result<ProjectSummary, AccessError> {
+    let mut projects_res = db
+        .query("SELECT * FROM type::table($table) WHERE owner = $owner AND slug = $slug LIMIT 1")
+        .bind(("table", Project::TABLE))
+        .bind(("owner", thing(User::TABLE, owner_id)))
+        .bind(("slug", slug.to_string()))
+        .await?;
+    let project: Option<Project> = projects_res.take(0)?;
+    let project = project.ok_or(AccessError::ProjectNotFound)?;
+    let role = project_role(db, &project, user_id)
+        .await?
+        .ok_or(AccessError::ProjectNotFound)?;
+    Ok(ProjectSummary::new(project, role))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenameProjectError {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+    #[error(transparent)]
+    Access(#[from] AccessError),
+    #[error("project name is required")]
+    NameNotProvided,
+}
+
+/// Renaming the project and regenerating the slug. Only the owner can rename
+///
+/// Opened tabs of the project are also renamed and the sessions are notified through `events`.
+pub async fn rename_project<D: Connection>(
+    db: &Surreal<D>,
+    events: &DocumentEvents,
+    project_id: &str,
+    user_id: &str,
+    name: String,
+) -> Result<Project, RenameProjectError> {
+    let name = remove_symbols_and_extra_spaces(name).trim().to_string();
+    if name.is_empty() {
+        return Err(RenameProjectError::NameNotProvided);
+    }
+    let (mut project, _) = require_role(db, project_id, user_id, Role::Owner).await?;
+
+    project.name = name;
+    let project = save_with_unique_slug(db, project, |project| async move {
+        let updated: Option<Project> = db
+            .update(project.id.clone().unwrap())
+            .content(project.clone())
+            .await?;
+        Ok(updated.unwrap_or(project))
+    })
+    .await?;
+
+    let mut tabs_res = db
+        .query("UPDATE type::table($table) SET name = $name WHERE branch = $branch AND exited_at IS none")
+        .bind(("table", Tab::TABLE))
+        .bind(("name", project.name.clone()))
+        .bind(("branch", project.default_branch.clone()))
+        .await?;
+    let _tabs: Vec<Tab> = tabs_res.take(0)?;
+    events.publish(DocumentEvent::ProjectRenamed {
+        branch: project.default_branch.clone(),
+        name: project.name.clone(),
+    });
+    Ok(project)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteProjectError<SE: Debug> {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+    #[error(transparent)]
+    Access(#[from] AccessError),
+    #[error("asset upload/download error")]
+    Storage(SE),
+}
+
+/// Removing the project with its branches, commits, snapshots and stored assets
+///
+/// Only the owner can delete. Tabs that already opened the project are keeping their own
+/// copies of the snapshot and assets, so those are removed with the sessions.
+pub async fn delete_project<
+    D: Connection,
+    SE: Debug + StdError,
+    SI: StorageId,
+    S: Storage<SE, SI>,
+>(
+    db: &Surreal<D>,
+    storage: &S,
+    project_id: &str,
+    user_id: &str,
+) -> Result<(), DeleteProjectError<SE>> {
+    let (project, _) = require_role(db, project_id, user_id, Role::Owner).await?;
+    let project_thing = project.id.clone().unwrap();
+
+    let mut commits_res = db
+        .query("SELECT * FROM type::table($table) WHERE branch = $branch")
+        .bind(("table", Commit::TABLE))
+        .bind(("branch", project.default_branch.clone()))
+        .await?;
+    let commits: Vec<Commit> = commits_res.take(0)?;
+
+    for commit in commits {
+        // Commits can share the same snapshot
+        let snapshot: Option<Snapshot<SI>> = db.delete(commit.snapshot).await?;
+        if let Some(snapshot) = snapshot {
+            for asset in snapshot.oxd.get_assets() {
+                storage
+                    .delete(asset)
+                    .await
+                    .map_err(DeleteProjectError::Storage)?;
+            }
+        }
+        let _deleted: Option<Commit> = db.delete(commit.id.unwrap()).await?;
+    }
+
+    let deleted_branch: Option<Branch> = db.delete(project.default_branch).await?;
+    if let Some(branch) = deleted_branch {
+        record_event(
+            db,
+            AuditEvent::new(AuditAction::BranchDeleted, Some(thing(User::TABLE, user_id)))
+                .target(branch.id.unwrap())
+                .detail("project", &project_thing)
+                .detail("name", branch.name),
+        )
+        .await;
+    }
+
+    let mut sharing_res = db
+        .query("DELETE type::table($member_table) WHERE project = $project")
+        .query("DELETE type::table($invitation_table) WHERE project = $project")
+        .bind(("member_table", ProjectMember::TABLE))
+        .bind(("invitation_table", ProjectInvitation::TABLE))
+        .bind(("project", project_thing.clone()))
+        .await?;
+    let _members: Vec<ProjectMember> = sharing_res.take(0)?;
+    let _invitations: Vec<ProjectInvitation> = sharing_res.take(1)?;
+
+    let _deleted_project: Option<Project> = db.delete(project_thing).await?;
+    Ok(())
+}
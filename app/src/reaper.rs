@@ -0,0 +1,104 @@
+//! Cleaning up the sessions that users abandoned
+//!
+//! Connections can drop without a close message. Those sessions are kept open to
+//! resume, but if nobody came back within a timeout we are closing them and
+//! removing the tabs with their duplicated assets.
+
+use std::{error::Error as StdError, fmt::Debug, sync::Arc, time::Duration};
+
+use log::info;
+use surrealdb::{sql::Duration as SurrealDuration, Connection, Surreal};
+
+use crate::{
+    asset::GetAssets,
+    live::LiveSessions,
+    model::{Session, Snapshot, Tab, TabAction},
+    storage::{Storage, StorageId},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RemoveTabError<SE: Debug> {
+    #[error("could not read/write the data from database")]
+    Db(#[from] surrealdb::Error),
+    #[error("asset upload/download error")]
+    Storage(SE),
+}
+
+/// Removing a tab with its actions, snapshot and the assets duplicated for the tab
+pub(crate) async fn remove_tab_with_data<
+    D: Connection,
+    SE: Debug + StdError,
+    SI: StorageId,
+    S: Storage<SE, SI>,
+>(
+    db: &Surreal<D>,
+    storage: &S,
+    tab: Tab,
+) -> Result<(), RemoveTabError<SE>> {
+    let tab_id = tab.id.clone().unwrap();
+    let mut changes_res = db
+        .query("SELECT * FROM type::table($tab_action_table) WHERE tab=$tab")
+        .bind(("tab_action_table", TabAction::TABLE))
+        .bind(("tab", tab_id.clone()))
+        .await?;
+    let changes: Vec<TabAction> = changes_res.take(0)?;
+
+    for change in changes {
+        // Remove added assets
+        let _deleted: Option<TabAction> = db.delete(change.id.unwrap()).await?;
+    }
+
+    let snapshot: Option<Snapshot<SI>> = db.delete(tab.snapshot).await?;
+    if let Some(snapshot) = snapshot {
+        for asset in snapshot.oxd.get_assets() {
+            storage.delete(asset).await.map_err(RemoveTabError::Storage)?;
+        }
+    }
+
+    let _deleted_tab: Option<Tab> = db.delete(tab_id).await?;
+    Ok(())
+}
+
+/// Closing the sessions without any activity for `idle_for` duration
+///
+/// Sessions in `live` are skipped even when their activity is late, since a connection is
+/// still driving them. Returning the count of closed sessions.
+pub(crate) async fn reap_abandoned_sessions<
+    D: Connection,
+    SE: Debug + StdError,
+    SI: StorageId,
+    S: Storage<SE, SI>,
+>(
+    db: Arc<Surreal<D>>,
+    storage: Arc<S>,
+    idle_for: Duration,
+    live: &LiveSessions,
+) -> Result<usize, RemoveTabError<SE>> {
+    let mut sessions_res = db
+        .query("SELECT * FROM type::table($table) WHERE closed_at IS none AND last_activity < time::now() - $idle_for")
+        .bind(("table", Session::TABLE))
+        .bind(("idle_for", SurrealDuration::from(idle_for)))
+        .await?;
+    let mut sessions: Vec<Session> = sessions_res.take(0)?;
+    sessions.retain(|session| !live.contains(session.id.as_ref().unwrap()));
+    let reaped = sessions.len();
+
+    for mut session in sessions {
+        let session_id = session.id.clone().unwrap();
+        let mut tabs_res = db
+            .query("SELECT * FROM type::table($table) WHERE session = $session")
+            .bind(("table", Tab::TABLE))
+            .bind(("session", session_id.clone()))
+            .await?;
+        let tabs: Vec<Tab> = tabs_res.take(0)?;
+        for tab in tabs {
+            remove_tab_with_data(&db, storage.as_ref(), tab).await?;
+        }
+
+        session.mark_closed();
+        let _: Option<Session> = db.update(session_id.clone()).content(session).await?;
+        info!("Closed abandoned session:- {}", session_id);
+    }
+
+    Ok(reaped)
+}
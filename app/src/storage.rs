@@ -3,6 +3,8 @@
 //! We can use S3 as production storage, File system as the staging storage,
 //! user's file system as the desktop storage.
 
+pub mod fs;
+
 use std::{fmt::Debug, hash::Hash};
 
 use async_trait::async_trait;
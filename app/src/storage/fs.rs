@@ -0,0 +1,139 @@
+//! File system as the storage
+//!
+//! Files are written to a temporary file next to the final path and renamed after synced to
+//! the disk. So a crash or a failed upload never leaves a partial file at a returned key.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use log::warn;
+use tokio::{
+    fs::{create_dir_all, metadata, remove_file, rename, File},
+    io::{copy, AsyncRead, AsyncWriteExt},
+};
+use uuid::Uuid;
+
+use super::{Storage, StorageObjInfo};
+
+pub struct FileSystemStorage {
+    root: PathBuf,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("failed to read or write some data")]
+    Io(#[from] io::Error),
+}
+
+/// File name with a new id and the same extension. Eg:- `<uuid>.png`
+fn new_file_name(ext: Option<&str>) -> String {
+    match ext {
+        Some(ext) => format!("{}.{}", Uuid::new_v4(), ext),
+        None => Uuid::new_v4().to_string(),
+    }
+}
+
+/// Syncing the directory entries, so a renamed file is not lost on a power failure
+///
+/// Directories can not be opened as files on Windows, and renames are already durable there.
+#[cfg(unix)]
+async fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir).await?.sync_all().await
+}
+
+#[cfg(not(unix))]
+async fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+async fn write_temp<I: AsyncRead + Unpin + Send>(reader: &mut I, temp: &Path) -> io::Result<()> {
+    let mut file = File::create(temp).await?;
+    copy(reader, &mut file).await?;
+    file.flush().await?;
+    file.sync_all().await
+}
+
+/// Writing the content to a new file at the path. Parent directories are created if missing
+async fn write_atomically<I: AsyncRead + Unpin + Send>(
+    reader: &mut I,
+    path: &Path,
+) -> io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    create_dir_all(dir).await?;
+
+    // Hidden and in the same directory, since a rename is only atomic in the same file system
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = dir.join(format!(".{}.tmp", file_name));
+    if let Err(e) = write_temp(reader, &temp).await {
+        if let Err(remove_e) = remove_file(&temp).await {
+            warn!(
+                "Failed to remove the temporary file {:?}:- {:?}",
+                temp, remove_e
+            );
+        }
+        return Err(e);
+    }
+    rename(&temp, path).await?;
+    sync_dir(dir).await
+}
+
+impl FileSystemStorage {
+    /// Storage that saves the files under the root directory. Created when the first file saved
+    pub fn new(root: PathBuf) -> FileSystemStorage {
+        FileSystemStorage { root }
+    }
+}
+
+#[async_trait]
+impl Storage<StorageError, PathBuf> for FileSystemStorage {
+    type Read = File;
+
+    /// Saving a file to storage
+    ///
+    /// Namespace is used as the directory of the file.
+    async fn put<'a, I: AsyncRead + Unpin + Send>(
+        &self,
+        reader: &'a mut I,
+        namespace: String,
+        ext: String,
+    ) -> Result<PathBuf, StorageError> {
+        let path = self.root.join(namespace).join(new_file_name(Some(&ext)));
+        write_atomically(reader, &path).await?;
+        Ok(path)
+    }
+
+    /// Retrieving the saved file from the storage
+    async fn get(&self, key: PathBuf) -> Result<Self::Read, StorageError> {
+        Ok(File::open(key).await?)
+    }
+
+    /// Removing a saved file
+    async fn delete(&self, key: PathBuf) -> Result<(), StorageError> {
+        Ok(remove_file(key).await?)
+    }
+
+    /// Retrieve the information of a storage object
+    async fn info(&self, key: PathBuf) -> Result<StorageObjInfo, StorageError> {
+        let size = metadata(&key).await?.len();
+        Ok(StorageObjInfo {
+            ext: key
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(String::from),
+            size,
+        })
+    }
+
+    /// Duplicating a storage object. The copy is saved next to the original
+    async fn duplicate(&self, key: PathBuf) -> Result<PathBuf, StorageError> {
+        let mut new_path = key.clone();
+        new_path.set_file_name(new_file_name(key.extension().and_then(|ext| ext.to_str())));
+
+        let mut file = File::open(&key).await?;
+        write_atomically(&mut file, &new_path).await?;
+        Ok(new_path)
+    }
+}
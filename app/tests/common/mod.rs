@@ -0,0 +1,105 @@
+//! Helpers shared by the integration tests
+
+use std::{io, pin::Pin, task::Poll};
+
+use app::storage::{Storage, StorageObjInfo};
+use async_trait::async_trait;
+use futures::{
+    channel::mpsc::{unbounded, SendError, UnboundedReceiver, UnboundedSender},
+    Sink, Stream,
+};
+use tokio::io::Empty;
+
+/// One side of an in-memory connection between the UI and a session
+pub struct Channel {
+    sender: UnboundedSender<Vec<u8>>,
+    receiver: UnboundedReceiver<Vec<u8>>,
+}
+
+pub fn channel() -> (Channel, Channel) {
+    let (sender1, receiver1) = unbounded();
+    let (sender2, receiver2) = unbounded();
+    (
+        Channel {
+            sender: sender1,
+            receiver: receiver2,
+        },
+        Channel {
+            sender: sender2,
+            receiver: receiver1,
+        },
+    )
+}
+
+impl Stream for Channel {
+    type Item = Vec<u8>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().receiver).poll_next(cx)
+    }
+}
+
+impl Sink<Vec<u8>> for Channel {
+    type Error = SendError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().sender).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Vec<u8>) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().sender).start_send(item)
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().sender).poll_flush(cx)
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().sender).poll_close(cx)
+    }
+}
+
+/// Storage for sessions that never touching any asset
+pub struct NoStorage;
+
+#[async_trait]
+impl Storage<io::Error, String> for NoStorage {
+    type Read = Empty;
+
+    async fn put<'a, I: tokio::io::AsyncRead + Unpin + Send>(
+        &self,
+        _file: &'a mut I,
+        _namespace: String,
+        _ext: String,
+    ) -> Result<String, io::Error> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    async fn get(&self, _key: String) -> Result<Empty, io::Error> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    async fn delete(&self, _key: String) -> Result<(), io::Error> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    async fn info(&self, _key: String) -> Result<StorageObjInfo, io::Error> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    async fn duplicate(&self, _key: String) -> Result<String, io::Error> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
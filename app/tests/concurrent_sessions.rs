@@ -0,0 +1,88 @@
+//! Sessions should not block each other. Running many sessions at the same time and
+//! checking that every one of them is answering.
+
+use std::{sync::Arc, time::Duration};
+
+use app::App;
+use common::{channel, Channel, NoStorage};
+use futures::{channel::mpsc::SendError, future::join_all};
+use surrealdb::{engine::local::Mem, Surreal};
+use tokio::time::timeout;
+use transport::{
+    app::{ApplicationMessage, PongMessage},
+    ui::UIMessage,
+    Client,
+};
+
+mod common;
+
+const SESSIONS: usize = 50;
+const PINGS_PER_SESSION: usize = 20;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_sessions_make_progress() {
+    let db = Arc::new(Surreal::new::<Mem>(()).await.unwrap());
+    db.use_ns("test").use_db("test").await.unwrap();
+    let app = Arc::new(App::new(db));
+    let storage = Arc::new(NoStorage);
+
+    let mut clients = vec![];
+    for i in 0..SESSIONS {
+        let (ui_side, app_side) = channel();
+        let app = app.clone();
+        let storage = storage.clone();
+        tokio::spawn(async move {
+            let mut session = app
+                .create_session(format!("user{}", i), app_side, storage)
+                .await
+                .unwrap();
+            while let Ok(message) = session.receive_message().await {
+                session.handle_message(message).await;
+            }
+        });
+
+        clients.push(tokio::spawn(async move {
+            let mut client: Client<ApplicationMessage, UIMessage, SendError, Channel> =
+                Client::new(ui_side);
+            for _ in 0..PINGS_PER_SESSION {
+                let _pong: PongMessage = client.send_and_receive(UIMessage::Ping).await.unwrap();
+            }
+        }));
+    }
+
+    let finished = timeout(Duration::from_secs(60), join_all(clients))
+        .await
+        .expect("Sessions are blocking each other");
+    for client in finished {
+        client.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn live_sessions_are_attached_once() {
+    let db = Arc::new(Surreal::new::<Mem>(()).await.unwrap());
+    db.use_ns("test").use_db("test").await.unwrap();
+    let app = App::new(db);
+
+    let (_ui_side, app_side) = channel();
+    let session = app
+        .create_session(String::from("designer"), app_side, Arc::new(NoStorage))
+        .await
+        .unwrap();
+    let session_data = app
+        .resumable_session(session.id(), String::from("designer"))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let (_ui_side, app_side) = channel();
+    assert!(app
+        .attach_session(session_data.clone(), app_side, Arc::new(NoStorage))
+        .is_err());
+
+    drop(session);
+    let (_ui_side, app_side) = channel();
+    assert!(app
+        .attach_session(session_data, app_side, Arc::new(NoStorage))
+        .is_ok());
+}
@@ -0,0 +1,134 @@
+//! Changes made to a project should reach the sessions that opened it
+
+use std::{sync::Arc, time::Duration};
+
+use app::{
+    model::{thing, Project, ProjectMember, Role, Tab, User},
+    projects::rename_project,
+    App,
+};
+use common::{channel, Channel, NoStorage};
+use futures::channel::mpsc::SendError;
+use surrealdb::{engine::local::Mem, Surreal};
+use tokio::time::timeout;
+use transport::{
+    app::{ApplicationMessage, CommitCreatedMessage, TabRenamedMessage},
+    ui::UIMessage,
+    Client,
+};
+
+mod common;
+
+#[tokio::test]
+async fn opened_tabs_are_notified() {
+    let db = Arc::new(Surreal::new::<Mem>(()).await.unwrap());
+    db.use_ns("test").use_db("test").await.unwrap();
+    let app = App::new(db.clone());
+
+    let (ui_side, app_side) = channel();
+    let mut session = app
+        .create_session(String::from("designer"), app_side, Arc::new(NoStorage))
+        .await
+        .unwrap();
+    let project_id = session
+        .create_project(String::from("Landing Page"))
+        .await
+        .unwrap();
+    let tab = session
+        .add_tab_with_project(project_id.clone())
+        .await
+        .unwrap();
+    tokio::spawn(async move {
+        while let Ok(message) = session.receive_message().await {
+            session.handle_message(message).await;
+        }
+    });
+
+    let mut client: Client<ApplicationMessage, UIMessage, SendError, Channel> =
+        Client::new(ui_side);
+    let committed: CommitCreatedMessage = timeout(Duration::from_secs(5), client.receive())
+        .await
+        .expect("Initial commit not pushed")
+        .unwrap();
+    assert_eq!(committed.tab_id, tab.tab_id);
+    assert_eq!(committed.message, "Initial Commit");
+
+    rename_project(
+        &db,
+        &app.events(),
+        &project_id,
+        "designer",
+        String::from("Home Page"),
+    )
+    .await
+    .unwrap();
+    let renamed: TabRenamedMessage = timeout(Duration::from_secs(5), client.receive())
+        .await
+        .expect("Renamed tab not pushed")
+        .unwrap();
+    assert_eq!(renamed.tab_id, tab.tab_id);
+    assert_eq!(renamed.tab_name, "Home Page");
+}
+
+#[tokio::test]
+async fn commits_reach_the_other_sessions_of_the_branch() {
+    let db = Arc::new(Surreal::new::<Mem>(()).await.unwrap());
+    db.use_ns("test").use_db("test").await.unwrap();
+    let app = App::new(db.clone());
+
+    let (_owner_ui_side, app_side) = channel();
+    let mut owner = app
+        .create_session(String::from("designer"), app_side, Arc::new(NoStorage))
+        .await
+        .unwrap();
+    let project_id = owner
+        .create_project(String::from("Landing Page"))
+        .await
+        .unwrap();
+    let owner_tab = owner
+        .add_tab_with_project(project_id.clone())
+        .await
+        .unwrap();
+
+    let _member: Vec<ProjectMember> = db
+        .create(ProjectMember::TABLE)
+        .content(ProjectMember::new(
+            thing(Project::TABLE, project_id.clone()),
+            thing(User::TABLE, "viewer"),
+            Role::Viewer,
+        ))
+        .await
+        .unwrap();
+    let (viewer_ui_side, app_side) = channel();
+    let mut viewer = app
+        .create_session(String::from("viewer"), app_side, Arc::new(NoStorage))
+        .await
+        .unwrap();
+    let viewer_tab = viewer.add_tab_with_project(project_id).await.unwrap();
+    assert!(viewer_tab.read_only);
+    tokio::spawn(async move {
+        while let Ok(message) = viewer.receive_message().await {
+            viewer.handle_message(message).await;
+        }
+    });
+
+    owner
+        .commit_tab(owner_tab.tab_id, String::from("Added a header"))
+        .await
+        .unwrap();
+    let mut client: Client<ApplicationMessage, UIMessage, SendError, Channel> =
+        Client::new(viewer_ui_side);
+    let committed: CommitCreatedMessage = timeout(Duration::from_secs(5), client.receive())
+        .await
+        .expect("Commit not pushed to the other session")
+        .unwrap();
+    assert_eq!(committed.tab_id, viewer_tab.tab_id);
+    assert_eq!(committed.message, "Added a header");
+
+    // Read only tabs are following the branch
+    let tab: Option<Tab> = db
+        .select((Tab::TABLE, viewer_tab.tab_id.as_str()))
+        .await
+        .unwrap();
+    assert_eq!(tab.unwrap().head.id.to_string(), committed.commit_id);
+}
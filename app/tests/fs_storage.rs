@@ -0,0 +1,125 @@
+//! Saving, reading, copying and removing the files of the file system storage
+
+use std::{
+    env::temp_dir,
+    fs::{read_dir, remove_dir_all},
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use app::storage::{
+    fs::{FileSystemStorage, StorageError},
+    Storage,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use uuid::Uuid;
+
+/// Root directory that is removed after the test
+struct TempRoot(PathBuf);
+
+impl TempRoot {
+    fn new() -> TempRoot {
+        TempRoot(temp_dir().join(format!("openxd-fs-storage-{}", Uuid::new_v4())))
+    }
+}
+
+impl Drop for TempRoot {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.0);
+    }
+}
+
+/// Reader that fails after giving some data. Eg:- an upload that the client cancelled
+struct FailingReader {
+    sent: bool,
+}
+
+impl AsyncRead for FailingReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if self.sent {
+            return Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "cancelled",
+            )));
+        }
+        self.sent = true;
+        buf.put_slice(b"partial");
+        Poll::Ready(Ok(()))
+    }
+}
+
+async fn read_all(storage: &FileSystemStorage, key: PathBuf) -> Vec<u8> {
+    let mut content = vec![];
+    storage
+        .get(key)
+        .await
+        .unwrap()
+        .read_to_end(&mut content)
+        .await
+        .unwrap();
+    content
+}
+
+#[tokio::test]
+async fn puts_files_in_new_namespace_directories() {
+    let root = TempRoot::new();
+    let storage = FileSystemStorage::new(root.0.clone());
+    let mut file: &[u8] = b"<svg/>";
+    let key = storage
+        .put(&mut file, String::from("assets"), String::from("svg"))
+        .await
+        .unwrap();
+
+    assert_eq!(key.parent().unwrap(), root.0.join("assets"));
+    assert_eq!(key.extension().unwrap(), "svg");
+    assert_eq!(read_all(&storage, key.clone()).await, b"<svg/>");
+
+    let info = storage.info(key).await.unwrap();
+    assert_eq!(info.ext.as_deref(), Some("svg"));
+    assert_eq!(info.size, 6);
+
+    // No temporary files are left next to the saved file
+    assert_eq!(read_dir(root.0.join("assets")).unwrap().count(), 1);
+}
+
+#[tokio::test]
+async fn leaves_nothing_when_reading_failed() {
+    let root = TempRoot::new();
+    let storage = FileSystemStorage::new(root.0.clone());
+    let result = storage
+        .put(
+            &mut FailingReader { sent: false },
+            String::from("assets"),
+            String::from("png"),
+        )
+        .await;
+
+    assert!(matches!(result, Err(StorageError::Io(_))));
+    assert_eq!(read_dir(root.0.join("assets")).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn duplicates_and_deletes_files() {
+    let root = TempRoot::new();
+    let storage = FileSystemStorage::new(root.0.clone());
+    let mut file: &[u8] = b"image";
+    let key = storage
+        .put(&mut file, String::from("assets"), String::from("png"))
+        .await
+        .unwrap();
+    let copy = storage.duplicate(key.clone()).await.unwrap();
+
+    assert_ne!(copy, key);
+    assert_eq!(copy.parent(), key.parent());
+    assert_eq!(copy.extension().unwrap(), "png");
+    assert_eq!(read_all(&storage, copy.clone()).await, b"image");
+
+    storage.delete(key.clone()).await.unwrap();
+    assert!(storage.get(key).await.is_err());
+    assert_eq!(read_all(&storage, copy).await, b"image");
+}
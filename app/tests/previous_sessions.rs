@@ -0,0 +1,63 @@
+//! A session that is still attached to a connection is not a previous session. Opening the
+//! editor in two windows should not offer, restore or discard the session of the other window.
+
+use std::sync::Arc;
+
+use app::{App, RestoreSessionError};
+use common::{channel, Channel, NoStorage};
+use futures::channel::mpsc::SendError;
+use surrealdb::{engine::local::Mem, Surreal};
+use transport::{
+    app::{ApplicationMessage, PreviousSessionFoundMessage},
+    ui::UIMessage,
+    Client,
+};
+
+mod common;
+
+#[tokio::test]
+async fn live_sessions_are_not_previous_sessions() {
+    let db = Arc::new(Surreal::new::<Mem>(()).await.unwrap());
+    db.use_ns("test").use_db("test").await.unwrap();
+    let app = App::new(db);
+
+    let (_first_ui_side, app_side) = channel();
+    let mut first = app
+        .create_session(String::from("designer"), app_side, Arc::new(NoStorage))
+        .await
+        .unwrap();
+    let project_id = first
+        .create_project(String::from("Landing Page"))
+        .await
+        .unwrap();
+    first.add_tab_with_project(project_id).await.unwrap();
+    let first_id = first.id();
+
+    let (second_ui_side, app_side) = channel();
+    let mut second = app
+        .create_session(String::from("designer"), app_side, Arc::new(NoStorage))
+        .await
+        .unwrap();
+    let mut client: Client<ApplicationMessage, UIMessage, SendError, Channel> =
+        Client::new(second_ui_side);
+
+    second.offer_previous_session().await.unwrap();
+    let found: Option<PreviousSessionFoundMessage> = client.try_receive().unwrap();
+    assert!(found.is_none(), "Live session offered as a previous session");
+
+    let restored = second.restore_session(first_id.id.to_raw()).await;
+    assert!(matches!(restored, Err(RestoreSessionError::SessionNotFound)));
+    let discarded = second.discard_session(first_id.id.to_raw()).await;
+    assert!(matches!(discarded, Err(RestoreSessionError::SessionNotFound)));
+    let first_data = app
+        .resumable_session(first_id.clone(), String::from("designer"))
+        .await
+        .unwrap();
+    assert!(first_data.is_some(), "Live session closed by another session");
+
+    // Connection of the first window dropped
+    drop(first);
+    second.offer_previous_session().await.unwrap();
+    let found: Option<PreviousSessionFoundMessage> = client.try_receive().unwrap();
+    assert_eq!(found.unwrap().session_id, first_id.id.to_string());
+}
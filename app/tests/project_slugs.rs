@@ -0,0 +1,104 @@
+//! Slugs are unique for an owner. When another project took the slug after it was generated,
+//! the project should be saved with the next suffix instead of a duplicate slug.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use app::{
+    helpers::is_unique_violation,
+    model::{thing, Branch, Project, User},
+    projects::{define_project_indexes, save_with_unique_slug},
+};
+use surrealdb::{engine::local::Mem, Surreal};
+
+fn landing_page(id: &str) -> Project {
+    Project::new(
+        thing(Project::TABLE, id),
+        String::from("Landing Page"),
+        String::new(),
+        thing(Branch::TABLE, id),
+        thing(User::TABLE, "designer"),
+    )
+}
+
+#[tokio::test]
+async fn conflicting_slug_takes_the_next_suffix() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    define_project_indexes(&db).await.unwrap();
+
+    let attempts = AtomicUsize::new(0);
+    let saved = save_with_unique_slug(&db, landing_page("second"), |project| {
+        let db = &db;
+        let attempts = &attempts;
+        async move {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                // Another project of the owner saved after the slug was generated
+                let mut first = landing_page("first");
+                first.slug = project.slug.clone();
+                let _first: Vec<Project> = db.create(Project::TABLE).content(first).await?;
+            }
+            let mut created: Vec<Project> = db.create(Project::TABLE).content(project).await?;
+            Ok(created.pop().unwrap())
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(saved.slug, "landing-page-2");
+}
+
+#[tokio::test]
+async fn unique_index_rejects_a_taken_slug() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    define_project_indexes(&db).await.unwrap();
+
+    let _first: Vec<Project> = db
+        .create(Project::TABLE)
+        .content(landing_page("first"))
+        .await
+        .unwrap();
+    let taken: Result<Vec<Project>, _> = db
+        .create(Project::TABLE)
+        .content(landing_page("second"))
+        .await;
+    assert!(is_unique_violation(&taken.err().unwrap()));
+
+    // Other conflicts are not a taken slug
+    let mut first = landing_page("first");
+    first.slug = String::from("another-slug");
+    let existing: Result<Option<Project>, _> =
+        db.create((Project::TABLE, "first")).content(first).await;
+    assert!(!is_unique_violation(&existing.err().unwrap()));
+}
+
+#[tokio::test]
+async fn other_errors_are_not_retried() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    define_project_indexes(&db).await.unwrap();
+    let _first: Option<Project> = db
+        .create((Project::TABLE, "first"))
+        .content(landing_page("first"))
+        .await
+        .unwrap();
+
+    let attempts = AtomicUsize::new(0);
+    let saved = save_with_unique_slug(&db, landing_page("first"), |project| {
+        let db = &db;
+        let attempts = &attempts;
+        async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            let created: Option<Project> = db
+                .create((Project::TABLE, "first"))
+                .content(project)
+                .await?;
+            Ok(created.unwrap())
+        }
+    })
+    .await;
+
+    assert!(saved.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
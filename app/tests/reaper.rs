@@ -0,0 +1,44 @@
+//! Sessions that nobody reattached are closed. But a session that is still attached to a
+//! connection should not lose its tabs, even when its activity was not updated in time.
+
+use std::{sync::Arc, time::Duration};
+
+use app::{model::Session, App};
+use common::{channel, NoStorage};
+use surrealdb::{engine::local::Mem, Surreal};
+
+mod common;
+
+#[tokio::test]
+async fn live_sessions_are_not_reaped() {
+    let db = Arc::new(Surreal::new::<Mem>(()).await.unwrap());
+    db.use_ns("test").use_db("test").await.unwrap();
+    let app = App::new(db.clone());
+
+    let (_ui_side, app_side) = channel();
+    let session = app
+        .create_session(String::from("designer"), app_side, Arc::new(NoStorage))
+        .await
+        .unwrap();
+    let mut idle_res = db
+        .query("UPDATE $session SET last_activity = time::now() - 1h")
+        .bind(("session", session.id()))
+        .await
+        .unwrap();
+    let _idle: Vec<Session> = idle_res.take(0).unwrap();
+
+    let idle_for = Duration::from_secs(60);
+    let reaped = app
+        .reap_abandoned_sessions(Arc::new(NoStorage), idle_for)
+        .await
+        .unwrap();
+    assert_eq!(reaped, 0, "Live session closed as abandoned");
+
+    // Connection dropped and nobody reattached
+    drop(session);
+    let reaped = app
+        .reap_abandoned_sessions(Arc::new(NoStorage), idle_for)
+        .await
+        .unwrap();
+    assert_eq!(reaped, 1);
+}
@@ -0,0 +1,82 @@
+//! User ids are not having a fixed length. Generated ids, email based ids and hand written
+//! ids should be able to connect and reattach to their sessions.
+
+use std::sync::Arc;
+
+use app::{
+    model::{thing, User},
+    App,
+};
+use common::{channel, Channel, NoStorage};
+use futures::channel::mpsc::SendError;
+use surrealdb::{
+    engine::local::{Db, Mem},
+    Surreal,
+};
+use transport::{
+    app::{ApplicationMessage, PongMessage},
+    ui::UIMessage,
+    Client,
+};
+
+mod common;
+
+/// Connecting as the user, then checking that the session can be reattached
+async fn connect_and_resume(app: &App<Db>, user_id: String) {
+    let (ui_side, app_side) = channel();
+    let mut session = app
+        .create_session(user_id.clone(), app_side, Arc::new(NoStorage))
+        .await
+        .unwrap();
+    let session_id = session.id();
+    let session_task = tokio::spawn(async move {
+        while let Ok(message) = session.receive_message().await {
+            session.handle_message(message).await;
+        }
+    });
+
+    let mut client: Client<ApplicationMessage, UIMessage, SendError, Channel> =
+        Client::new(ui_side);
+    let _pong: PongMessage = client.send_and_receive(UIMessage::Ping).await.unwrap();
+    drop(client);
+    session_task.await.unwrap();
+
+    let resumable = app
+        .resumable_session(session_id, user_id.clone())
+        .await
+        .unwrap()
+        .unwrap_or_else(|| panic!("Session of {} not resumable", user_id));
+    assert_eq!(resumable.user, thing(User::TABLE, user_id.clone()));
+
+    let (_ui_side, app_side) = channel();
+    let session = app
+        .attach_session(resumable, app_side, Arc::new(NoStorage))
+        .unwrap_or_else(|_| panic!("Session of {} is still attached", user_id));
+    assert_eq!(session.user_id(), user_id);
+}
+
+#[tokio::test]
+async fn users_with_any_id_length_can_connect() {
+    let db = Arc::new(Surreal::new::<Mem>(()).await.unwrap());
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let mut generated: Vec<User> = db
+        .create(User::TABLE)
+        .content(User::new(String::from("Generated User")))
+        .await
+        .unwrap();
+    let generated_id = generated.pop().unwrap().id.unwrap().id.to_raw();
+
+    let app = App::new(db);
+    let user_ids = [
+        String::from("a"),
+        String::from("designer"),
+        String::from("testusertestusertest"),
+        String::from("f47ac10b-58cc-4372-a567-0e02b2c3d479"),
+        String::from("designer.with.a.long.name@example.com"),
+        generated_id,
+    ];
+    for user_id in user_ids {
+        connect_and_resume(&app, user_id).await;
+    }
+}
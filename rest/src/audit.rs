@@ -0,0 +1,51 @@
+//! Audit events for the admins
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Security and content related actions
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Login,
+    LoginFailed,
+    /// A WebSocket connection opened with a ticket
+    TicketUsed,
+    ProjectUploaded,
+    SnapshotExported,
+    CommitCreated,
+    BranchDeleted,
+    ProjectDeleted,
+    MemberInvited,
+    InvitationAccepted,
+    InvitationDeclined,
+    MemberRoleChanged,
+    MemberRemoved,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuditEventResponse {
+    pub id: String,
+    pub action: AuditAction,
+    /// Id of the user who did the action. Not known for the failed logins
+    pub actor: Option<String>,
+    /// In the `table:id` form. Eg:- `projects:abc`
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub details: BTreeMap<String, String>,
+    /// RFC 3339 time
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuditPageResponse {
+    pub events: Vec<AuditEventResponse>,
+    /// Count of all events that matched the filters
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
@@ -0,0 +1,76 @@
+//! Accounts, tokens and the WebSocket tickets
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegisterRequest {
+    pub name: String,
+    pub email: String,
+    /// At least 8 characters
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegisterResponse {
+    pub user_id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LogoutRequest {
+    /// Refresh token to revoke with the access token of the request
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TokenResponse {
+    pub access_token: String,
+    /// Always `Bearer`
+    pub token_type: String,
+    /// Seconds until the access token expires
+    pub expires_in: u64,
+    /// Can be used once to get new tokens
+    pub refresh_token: String,
+}
+
+impl TokenResponse {
+    pub fn new(access_token: String, expires_in: u64, refresh_token: String) -> TokenResponse {
+        TokenResponse {
+            access_token,
+            token_type: String::from("Bearer"),
+            expires_in,
+            refresh_token,
+        }
+    }
+
+    /// Encoding as an URL fragment to redirect the browser with the tokens
+    pub fn to_fragment(&self) -> String {
+        format!(
+            "access_token={}&token_type={}&expires_in={}&refresh_token={}",
+            self.access_token, self.token_type, self.expires_in, self.refresh_token
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TicketResponse {
+    /// One-time ticket to open a WebSocket connection
+    pub ticket: String,
+}
@@ -0,0 +1,12 @@
+//! Requests and responses of the REST API
+//!
+//! Shared by the server and the clients, so both are agreeing on the JSON bodies and the
+//! paths. Schemas for the OpenAPI document are derived with the `openapi` feature. The
+//! document is served by the server at `/api/openapi.json`.
+
+pub mod audit;
+pub mod auth;
+pub mod paths;
+pub mod projects;
+pub mod sharing;
+pub mod system;
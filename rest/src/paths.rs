@@ -0,0 +1,61 @@
+//! Routes of the REST API. `:name` segments are the path parameters
+
+pub const REGISTER: &str = "/api/auth/register";
+pub const LOGIN: &str = "/api/auth/login";
+pub const REFRESH: &str = "/api/auth/refresh";
+pub const LOGOUT: &str = "/api/auth/logout";
+pub const LOGOUT_ALL: &str = "/api/auth/logout-all";
+pub const OIDC_LOGIN: &str = "/api/auth/oidc/:provider/login";
+pub const OIDC_CALLBACK: &str = "/api/auth/oidc/:provider/callback";
+pub const TICKET: &str = "/api/auth/ticket";
+pub const PROJECTS: &str = "/api/projects";
+pub const PROJECT: &str = "/api/projects/:projectId";
+pub const PROJECT_MEMBERS: &str = "/api/projects/:projectId/members";
+pub const PROJECT_MEMBER: &str = "/api/projects/:projectId/members/:userId";
+pub const PROJECT_INVITATIONS: &str = "/api/projects/:projectId/invitations";
+pub const PROJECT_BY_SLUG: &str = "/api/projects/:ownerId/:slug";
+pub const INVITATIONS: &str = "/api/invitations";
+pub const ACCEPT_INVITATION: &str = "/api/invitations/:invitationId/accept";
+pub const DECLINE_INVITATION: &str = "/api/invitations/:invitationId/decline";
+pub const AUDIT_EVENTS: &str = "/api/admin/audit-events";
+pub const CREATE_PROJECT: &str = "/api/create-project";
+pub const CURRENT_TAB_SNAPSHOT: &str = "/api/current-tab-snapshot";
+pub const SNAPSHOT: &str = "/api/snapshot/:downloadId";
+pub const OPENAPI: &str = "/api/openapi.json";
+pub const WEB_CONFIG: &str = "/config.json";
+pub const HEALTHZ: &str = "/healthz";
+pub const READYZ: &str = "/readyz";
+pub const METRICS: &str = "/metrics";
+
+/// Replacing the `:name` segments of the route with the values in the order
+///
+/// Eg:- `with_params(SNAPSHOT, &["abc"])` is `/api/snapshot/abc`
+pub fn with_params(route: &str, values: &[&str]) -> String {
+    let mut values = values.iter();
+    route
+        .split('/')
+        .map(|segment| {
+            if segment.starts_with(':') {
+                values.next().copied().unwrap_or(segment)
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_params_in_order() {
+        assert_eq!(with_params(SNAPSHOT, &["abc"]), "/api/snapshot/abc");
+        assert_eq!(
+            with_params(PROJECT_MEMBER, &["p1", "u1"]),
+            "/api/projects/p1/members/u1"
+        );
+        assert_eq!(with_params(PROJECTS, &[]), "/api/projects");
+    }
+}
@@ -0,0 +1,77 @@
+//! Projects and the snapshot downloads
+
+use serde::{Deserialize, Serialize};
+
+/// Access level of a user to a project. Ordered from the lowest to the highest
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Can open the project as a read-only tab
+    Viewer,
+    /// Viewer who can also comment
+    Commenter,
+    /// Can edit, commit and manage branches
+    Editor,
+    /// Can also share the project and change the roles. Only the project owner has this role
+    Owner,
+}
+
+impl Role {
+    pub fn can_edit(&self) -> bool {
+        *self >= Role::Editor
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProjectSummary {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+    /// Id of the user who owns the project
+    pub owner: String,
+    /// Role of the requested user
+    pub role: Role,
+    /// RFC 3339 time. Eg:- `2023-04-01T10:00:00Z`
+    pub created_at: String,
+    pub last_opened_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProjectPage {
+    pub projects: Vec<ProjectSummary>,
+    /// Count of all projects that matched the search
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RenameProjectRequest {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RenameProjectResponse {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+}
+
+/// Project created from an uploaded oxd file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OxdUploadSuccessResponse {
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CurrentTabSnapshotResponse {
+    /// One-time id to download the snapshot from `/api/snapshot/{downloadId}`
+    pub download_id: String,
+}
@@ -0,0 +1,38 @@
+//! Members and the invitations of the projects
+
+use serde::{Deserialize, Serialize};
+
+use crate::projects::Role;
+
+/// A user who can access the project
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProjectMemberResponse {
+    pub user_id: String,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InviteRequest {
+    pub user_id: String,
+    /// Any role other than `owner`
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChangeRoleRequest {
+    /// Any role other than `owner`
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InvitationResponse {
+    pub invitation_id: String,
+    pub project_id: String,
+    pub role: Role,
+    /// Id of the user who invited
+    pub invited_by: String,
+}
@@ -0,0 +1,22 @@
+//! Endpoints for the deployments
+
+use serde::{Deserialize, Serialize};
+
+/// Endpoints that the web frontend should use. Also the format of the `config.json` of the
+/// web bundle
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebConfig {
+    /// Base URL of the REST API. Eg:- `https://openxd.example.com`
+    pub api_url: String,
+    /// URL to open the WebSocket connections. Eg:- `wss://openxd.example.com/ws`
+    pub ws_url: String,
+}
+
+/// Status of each dependency. One of `ok`, `failed` or `timeout`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReadinessResponse {
+    pub db: String,
+    pub storage: String,
+}
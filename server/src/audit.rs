@@ -0,0 +1,127 @@
+//! Recording the audit events of the requests and querying them by the admins
+
+use std::collections::HashMap;
+
+use app::{
+    audit::{query_events, record_event, AuditFilter},
+    model::{thing, AuditAction, AuditEvent, User},
+};
+use hyper::{Body, Request, Response, StatusCode};
+use openidconnect::url::form_urlencoded;
+use rest::audit::{AuditEventResponse, AuditPageResponse};
+use routerify::prelude::RequestExt;
+use serde_json::{from_value, Value};
+use surrealdb::sql::Thing;
+
+use crate::{
+    auth::json_response, config::config, error::Error, get_db, rate_limit::client_ip,
+    storage::StorageError, UserId,
+};
+
+/// Events in a page when the `per_page` parameter not provided
+const DEFAULT_PER_PAGE: usize = 50;
+
+fn record_id(thing: Thing) -> String {
+    format!("{}:{}", thing.tb, thing.id.to_raw())
+}
+
+fn event_response(event: AuditEvent) -> AuditEventResponse {
+    AuditEventResponse {
+        id: event.id.unwrap().id.to_raw(),
+        action: event.action,
+        actor: event.actor.map(|actor| actor.id.to_raw()),
+        target: event.target.map(record_id),
+        ip: event.ip,
+        details: event.details,
+        created_at: event.created_at.to_raw(),
+    }
+}
+
+/// Event of an action that the user did with the request
+pub fn request_event(req: &Request<Body>, action: AuditAction, user_id: &str) -> AuditEvent {
+    AuditEvent::new(action, Some(thing(User::TABLE, user_id))).ip(client_ip(req).to_string())
+}
+
+/// Saving the event. Failures are only logged
+pub async fn record(event: AuditEvent) {
+    record_event(get_db().await, event).await;
+}
+
+/// Querying the audit events. Only the users in `ADMIN_USER_IDS` can access
+///
+/// Query parameters:- `actor`, `action` (Eg:- `snapshot_exported`), `target`
+/// (Eg:- `projects:abc`), `from`, `to` (RFC 3339 times), `page` and `per_page`.
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit-events",
+    tag = "admin",
+    params(
+        ("actor" = Option<String>, Query, description = "Id of the user who did the action"),
+        ("action" = Option<AuditAction>, Query, description = "Eg:- `snapshot_exported`"),
+        ("target" = Option<String>, Query, description = "Eg:- `projects:abc`"),
+        ("from" = Option<String>, Query, description = "RFC 3339 time. Inclusive"),
+        ("to" = Option<String>, Query, description = "RFC 3339 time. Exclusive"),
+        ("page" = Option<usize>, Query, description = "Starting from 1"),
+        ("per_page" = Option<usize>, Query, description = "50 by default. At most 200"),
+    ),
+    responses(
+        (status = 200, body = AuditPageResponse),
+        (status = 400, description = "VALIDATION ERROR"),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 403, description = "FORBIDDEN"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn audit_events_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    if !config().admin_user_ids.contains(&user_id.0) {
+        return Err(Error::AdminRequired);
+    }
+
+    let mut params: HashMap<String, String> =
+        form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+            .into_owned()
+            .collect();
+    let page = params
+        .get("page")
+        .and_then(|page| page.parse().ok())
+        .unwrap_or(1);
+    let per_page = params
+        .get("per_page")
+        .and_then(|per_page| per_page.parse().ok())
+        .unwrap_or(DEFAULT_PER_PAGE);
+    let action = match params.remove("action") {
+        Some(action) => Some(
+            from_value::<AuditAction>(Value::String(action.clone()))
+                .map_err(|_| Error::InvalidAuditFilter("action", action))?,
+        ),
+        None => None,
+    };
+    let target = match params.remove("target") {
+        Some(target) => match target.split_once(':') {
+            Some((table, id)) if !table.is_empty() && !id.is_empty() => Some(thing(table, id)),
+            _ => return Err(Error::InvalidAuditFilter("target", target)),
+        },
+        None => None,
+    };
+
+    let filter = AuditFilter {
+        actor: params.remove("actor"),
+        action,
+        target,
+        from: params.remove("from"),
+        to: params.remove("to"),
+    };
+    let events = query_events(get_db().await, filter, page, per_page).await?;
+    Ok(json_response(
+        StatusCode::OK,
+        &AuditPageResponse {
+            events: events.events.into_iter().map(event_response).collect(),
+            total: events.total,
+            page: events.page,
+            per_page: events.per_page,
+        },
+    ))
+}
@@ -0,0 +1,572 @@
+//! User accounts and access tokens
+//!
+//! Passwords are stored as Argon2 hashes. Login is issuing a short lived access JWT with an
+//! `exp` claim and a long lived refresh token. Access tokens are verified by the `api_auth`
+//! middleware against the revocation list and the WebSocket tickets are created using them.
+//!
+//! Refresh tokens are random strings stored as SHA-256 hashes. Each refresh token can be used
+//! once. Using an already rotated refresh token again is revoking all tokens of the user,
+//! because it means that someone else may have a copy of it.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use app::{
+    helpers::is_unique_violation,
+    model::{thing, AuditAction, AuditEvent, User},
+};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use hmac::{Hmac, Mac};
+use hyper::{body::to_bytes, Body, Request, Response, StatusCode};
+use jwt::{Claims, RegisteredClaims, SignWithKey, VerifyWithKey};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use routerify::prelude::RequestExt;
+use rest::auth::{
+    LoginRequest, LogoutRequest, RefreshRequest, RegisterRequest, RegisterResponse,
+    TicketResponse, TokenResponse,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{from_slice, ser::to_string};
+use sha2::{Digest, Sha256};
+use surrealdb::{sql::Thing, Connection, Surreal};
+use tokio::task::spawn_blocking;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    config::config,
+    error::{AccountError, AuthError, Error},
+    get_db, live,
+    model::{OidcState, RefreshToken, RevokedToken, Ticket, TokenRevocation, UserAccount},
+    rate_limit::client_ip,
+    shutdown,
+    storage::StorageError,
+    ticket::ticket_expiry,
+    UserId,
+};
+
+/// Private claim of the access tokens with the token generation of the user
+const GENERATION_CLAIM: &str = "gen";
+
+/// Minimum length of a password in characters
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Hash to verify the password against when the email has no password
+static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(b"not a password of any account", &salt)
+        .unwrap()
+        .to_string()
+});
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn access_token_ttl() -> u64 {
+    config().access_token_ttl
+}
+
+fn refresh_token_ttl() -> u64 {
+    config().refresh_token_ttl
+}
+
+fn jwt_key() -> Result<Hmac<Sha256>, AuthError> {
+    Ok(Hmac::new_from_slice(config().jwt_secret.as_bytes())?)
+}
+
+/// Claims of a verified access token
+#[derive(Clone, Debug)]
+pub struct AccessToken {
+    pub user_id: String,
+    /// `jti` claim
+    pub id: String,
+    pub issued_at: u64,
+    pub expiration: u64,
+    /// Token generation of the user when the token issued
+    pub generation: u64,
+}
+
+/// Current token generation of the user. Revoking all tokens is moving to the next one
+async fn token_generation(user_id: &str) -> Result<u64, surrealdb::Error> {
+    let revocation: Option<TokenRevocation> = get_db()
+        .await
+        .select((TokenRevocation::TABLE, user_id))
+        .await?;
+    Ok(revocation.map(|revocation| revocation.generation).unwrap_or(0))
+}
+
+/// Signing an access token for the user. Returning the token and the lifetime in seconds
+pub async fn issue_access_token(user_id: String) -> Result<(String, u64), Error<StorageError>> {
+    let generation = token_generation(&user_id).await?;
+    let ttl = access_token_ttl();
+    let now = now_secs();
+    let mut claims = Claims::new(RegisteredClaims {
+        subject: Some(user_id),
+        issued_at: Some(now),
+        expiration: Some(now + ttl),
+        json_web_token_id: Some(Uuid::new_v4().simple().to_string()),
+        ..Default::default()
+    });
+    claims
+        .private
+        .insert(String::from(GENERATION_CLAIM), generation.into());
+    let token = claims
+        .sign_with_key(&jwt_key()?)
+        .map_err(AuthError::from)?;
+    Ok((token, ttl))
+}
+
+/// Verifying the signature and the expiry of an access token
+///
+/// This is not checking the revocation list. Use `authenticate` to accept a request.
+pub fn verify_access_token(token: &str) -> Result<AccessToken, AuthError> {
+    let claims: Claims = token.verify_with_key(&jwt_key()?)?;
+    let generation = claims
+        .private
+        .get(GENERATION_CLAIM)
+        .and_then(|generation| generation.as_u64())
+        .unwrap_or(0);
+    let claims = claims.registered;
+    let expiration = match claims.expiration {
+        Some(expiration) if expiration > now_secs() => expiration,
+        _ => return Err(AuthError::Expired),
+    };
+    Ok(AccessToken {
+        user_id: claims.subject.ok_or(AuthError::SubjectNotProvided)?,
+        id: claims.json_web_token_id.ok_or(AuthError::TokenIdNotProvided)?,
+        issued_at: claims.issued_at.unwrap_or(0),
+        expiration,
+        generation,
+    })
+}
+
+/// Verifying an access token and checking that it was not revoked
+pub async fn authenticate(token: &str) -> Result<AccessToken, Error<StorageError>> {
+    let access_token = verify_access_token(token)?;
+    let db = get_db().await;
+
+    let revoked: Option<RevokedToken> = db
+        .select((RevokedToken::TABLE, access_token.id.as_str()))
+        .await?;
+    if revoked.is_some() {
+        return Err(AuthError::Revoked.into());
+    }
+
+    let revocation: Option<TokenRevocation> = db
+        .select((TokenRevocation::TABLE, access_token.user_id.as_str()))
+        .await?;
+    match revocation {
+        Some(revocation) if access_token.generation < revocation.generation => {
+            Err(AuthError::Revoked.into())
+        }
+        _ => Ok(access_token),
+    }
+}
+
+fn hash_refresh_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Generating a refresh token that not stored yet. Returning the token and the record id
+fn generate_refresh_token() -> (String, Thing) {
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let id = thing(RefreshToken::TABLE, hash_refresh_token(&token));
+    (token, id)
+}
+
+async fn store_refresh_token(id: Thing, user_id: String) -> Result<(), surrealdb::Error> {
+    let refresh_token = RefreshToken::new(
+        id.clone(),
+        thing(User::TABLE, user_id),
+        now_secs() + refresh_token_ttl(),
+    );
+    let _created: Option<RefreshToken> = get_db().await.create(id).content(refresh_token).await?;
+    Ok(())
+}
+
+/// Issuing an access token and a refresh token for the user
+pub(crate) async fn issue_tokens(user_id: String) -> Result<TokenResponse, Error<StorageError>> {
+    let (access_token, expires_in) = issue_access_token(user_id.clone()).await?;
+    let (refresh_token, refresh_token_id) = generate_refresh_token();
+    store_refresh_token(refresh_token_id, user_id).await?;
+    Ok(TokenResponse::new(access_token, expires_in, refresh_token))
+}
+
+/// Revoking all access tokens and refresh tokens of the user
+///
+/// Outstanding WebSocket tickets are removed and the live sessions of the user are closed.
+pub async fn revoke_all_tokens(user_id: String, reason: &str) -> Result<(), surrealdb::Error> {
+    let db = get_db().await;
+    let user = thing(User::TABLE, user_id.clone());
+
+    // Tokens issued in the same second are still revoked, unlike comparing the `iat` claim
+    let mut revocation_res = db
+        .query("UPDATE $revocation SET generation += 1")
+        .bind(("revocation", thing(TokenRevocation::TABLE, user_id.clone())))
+        .await?;
+    let _revocation: Vec<TokenRevocation> = revocation_res.take(0)?;
+
+    let mut revoked_res = db
+        .query("UPDATE type::table($table) SET revoked_at = time::now() WHERE user = $user AND revoked_at IS none")
+        .bind(("table", RefreshToken::TABLE))
+        .bind(("user", user.clone()))
+        .await?;
+    let _revoked: Vec<RefreshToken> = revoked_res.take(0)?;
+
+    let mut tickets_res = db
+        .query("DELETE type::table($table) WHERE user = $user")
+        .bind(("table", Ticket::TABLE))
+        .bind(("user", user))
+        .await?;
+    let _tickets: Vec<Ticket> = tickets_res.take(0)?;
+
+    let closed = live::close_user_sessions(&user_id, reason);
+    info!("Revoked all tokens of {}. Closed {} live session(s)", user_id, closed);
+    Ok(())
+}
+
+/// Removing the revoked access tokens, refresh tokens and login states that already expired
+pub async fn purge_expired_tokens() -> Result<(), surrealdb::Error> {
+    let db = get_db().await;
+    let mut purged_res = db
+        .query("DELETE type::table($revoked_table) WHERE expires_at < $now; DELETE type::table($refresh_table) WHERE expires_at < $now; DELETE type::table($oidc_state_table) WHERE expires_at < $now")
+        .bind(("revoked_table", RevokedToken::TABLE))
+        .bind(("refresh_table", RefreshToken::TABLE))
+        .bind(("oidc_state_table", OidcState::TABLE))
+        .bind(("now", now_secs()))
+        .await?;
+    let _revoked: Vec<RevokedToken> = purged_res.take(0)?;
+    let _refresh: Vec<RefreshToken> = purged_res.take(1)?;
+    let _states: Vec<OidcState> = purged_res.take(2)?;
+    Ok(())
+}
+
+pub(crate) async fn json_body<T: DeserializeOwned>(req: Request<Body>) -> Result<T, AccountError> {
+    let bytes = to_bytes(req.into_body()).await?;
+    Ok(from_slice(&bytes)?)
+}
+
+pub(crate) fn json_response<T: Serialize>(status: StatusCode, content: &T) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(to_string(content).unwrap()))
+        .unwrap()
+}
+
+pub(crate) async fn find_account_by_email<C: Connection>(
+    db: &Surreal<C>,
+    email: &str,
+) -> Result<Option<UserAccount>, surrealdb::Error> {
+    let mut accounts = db
+        .query("SELECT * FROM type::table($table) WHERE email = $email LIMIT 1")
+        .bind(("table", UserAccount::TABLE))
+        .bind(("email", email))
+        .await?;
+    accounts.take(0)
+}
+
+/// Making sure that an email is registered once even when two registrations are racing
+pub async fn define_account_indexes() -> Result<(), surrealdb::Error> {
+    get_db()
+        .await
+        .query(format!(
+            "DEFINE INDEX account_email ON TABLE {} COLUMNS email UNIQUE",
+            UserAccount::TABLE
+        ))
+        .await?
+        .check()?;
+    Ok(())
+}
+
+/// Creating a user account with an email and a password
+#[utoipa::path(
+    post,
+    path = "/api/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, body = RegisterResponse),
+        (status = 400, description = "VALIDATION ERROR"),
+        (status = 409, description = "EMAIL ALREADY REGISTERED"),
+    )
+)]
+pub async fn register_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let body: RegisterRequest = json_body(req).await?;
+    let name = body.name.trim().to_string();
+    let email = body.email.trim().to_lowercase();
+
+    if name.is_empty() {
+        return Err(AccountError::Validation("name is required").into());
+    }
+    if !email.contains('@') {
+        return Err(AccountError::Validation("email is not valid").into());
+    }
+    if body.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AccountError::Validation("password is too short").into());
+    }
+    if find_account_by_email(get_db().await, &email).await?.is_some() {
+        return Err(AccountError::EmailTaken.into());
+    }
+
+    let password = body.password;
+    let password_hash = spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await
+    .map_err(AccountError::from)?
+    .map_err(AccountError::from)?;
+
+    let account = UserAccount::new(name, email, password_hash);
+    let created: Result<Vec<UserAccount>, surrealdb::Error> = get_db()
+        .await
+        .create(UserAccount::TABLE)
+        .content(account)
+        .await;
+    let mut created = match created {
+        Ok(created) => created,
+        // Another registration of the same email finished after the check above
+        Err(e) if is_unique_violation(&e) => return Err(AccountError::EmailTaken.into()),
+        Err(e) => return Err(e.into()),
+    };
+    let created = created.pop().unwrap();
+
+    Ok(json_response(
+        StatusCode::CREATED,
+        &RegisterResponse {
+            user_id: created.id.unwrap().id.to_raw(),
+        },
+    ))
+}
+
+/// Finding the account of the email and verifying the password
+async fn verify_password(
+    email: &str,
+    password: String,
+) -> Result<UserAccount, Error<StorageError>> {
+    let account = find_account_by_email(get_db().await, email).await?;
+    let password_hash = account
+        .as_ref()
+        .and_then(|account| account.password_hash.clone());
+
+    let verified = spawn_blocking(move || {
+        // Unknown emails are taking the same time to verify as the registered ones
+        let (password_hash, known) = match password_hash {
+            Some(password_hash) => (password_hash, true),
+            None => (DUMMY_PASSWORD_HASH.clone(), false),
+        };
+        let matched = PasswordHash::new(&password_hash)
+            .map(|hash| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &hash)
+                    .is_ok()
+            })
+            .unwrap_or(false);
+        known && matched
+    })
+    .await
+    .map_err(AccountError::from)?;
+
+    match account {
+        Some(account) if verified => Ok(account),
+        _ => Err(AuthError::InvalidCredentials.into()),
+    }
+}
+
+/// Verifying the password and issuing an access token
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, body = TokenResponse),
+        (status = 401, description = "UNAUTHORIZED"),
+    )
+)]
+pub async fn login_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let ip = client_ip(&req).to_string();
+    let body: LoginRequest = json_body(req).await?;
+    let email = body.email.trim().to_lowercase();
+
+    let account = match verify_password(&email, body.password).await {
+        Ok(account) => account,
+        Err(Error::Auth(AuthError::InvalidCredentials)) => {
+            audit::record(
+                AuditEvent::new(AuditAction::LoginFailed, None)
+                    .ip(ip)
+                    .detail("email", email),
+            )
+            .await;
+            return Err(AuthError::InvalidCredentials.into());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let user_id = account.id.unwrap().id.to_raw();
+    audit::record(
+        AuditEvent::new(AuditAction::Login, Some(thing(User::TABLE, user_id.clone())))
+            .ip(ip)
+            .detail("method", "password"),
+    )
+    .await;
+    let tokens = issue_tokens(user_id).await?;
+    Ok(json_response(StatusCode::OK, &tokens))
+}
+
+/// Exchanging a refresh token to a new access token and a new refresh token
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, body = TokenResponse),
+        (status = 401, description = "UNAUTHORIZED"),
+    )
+)]
+pub async fn refresh_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let body: RefreshRequest = json_body(req).await?;
+    let db = get_db().await;
+    let token_id = thing(RefreshToken::TABLE, hash_refresh_token(&body.refresh_token));
+
+    let stored: Option<RefreshToken> = db.select(token_id.clone()).await?;
+    let stored = stored.ok_or(AuthError::InvalidRefreshToken)?;
+    let user_id = stored.user.id.to_raw();
+
+    if stored.replaced_by.is_some() {
+        warn!("Rotated refresh token used again for {}. Revoking all tokens", user_id);
+        revoke_all_tokens(user_id, "Your account was signed out for security reasons.").await?;
+        return Err(AuthError::InvalidRefreshToken.into());
+    }
+    if stored.revoked_at.is_some() || stored.expires_at <= now_secs() {
+        return Err(AuthError::InvalidRefreshToken.into());
+    }
+
+    // Rotating only if nobody used the same token concurrently
+    let (refresh_token, new_token_id) = generate_refresh_token();
+    let mut rotated = db
+        .query("UPDATE $token SET revoked_at = time::now(), replaced_by = $replaced_by WHERE revoked_at IS none")
+        .bind(("token", token_id))
+        .bind(("replaced_by", new_token_id.clone()))
+        .await?;
+    let rotated: Vec<RefreshToken> = rotated.take(0)?;
+    if rotated.is_empty() {
+        return Err(AuthError::InvalidRefreshToken.into());
+    }
+    store_refresh_token(new_token_id, user_id.clone()).await?;
+
+    let (access_token, expires_in) = issue_access_token(user_id).await?;
+    Ok(json_response(
+        StatusCode::OK,
+        &TokenResponse::new(access_token, expires_in, refresh_token),
+    ))
+}
+
+/// Revoking the access token of the request and the given refresh token
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    request_body(content = Option<LogoutRequest>, description = "Optional"),
+    responses(
+        (status = 204),
+        (status = 401, description = "UNAUTHORIZED"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn logout_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let access_token = req.context::<AccessToken>().unwrap();
+    // Refresh token is optional. Accepting a request without a body
+    let bytes = to_bytes(req.into_body()).await.map_err(AccountError::from)?;
+    let body: LogoutRequest = if bytes.is_empty() {
+        LogoutRequest::default()
+    } else {
+        from_slice(&bytes).map_err(AccountError::from)?
+    };
+    let db = get_db().await;
+    let user = thing(User::TABLE, access_token.user_id.clone());
+
+    let revoked_id = thing(RevokedToken::TABLE, access_token.id);
+    let _revoked: Option<RevokedToken> = db
+        .update(revoked_id.clone())
+        .content(RevokedToken::new(revoked_id, user.clone(), access_token.expiration))
+        .await?;
+
+    if let Some(refresh_token) = body.refresh_token {
+        let mut revoked_res = db
+            .query("UPDATE $token SET revoked_at = time::now() WHERE user = $user AND revoked_at IS none")
+            .bind(("token", thing(RefreshToken::TABLE, hash_refresh_token(&refresh_token))))
+            .bind(("user", user))
+            .await?;
+        let _revoked: Vec<RefreshToken> = revoked_res.take(0)?;
+    }
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
+
+/// Revoking all tokens of the user and closing the sessions on every device
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout-all",
+    tag = "auth",
+    responses(
+        (status = 204),
+        (status = 401, description = "UNAUTHORIZED"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn logout_all_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    revoke_all_tokens(user_id.0, "You signed out from all devices.").await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
+
+/// Creating a one-time ticket to open a WebSocket connection
+#[utoipa::path(
+    post,
+    path = "/api/auth/ticket",
+    tag = "auth",
+    responses(
+        (status = 201, body = TicketResponse),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 503, description = "SERVER SHUTTING DOWN"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn ticket_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    if shutdown::is_draining() {
+        return Err(Error::ShuttingDown);
+    }
+    let user_id = req.context::<UserId>().unwrap();
+    let ticket = Ticket::new(thing(User::TABLE, user_id.0), ticket_expiry());
+    let mut created_ticket: Vec<Ticket> = get_db()
+        .await
+        .create(Ticket::TABLE)
+        .content(ticket)
+        .await?;
+
+    Ok(json_response(
+        StatusCode::CREATED,
+        &TicketResponse {
+            ticket: created_ticket.pop().unwrap().id.unwrap().id.to_string(),
+        },
+    ))
+}
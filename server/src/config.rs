@@ -1,16 +1,939 @@
-use dotenv_codegen::dotenv;
+//! Configuration of the server
+//!
+//! Loaded once at the startup. Values can be given in a TOML file at `OPENXD_CONFIG` using the
+//! lowercase names (Eg:- `jwt_secret = "..."`), and the environment variables are overriding
+//! them. A `.env` file in the working directory is loaded to the environment for development.
 
-pub const WS_HOST:&str = dotenv!("WS_HOST");
-pub const WS_PORT:&str = dotenv!("WS_PORT");
-pub const WS_PATH:&str = dotenv!("WS_PATH");
+use std::{
+    env::{self, VarError},
+    fmt::Display,
+    fs,
+    net::IpAddr,
+    path::PathBuf,
+    str::FromStr,
+};
 
-pub const DB_URL: &str = dotenv!("DB_URL");
-pub const DB_NAME: &str = dotenv!("DB_NAME");
-pub const DB_NAMESPACE: &str = dotenv!("DB_NAMESPACE");
-pub const DB_USER: &str = dotenv!("DB_USER");
-pub const DB_PASSWORD: &str = dotenv!("DB_PASSWORD");
+use hyper::{header::HeaderName, Method};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
 
-#[cfg(feature="storage-fs")]
-pub const STORAGE_FS_ROOT: &str = dotenv!("STORAGE_FS_ROOT");
+use crate::{cors::normalize_origin, rate_limit::MAX_REQUEST_COST};
 
-pub const JWT_SECRET: &str = dotenv!("JWT_SECRET");
+/// Environment variable with the path to the TOML config file
+pub const CONFIG_FILE_VAR: &str = "OPENXD_CONFIG";
+
+/// Minimum bytes of the secret to sign the access tokens
+const MIN_JWT_SECRET_LENGTH: usize = 32;
+
+/// Heartbeat rounds that a live session should survive without looking abandoned
+const ABANDON_HEARTBEAT_ROUNDS: u64 = 2;
+
+/// Minimum MiB of a part that S3 is accepting in the multipart uploads
+#[cfg(feature = "storage-s3")]
+const MIN_S3_PART_SIZE: u32 = 5;
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("could not read the config file {path:?}. {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("config file {path:?} is not valid. {source}")]
+    File {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("could not load the .env file. {0}")]
+    DotEnv(#[from] dotenvy::Error),
+    #[error("{0} is not configured")]
+    Missing(&'static str),
+    #[error("{name} is not valid. {reason}")]
+    Invalid { name: &'static str, reason: String },
+}
+
+/// Values in the TOML config file. All are optional since the environment can provide them
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    ws_host: Option<IpAddr>,
+    ws_port: Option<u16>,
+    ws_path: Option<String>,
+    metrics_host: Option<IpAddr>,
+    metrics_port: Option<u16>,
+    public_api_url: Option<String>,
+    public_ws_url: Option<String>,
+    db_url: Option<String>,
+    db_name: Option<String>,
+    db_namespace: Option<String>,
+    db_user: Option<String>,
+    db_password: Option<String>,
+    #[cfg(feature = "storage-fs")]
+    storage_fs_root: Option<PathBuf>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_bucket: Option<String>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_prefix: Option<String>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_region: Option<String>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_endpoint: Option<String>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_path_style: Option<bool>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_access_key_id: Option<String>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_secret_access_key: Option<String>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_session_token: Option<String>,
+    #[cfg(feature = "storage-s3")]
+    storage_s3_part_size: Option<u32>,
+    jwt_secret: Option<String>,
+    access_token_ttl: Option<u64>,
+    refresh_token_ttl: Option<u64>,
+    oidc_providers_file: Option<PathBuf>,
+    oidc_callback_base_url: Option<String>,
+    ticket_ttl: Option<u64>,
+    heartbeat_interval: Option<u64>,
+    heartbeat_timeout: Option<u64>,
+    session_abandon_timeout: Option<u64>,
+    session_reap_interval: Option<u64>,
+    shutdown_timeout: Option<u64>,
+    rate_limit_ip_per_minute: Option<u32>,
+    rate_limit_ip_burst: Option<u32>,
+    rate_limit_user_per_minute: Option<u32>,
+    rate_limit_user_burst: Option<u32>,
+    rate_limit_trust_forwarded: Option<bool>,
+    ws_messages_per_second: Option<u32>,
+    ws_message_burst: Option<u32>,
+    cors_allowed_origins: Option<Vec<String>>,
+    cors_allowed_methods: Option<Vec<String>>,
+    cors_allowed_headers: Option<Vec<String>>,
+    admin_user_ids: Option<Vec<String>>,
+}
+
+/// Not implementing `Debug` to keep the secrets out of the logs
+pub struct Config {
+    pub ws_host: IpAddr,
+    pub ws_port: u16,
+    /// Path to accept the WebSocket connections
+    pub ws_path: String,
+    /// Address of the internal listener serving `/metrics`. Should not be public
+    pub metrics_host: IpAddr,
+    pub metrics_port: u16,
+    /// URL that the web frontend should use for the REST API
+    pub public_api_url: String,
+    /// URL that the web frontend should use to open the WebSocket connections
+    pub public_ws_url: String,
+    pub db_url: String,
+    pub db_name: String,
+    pub db_namespace: String,
+    pub db_user: String,
+    pub db_password: String,
+    #[cfg(feature = "storage-fs")]
+    pub storage_fs_root: PathBuf,
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_bucket: String,
+    /// Prepended to the keys of the objects. Eg:- `openxd/`
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_prefix: String,
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_region: String,
+    /// URL of the S3 API. Eg:- `http://localhost:9000` for MinIO. Defaults to the AWS endpoint
+    /// of the region
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_endpoint: String,
+    /// Sending the bucket in the path instead of the host name. Eg:- MinIO
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_path_style: bool,
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_access_key_id: String,
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_secret_access_key: String,
+    /// Only for the temporary credentials
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_session_token: Option<String>,
+    /// MiB in a part of the multipart uploads. Files smaller than this are uploaded at once
+    #[cfg(feature = "storage-s3")]
+    pub storage_s3_part_size: u32,
+    pub jwt_secret: String,
+    /// Seconds that an access token is valid after issued
+    pub access_token_ttl: u64,
+    /// Seconds that a refresh token can be used after issued
+    pub refresh_token_ttl: u64,
+    /// JSON file with the OpenID Connect providers. External login is disabled without it
+    pub oidc_providers_file: Option<PathBuf>,
+    /// Public URL of this server that the providers redirect back to
+    pub oidc_callback_base_url: String,
+    /// Seconds that a one-time WebSocket ticket can be used after created
+    pub ticket_ttl: u64,
+    /// Seconds without any message before pinging the client
+    pub heartbeat_interval: u64,
+    /// Seconds to wait for the answer of a ping before closing the connection
+    pub heartbeat_timeout: u64,
+    /// Seconds without any activity before closing a disconnected session
+    pub session_abandon_timeout: u64,
+    /// Seconds between two runs of the abandoned session cleanup
+    pub session_reap_interval: u64,
+    /// Seconds to wait for the sessions to close after a shutdown signal
+    pub shutdown_timeout: u64,
+    /// Requests a client IP can send in a minute. Expensive requests are counted as more
+    pub rate_limit_ip_per_minute: u32,
+    /// Requests a client IP can send at once
+    pub rate_limit_ip_burst: u32,
+    /// Requests a user can send in a minute from all addresses
+    pub rate_limit_user_per_minute: u32,
+    /// Requests a user can send at once
+    pub rate_limit_user_burst: u32,
+    /// Using the last `X-Forwarded-For` address as the client IP. Only enable behind a proxy
+    pub rate_limit_trust_forwarded: bool,
+    /// Messages a WebSocket session can send in a second
+    pub ws_messages_per_second: u32,
+    /// Messages a WebSocket session can send at once
+    pub ws_message_burst: u32,
+    /// Origins that can call the API and open the WebSockets from a browser. Eg:-
+    /// `https://openxd.example.com`. `*` is allowing any origin. Defaults to the origin of
+    /// `public_api_url`, for the web frontend served by the same host
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allowed_methods: Vec<String>,
+    pub cors_allowed_headers: Vec<String>,
+    /// Users who can query the audit events
+    pub admin_user_ids: Vec<String>,
+}
+
+/// Comma separated values in the environment. Eg:- `GET,POST`
+struct List(Vec<String>);
+
+impl FromStr for List {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(List(
+            value
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+        ))
+    }
+}
+
+fn list(
+    name: &'static str,
+    file_value: Option<Vec<String>>,
+    default: &[&str],
+) -> Result<Vec<String>, ConfigError> {
+    Ok(setting(name, file_value.map(List))?
+        .map(|list| list.0)
+        .unwrap_or_else(|| default.iter().map(|item| item.to_string()).collect()))
+}
+
+/// Allowed origins in the same form as the `Origin` header
+fn origins(
+    name: &'static str,
+    file_value: Option<Vec<String>>,
+    default: &[&str],
+) -> Result<Vec<String>, ConfigError> {
+    list(name, file_value, default)?
+        .into_iter()
+        .map(|origin| {
+            if origin == "*" {
+                return Ok(origin);
+            }
+            normalize_origin(&origin).ok_or_else(|| ConfigError::Invalid {
+                name,
+                reason: format!("{} is not an origin. Eg:- https://openxd.example.com", origin),
+            })
+        })
+        .collect()
+}
+
+/// Returning the value of the environment variable, or the value in the config file
+///
+/// Empty environment variables are treated as not set.
+fn setting<T: FromStr>(name: &'static str, file_value: Option<T>) -> Result<Option<T>, ConfigError>
+where
+    T::Err: Display,
+{
+    match env::var(name) {
+        Ok(value) if value.trim().is_empty() => Ok(file_value),
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e: T::Err| ConfigError::Invalid {
+                name,
+                reason: e.to_string(),
+            }),
+        Err(VarError::NotPresent) => Ok(file_value),
+        Err(VarError::NotUnicode(_)) => Err(ConfigError::Invalid {
+            name,
+            reason: String::from("value is not valid unicode"),
+        }),
+    }
+}
+
+fn required<T: FromStr>(name: &'static str, file_value: Option<T>) -> Result<T, ConfigError>
+where
+    T::Err: Display,
+{
+    setting(name, file_value)?.ok_or(ConfigError::Missing(name))
+}
+
+/// Count that should be greater than zero
+fn limit(name: &'static str, file_value: Option<u32>, default: u32) -> Result<u32, ConfigError> {
+    let count = setting(name, file_value)?.unwrap_or(default);
+    if count == 0 {
+        return Err(ConfigError::Invalid {
+            name,
+            reason: String::from("should be greater than zero"),
+        });
+    }
+    Ok(count)
+}
+
+/// Seconds that should be greater than zero
+fn duration(name: &'static str, file_value: Option<u64>, default: u64) -> Result<u64, ConfigError> {
+    let seconds = setting(name, file_value)?.unwrap_or(default);
+    if seconds == 0 {
+        return Err(ConfigError::Invalid {
+            name,
+            reason: String::from("should be greater than zero"),
+        });
+    }
+    Ok(seconds)
+}
+
+impl Config {
+    /// Loading the configuration from the environment and the optional config file
+    pub fn load() -> Result<Config, ConfigError> {
+        match dotenvy::dotenv() {
+            Ok(_) => {}
+            Err(e) if e.not_found() => {}
+            Err(e) => return Err(e.into()),
+        }
+        Config::from_env()
+    }
+
+    /// Loading the configuration from the environment as it is, without the `.env` file
+    fn from_env() -> Result<Config, ConfigError> {
+        let file: FileConfig = match setting::<PathBuf>(CONFIG_FILE_VAR, None)? {
+            Some(path) => {
+                let content = fs::read_to_string(&path).map_err(|source| ConfigError::Read {
+                    path: path.clone(),
+                    source,
+                })?;
+                toml::from_str(&content).map_err(|source| ConfigError::File { path, source })?
+            }
+            None => FileConfig::default(),
+        };
+
+        let ws_host = setting("WS_HOST", file.ws_host)?.unwrap_or(IpAddr::from([127, 0, 0, 1]));
+        let ws_port = setting("WS_PORT", file.ws_port)?.unwrap_or(8000);
+        let ws_path = setting("WS_PATH", file.ws_path)?.unwrap_or_else(|| String::from("/ws"));
+        let public_api_url = setting("PUBLIC_API_URL", file.public_api_url)?
+            .unwrap_or_else(|| format!("http://{}:{}", ws_host, ws_port));
+        let public_ws_url = setting("PUBLIC_WS_URL", file.public_ws_url)?
+            .unwrap_or_else(|| format!("ws://{}:{}{}", ws_host, ws_port, ws_path));
+        let oidc_callback_base_url = setting("OIDC_CALLBACK_BASE_URL", file.oidc_callback_base_url)?
+            .unwrap_or_else(|| public_api_url.clone());
+        #[cfg(feature = "storage-s3")]
+        let storage_s3_region = setting("STORAGE_S3_REGION", file.storage_s3_region)?
+            .unwrap_or_else(|| String::from("us-east-1"));
+        #[cfg(feature = "storage-s3")]
+        let storage_s3_endpoint = setting("STORAGE_S3_ENDPOINT", file.storage_s3_endpoint)?
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", storage_s3_region));
+
+        let config = Config {
+            ws_host,
+            ws_port,
+            ws_path,
+            metrics_host: setting("METRICS_HOST", file.metrics_host)?
+                .unwrap_or(IpAddr::from([127, 0, 0, 1])),
+            metrics_port: setting("METRICS_PORT", file.metrics_port)?.unwrap_or(8002),
+            public_api_url: public_api_url.clone(),
+            public_ws_url,
+            db_url: required("DB_URL", file.db_url)?,
+            db_name: required("DB_NAME", file.db_name)?,
+            db_namespace: required("DB_NAMESPACE", file.db_namespace)?,
+            db_user: required("DB_USER", file.db_user)?,
+            db_password: required("DB_PASSWORD", file.db_password)?,
+            #[cfg(feature = "storage-fs")]
+            storage_fs_root: required("STORAGE_FS_ROOT", file.storage_fs_root)?,
+            #[cfg(feature = "storage-s3")]
+            storage_s3_bucket: required("STORAGE_S3_BUCKET", file.storage_s3_bucket)?,
+            #[cfg(feature = "storage-s3")]
+            storage_s3_prefix: setting("STORAGE_S3_PREFIX", file.storage_s3_prefix)?
+                .unwrap_or_default(),
+            #[cfg(feature = "storage-s3")]
+            storage_s3_region,
+            #[cfg(feature = "storage-s3")]
+            storage_s3_endpoint,
+            #[cfg(feature = "storage-s3")]
+            storage_s3_path_style: setting("STORAGE_S3_PATH_STYLE", file.storage_s3_path_style)?
+                .unwrap_or(false),
+            #[cfg(feature = "storage-s3")]
+            storage_s3_access_key_id: required(
+                "STORAGE_S3_ACCESS_KEY_ID",
+                file.storage_s3_access_key_id,
+            )?,
+            #[cfg(feature = "storage-s3")]
+            storage_s3_secret_access_key: required(
+                "STORAGE_S3_SECRET_ACCESS_KEY",
+                file.storage_s3_secret_access_key,
+            )?,
+            #[cfg(feature = "storage-s3")]
+            storage_s3_session_token: setting(
+                "STORAGE_S3_SESSION_TOKEN",
+                file.storage_s3_session_token,
+            )?,
+            #[cfg(feature = "storage-s3")]
+            storage_s3_part_size: limit("STORAGE_S3_PART_SIZE", file.storage_s3_part_size, 8)?,
+            jwt_secret: required("JWT_SECRET", file.jwt_secret)?,
+            access_token_ttl: duration("ACCESS_TOKEN_TTL", file.access_token_ttl, 900)?,
+            refresh_token_ttl: duration("REFRESH_TOKEN_TTL", file.refresh_token_ttl, 2592000)?,
+            oidc_providers_file: setting("OIDC_PROVIDERS_FILE", file.oidc_providers_file)?,
+            oidc_callback_base_url,
+            ticket_ttl: duration("TICKET_TTL", file.ticket_ttl, 60)?,
+            heartbeat_interval: duration("HEARTBEAT_INTERVAL", file.heartbeat_interval, 30)?,
+            heartbeat_timeout: duration("HEARTBEAT_TIMEOUT", file.heartbeat_timeout, 10)?,
+            session_abandon_timeout: duration(
+                "SESSION_ABANDON_TIMEOUT",
+                file.session_abandon_timeout,
+                86400,
+            )?,
+            session_reap_interval: duration(
+                "SESSION_REAP_INTERVAL",
+                file.session_reap_interval,
+                600,
+            )?,
+            shutdown_timeout: duration("SHUTDOWN_TIMEOUT", file.shutdown_timeout, 30)?,
+            rate_limit_ip_per_minute: limit(
+                "RATE_LIMIT_IP_PER_MINUTE",
+                file.rate_limit_ip_per_minute,
+                300,
+            )?,
+            rate_limit_ip_burst: limit("RATE_LIMIT_IP_BURST", file.rate_limit_ip_burst, 100)?,
+            rate_limit_user_per_minute: limit(
+                "RATE_LIMIT_USER_PER_MINUTE",
+                file.rate_limit_user_per_minute,
+                120,
+            )?,
+            rate_limit_user_burst: limit("RATE_LIMIT_USER_BURST", file.rate_limit_user_burst, 60)?,
+            rate_limit_trust_forwarded: setting(
+                "RATE_LIMIT_TRUST_FORWARDED",
+                file.rate_limit_trust_forwarded,
+            )?
+            .unwrap_or(false),
+            ws_messages_per_second: limit(
+                "WS_MESSAGES_PER_SECOND",
+                file.ws_messages_per_second,
+                20,
+            )?,
+            ws_message_burst: limit("WS_MESSAGE_BURST", file.ws_message_burst, 100)?,
+            cors_allowed_origins: origins(
+                "CORS_ALLOWED_ORIGINS",
+                file.cors_allowed_origins,
+                &[&public_api_url],
+            )?,
+            cors_allowed_methods: list(
+                "CORS_ALLOWED_METHODS",
+                file.cors_allowed_methods,
+                &["GET", "POST", "PATCH", "DELETE"],
+            )?
+            .into_iter()
+            .map(|method| method.to_uppercase())
+            .collect(),
+            cors_allowed_headers: list(
+                "CORS_ALLOWED_HEADERS",
+                file.cors_allowed_headers,
+                &["Authorization", "Content-Type"],
+            )?,
+            admin_user_ids: list("ADMIN_USER_IDS", file.admin_user_ids, &[])?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if !self.ws_path.starts_with('/') {
+            return Err(ConfigError::Invalid {
+                name: "WS_PATH",
+                reason: String::from("should start with a /"),
+            });
+        }
+        if self.metrics_port == self.ws_port {
+            return Err(ConfigError::Invalid {
+                name: "METRICS_PORT",
+                reason: String::from("should be different from WS_PORT"),
+            });
+        }
+        if self.jwt_secret.len() < MIN_JWT_SECRET_LENGTH {
+            return Err(ConfigError::Invalid {
+                name: "JWT_SECRET",
+                reason: format!("should be at least {} bytes", MIN_JWT_SECRET_LENGTH),
+            });
+        }
+        if self.refresh_token_ttl <= self.access_token_ttl {
+            return Err(ConfigError::Invalid {
+                name: "REFRESH_TOKEN_TTL",
+                reason: String::from("should be greater than ACCESS_TOKEN_TTL"),
+            });
+        }
+        let heartbeat_round = self.heartbeat_interval + self.heartbeat_timeout;
+        if self.session_abandon_timeout < ABANDON_HEARTBEAT_ROUNDS * heartbeat_round {
+            return Err(ConfigError::Invalid {
+                name: "SESSION_ABANDON_TIMEOUT",
+                reason: format!(
+                    "should be at least {} times HEARTBEAT_INTERVAL + HEARTBEAT_TIMEOUT",
+                    ABANDON_HEARTBEAT_ROUNDS
+                ),
+            });
+        }
+        for (name, burst) in [
+            ("RATE_LIMIT_IP_BURST", self.rate_limit_ip_burst),
+            ("RATE_LIMIT_USER_BURST", self.rate_limit_user_burst),
+        ] {
+            if burst < MAX_REQUEST_COST {
+                return Err(ConfigError::Invalid {
+                    name,
+                    reason: format!("should be at least {}", MAX_REQUEST_COST),
+                });
+            }
+        }
+        for method in &self.cors_allowed_methods {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                return Err(ConfigError::Invalid {
+                    name: "CORS_ALLOWED_METHODS",
+                    reason: format!("{} is not a method", method),
+                });
+            }
+        }
+        for header in &self.cors_allowed_headers {
+            if HeaderName::from_bytes(header.as_bytes()).is_err() {
+                return Err(ConfigError::Invalid {
+                    name: "CORS_ALLOWED_HEADERS",
+                    reason: format!("{} is not a header name", header),
+                });
+            }
+        }
+        #[cfg(feature = "storage-s3")]
+        self.validate_s3()?;
+        if let Some(providers_file) = &self.oidc_providers_file {
+            if !providers_file.is_file() {
+                return Err(ConfigError::Invalid {
+                    name: "OIDC_PROVIDERS_FILE",
+                    reason: format!("{:?} is not a file", providers_file),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "storage-s3")]
+    fn validate_s3(&self) -> Result<(), ConfigError> {
+        match reqwest::Url::parse(&self.storage_s3_endpoint) {
+            Ok(url) if ["http", "https"].contains(&url.scheme()) && url.has_host() => {}
+            _ => {
+                return Err(ConfigError::Invalid {
+                    name: "STORAGE_S3_ENDPOINT",
+                    reason: String::from("should be a http or https URL"),
+                })
+            }
+        }
+        if self.storage_s3_prefix.starts_with('/') {
+            return Err(ConfigError::Invalid {
+                name: "STORAGE_S3_PREFIX",
+                reason: String::from("should not start with a /"),
+            });
+        }
+        if self.storage_s3_part_size < MIN_S3_PART_SIZE {
+            return Err(ConfigError::Invalid {
+                name: "STORAGE_S3_PART_SIZE",
+                reason: format!("should be at least {}", MIN_S3_PART_SIZE),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Loading the configuration. Should be called once before using `config`
+pub fn init() -> Result<&'static Config, ConfigError> {
+    let config = Config::load()?;
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// Configuration loaded at the startup
+pub fn config() -> &'static Config {
+    CONFIG.get().expect("Configuration is not loaded")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, thread};
+
+    use super::*;
+
+    /// Environment is shared by the tests running at the same time
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Every variable that the configuration is reading
+    const VARIABLES: &[&str] = &[
+        CONFIG_FILE_VAR,
+        "WS_HOST",
+        "WS_PORT",
+        "WS_PATH",
+        "METRICS_HOST",
+        "METRICS_PORT",
+        "PUBLIC_API_URL",
+        "PUBLIC_WS_URL",
+        "DB_URL",
+        "DB_NAME",
+        "DB_NAMESPACE",
+        "DB_USER",
+        "DB_PASSWORD",
+        "STORAGE_FS_ROOT",
+        "STORAGE_S3_BUCKET",
+        "STORAGE_S3_PREFIX",
+        "STORAGE_S3_REGION",
+        "STORAGE_S3_ENDPOINT",
+        "STORAGE_S3_PATH_STYLE",
+        "STORAGE_S3_ACCESS_KEY_ID",
+        "STORAGE_S3_SECRET_ACCESS_KEY",
+        "STORAGE_S3_SESSION_TOKEN",
+        "STORAGE_S3_PART_SIZE",
+        "JWT_SECRET",
+        "ACCESS_TOKEN_TTL",
+        "REFRESH_TOKEN_TTL",
+        "OIDC_PROVIDERS_FILE",
+        "OIDC_CALLBACK_BASE_URL",
+        "TICKET_TTL",
+        "HEARTBEAT_INTERVAL",
+        "HEARTBEAT_TIMEOUT",
+        "SESSION_ABANDON_TIMEOUT",
+        "SESSION_REAP_INTERVAL",
+        "SHUTDOWN_TIMEOUT",
+        "RATE_LIMIT_IP_PER_MINUTE",
+        "RATE_LIMIT_IP_BURST",
+        "RATE_LIMIT_USER_PER_MINUTE",
+        "RATE_LIMIT_USER_BURST",
+        "RATE_LIMIT_TRUST_FORWARDED",
+        "WS_MESSAGES_PER_SECOND",
+        "WS_MESSAGE_BURST",
+        "CORS_ALLOWED_ORIGINS",
+        "CORS_ALLOWED_METHODS",
+        "CORS_ALLOWED_HEADERS",
+        "ADMIN_USER_IDS",
+    ];
+
+    /// Settings without a default
+    const REQUIRED: [(&str, &str); 10] = [
+        ("DB_URL", "127.0.0.1:8001"),
+        ("DB_NAME", "openxd"),
+        ("DB_NAMESPACE", "openxd"),
+        ("DB_USER", "root"),
+        ("DB_PASSWORD", "root"),
+        ("STORAGE_FS_ROOT", "/var/lib/openxd/"),
+        ("JWT_SECRET", "0123456789abcdef0123456789abcdef"),
+        ("STORAGE_S3_BUCKET", "openxd"),
+        ("STORAGE_S3_ACCESS_KEY_ID", "minio-access-key"),
+        ("STORAGE_S3_SECRET_ACCESS_KEY", "minio-secret-key"),
+    ];
+
+    /// Loading with only the given variables and the given content in the config file
+    fn load_with(variables: &[(&str, &str)], file: Option<&str>) -> Result<Config, ConfigError> {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        for name in VARIABLES {
+            env::remove_var(name);
+        }
+        let path = file.map(|content| {
+            let path = env::temp_dir().join(format!(
+                "openxd-config-{}-{:?}.toml",
+                std::process::id(),
+                thread::current().id()
+            ));
+            fs::write(&path, content).unwrap();
+            env::set_var(CONFIG_FILE_VAR, &path);
+            path
+        });
+        for (name, value) in variables {
+            env::set_var(name, value);
+        }
+        let config = Config::from_env();
+        if let Some(path) = path {
+            fs::remove_file(path).unwrap();
+        }
+        config
+    }
+
+    /// Loading with the required settings and the given variables overriding them
+    fn load(variables: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        load_with(&[&REQUIRED[..], variables].concat(), None)
+    }
+
+    /// Message of the error. `Config` is not implementing `Debug` to unwrap it
+    fn error(result: Result<Config, ConfigError>) -> String {
+        match result {
+            Ok(_) => panic!("configuration should be rejected"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn environment_overrides_the_config_file() {
+        let file = "ws_port = 9000\nws_path = \"/socket\"\nadmin_user_ids = [\"admin\"]";
+        let config = load_with(
+            &[&REQUIRED[..], &[("WS_PORT", "9100")]].concat(),
+            Some(file),
+        )
+        .unwrap();
+
+        assert_eq!(config.ws_port, 9100);
+        assert_eq!(config.ws_path, "/socket");
+        assert_eq!(config.admin_user_ids, vec!["admin"]);
+        assert_eq!(config.public_ws_url, "ws://127.0.0.1:9100/socket");
+    }
+
+    #[test]
+    fn empty_variables_are_not_overriding() {
+        let config = load_with(
+            &[&REQUIRED[..], &[("WS_PORT", " "), ("ADMIN_USER_IDS", "")]].concat(),
+            Some("ws_port = 9000\nadmin_user_ids = [\"admin\"]"),
+        )
+        .unwrap();
+
+        assert_eq!(config.ws_port, 9000);
+        assert_eq!(config.admin_user_ids, vec!["admin"]);
+    }
+
+    #[test]
+    fn defaults_are_derived_from_the_address() {
+        let config = load(&[("WS_HOST", "0.0.0.0"), ("WS_PORT", "8080")]).unwrap();
+
+        assert_eq!(config.public_api_url, "http://0.0.0.0:8080");
+        assert_eq!(config.public_ws_url, "ws://0.0.0.0:8080/ws");
+        assert_eq!(config.oidc_callback_base_url, "http://0.0.0.0:8080");
+        assert_eq!(config.cors_allowed_origins, vec!["http://0.0.0.0:8080"]);
+        assert_eq!(config.access_token_ttl, 900);
+        assert!(!config.rate_limit_trust_forwarded);
+    }
+
+    #[test]
+    #[cfg(feature = "storage-fs")]
+    fn example_config_file_is_valid() {
+        let example = include_str!("../config.example.toml");
+        // Secrets are not in the example
+        let secrets: Vec<_> = REQUIRED
+            .into_iter()
+            .filter(|(name, _)| *name == "JWT_SECRET" || name.starts_with("STORAGE_S3_"))
+            .collect();
+        let config = load_with(&secrets, Some(example)).unwrap();
+
+        assert_eq!(config.db_url, "127.0.0.1:8001");
+        assert_eq!(
+            config.cors_allowed_origins,
+            vec!["https://openxd.example.com"]
+        );
+    }
+
+    #[test]
+    fn config_file_rejects_unknown_settings() {
+        let message = error(load_with(&REQUIRED, Some("jwt_secrets = \"typo\"")));
+
+        assert!(message.starts_with("config file"), "{}", message);
+        assert!(
+            message.contains("unknown field `jwt_secrets`"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn required_settings_should_be_configured() {
+        let without_db_url: Vec<_> = REQUIRED
+            .into_iter()
+            .filter(|(name, _)| *name != "DB_URL")
+            .collect();
+        assert_eq!(
+            error(load_with(&without_db_url, None)),
+            "DB_URL is not configured"
+        );
+    }
+
+    #[test]
+    fn values_should_be_parsable() {
+        assert_eq!(
+            error(load(&[("WS_PORT", "eighty")])),
+            "WS_PORT is not valid. invalid digit found in string"
+        );
+        assert_eq!(
+            error(load(&[("RATE_LIMIT_TRUST_FORWARDED", "yes")])),
+            "RATE_LIMIT_TRUST_FORWARDED is not valid. provided string was not `true` or `false`"
+        );
+    }
+
+    #[test]
+    fn ws_path_should_start_with_a_slash() {
+        assert_eq!(
+            error(load(&[("WS_PATH", "ws")])),
+            "WS_PATH is not valid. should start with a /"
+        );
+    }
+
+    #[test]
+    fn metrics_should_have_another_port() {
+        let config = load(&[]).unwrap();
+        assert_eq!(config.metrics_host, IpAddr::from([127, 0, 0, 1]));
+        assert_eq!(config.metrics_port, 8002);
+
+        assert_eq!(
+            error(load(&[("WS_PORT", "9000"), ("METRICS_PORT", "9000")])),
+            "METRICS_PORT is not valid. should be different from WS_PORT"
+        );
+    }
+
+    #[test]
+    fn jwt_secret_should_be_long_enough() {
+        assert_eq!(
+            error(load(&[("JWT_SECRET", "0123456789abcdef0123456789abcde")])),
+            "JWT_SECRET is not valid. should be at least 32 bytes"
+        );
+    }
+
+    #[test]
+    fn refresh_token_should_outlive_the_access_token() {
+        assert_eq!(
+            error(load(&[
+                ("ACCESS_TOKEN_TTL", "3600"),
+                ("REFRESH_TOKEN_TTL", "3600")
+            ])),
+            "REFRESH_TOKEN_TTL is not valid. should be greater than ACCESS_TOKEN_TTL"
+        );
+        assert!(load(&[("ACCESS_TOKEN_TTL", "3600"), ("REFRESH_TOKEN_TTL", "3601")]).is_ok());
+    }
+
+    #[test]
+    fn durations_and_limits_should_be_greater_than_zero() {
+        assert_eq!(
+            error(load(&[("TICKET_TTL", "0")])),
+            "TICKET_TTL is not valid. should be greater than zero"
+        );
+        assert_eq!(
+            error(load(&[("WS_MESSAGES_PER_SECOND", "0")])),
+            "WS_MESSAGES_PER_SECOND is not valid. should be greater than zero"
+        );
+    }
+
+    #[test]
+    fn abandon_timeout_should_cover_the_heartbeats() {
+        let heartbeat = [("HEARTBEAT_INTERVAL", "30"), ("HEARTBEAT_TIMEOUT", "10")];
+        assert_eq!(
+            error(load(&[&heartbeat[..], &[("SESSION_ABANDON_TIMEOUT", "79")]].concat())),
+            "SESSION_ABANDON_TIMEOUT is not valid. should be at least 2 times HEARTBEAT_INTERVAL + HEARTBEAT_TIMEOUT"
+        );
+        assert!(load(&[&heartbeat[..], &[("SESSION_ABANDON_TIMEOUT", "80")]].concat()).is_ok());
+    }
+
+    #[test]
+    fn bursts_should_allow_the_most_expensive_request() {
+        let too_small = (MAX_REQUEST_COST - 1).to_string();
+        for name in ["RATE_LIMIT_IP_BURST", "RATE_LIMIT_USER_BURST"] {
+            assert_eq!(
+                error(load(&[(name, too_small.as_str())])),
+                format!(
+                    "{} is not valid. should be at least {}",
+                    name, MAX_REQUEST_COST
+                )
+            );
+        }
+        let enough = MAX_REQUEST_COST.to_string();
+        let bursts = [
+            ("RATE_LIMIT_IP_BURST", enough.as_str()),
+            ("RATE_LIMIT_USER_BURST", enough.as_str()),
+        ];
+        assert!(load(&bursts).is_ok());
+    }
+
+    #[test]
+    fn cors_origins_are_normalized() {
+        let config = load(&[(
+            "CORS_ALLOWED_ORIGINS",
+            "https://openxd.example.com/, HTTP://LOCALHOST:80,https://openxd.example.com:8443",
+        )])
+        .unwrap();
+        assert_eq!(
+            config.cors_allowed_origins,
+            vec![
+                "https://openxd.example.com",
+                "http://localhost",
+                "https://openxd.example.com:8443"
+            ]
+        );
+
+        let config = load(&[("CORS_ALLOWED_ORIGINS", "*")]).unwrap();
+        assert_eq!(config.cors_allowed_origins, vec!["*"]);
+
+        assert_eq!(
+            error(load(&[("CORS_ALLOWED_ORIGINS", "openxd.example.com")])),
+            "CORS_ALLOWED_ORIGINS is not valid. openxd.example.com is not an origin. Eg:- https://openxd.example.com"
+        );
+    }
+
+    #[test]
+    fn cors_methods_and_headers_are_validated() {
+        let config = load(&[("CORS_ALLOWED_METHODS", "get, post")]).unwrap();
+        assert_eq!(config.cors_allowed_methods, vec!["GET", "POST"]);
+
+        assert_eq!(
+            error(load(&[("CORS_ALLOWED_METHODS", "GET,PO ST")])),
+            "CORS_ALLOWED_METHODS is not valid. PO ST is not a method"
+        );
+        assert_eq!(
+            error(load(&[("CORS_ALLOWED_HEADERS", "Authorization,X Request")])),
+            "CORS_ALLOWED_HEADERS is not valid. X Request is not a header name"
+        );
+    }
+
+    #[test]
+    fn oidc_providers_file_should_exist() {
+        let path = env::temp_dir().join("openxd-missing-oidc-providers.json");
+        let message = error(load(&[("OIDC_PROVIDERS_FILE", path.to_str().unwrap())]));
+        assert_eq!(
+            message,
+            format!("OIDC_PROVIDERS_FILE is not valid. {:?} is not a file", path)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "storage-s3")]
+    fn s3_endpoint_defaults_to_the_region() {
+        let config = load(&[("STORAGE_S3_REGION", "eu-west-1")]).unwrap();
+        assert_eq!(
+            config.storage_s3_endpoint,
+            "https://s3.eu-west-1.amazonaws.com"
+        );
+        assert_eq!(config.storage_s3_part_size, 8);
+    }
+
+    #[test]
+    #[cfg(feature = "storage-s3")]
+    fn s3_settings_are_validated() {
+        assert_eq!(
+            error(load(&[("STORAGE_S3_ENDPOINT", "ftp://minio.local")])),
+            "STORAGE_S3_ENDPOINT is not valid. should be a http or https URL"
+        );
+        assert_eq!(
+            error(load(&[("STORAGE_S3_PREFIX", "/assets/")])),
+            "STORAGE_S3_PREFIX is not valid. should not start with a /"
+        );
+        assert_eq!(
+            error(load(&[("STORAGE_S3_PART_SIZE", "4")])),
+            format!(
+                "STORAGE_S3_PART_SIZE is not valid. should be at least {}",
+                MIN_S3_PART_SIZE
+            )
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Cross-origin requests from the allowed origins
+//!
+//! Browsers are sending the `Origin` header with the cross-origin requests and the WebSocket
+//! upgrades. Responses to the other origins are not having any CORS headers, so browsers
+//! are blocking them. WebSocket upgrades are not protected by CORS, so those are rejected.
+
+use hyper::{
+    header::{
+        HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+        ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ORIGIN, VARY,
+    },
+    Body, HeaderMap, Method, Response,
+};
+use routerify::{Middleware, RequestInfo};
+use url::Url;
+
+use crate::{config::config, error::Error, storage::StorageError};
+
+/// Seconds that browsers can cache the answer of a preflight request
+const PREFLIGHT_MAX_AGE: u32 = 600;
+
+/// Origin in the `scheme://host:port` form. `None` for the values that are not an origin
+pub fn normalize_origin(origin: &str) -> Option<String> {
+    let url = Url::parse(origin.trim()).ok()?;
+    let origin = url.origin();
+    if origin.is_tuple() {
+        Some(origin.ascii_serialization())
+    } else {
+        None
+    }
+}
+
+/// Whether the origin is one of the `CORS_ALLOWED_ORIGINS`. `*` is allowing any origin
+fn is_origin_in(allowed: &[String], origin: &str) -> bool {
+    if allowed.iter().any(|allowed| allowed == "*") {
+        return true;
+    }
+    match normalize_origin(origin) {
+        Some(origin) => allowed.contains(&origin),
+        None => false,
+    }
+}
+
+/// Origin of the request if the request is coming from a browser
+pub fn request_origin(headers: &HeaderMap) -> Option<&str> {
+    headers.get(ORIGIN).and_then(|origin| origin.to_str().ok())
+}
+
+/// Rejecting a WebSocket upgrade from a page of another origin
+///
+/// Browsers are always sending the origin. Other clients are not limited by this.
+pub fn check_ws_origin(headers: &HeaderMap, allowed: &[String]) -> Result<(), Error<StorageError>> {
+    match request_origin(headers) {
+        Some(origin) if !is_origin_in(allowed, origin) => {
+            Err(Error::OriginNotAllowed(origin.to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Allowing the origin in the response. Preflight responses also have the allowed methods
+/// and headers
+fn set_cors_headers(
+    headers: &mut HeaderMap,
+    origin: &str,
+    preflight: bool,
+    allowed_methods: &[String],
+    allowed_headers: &[String],
+) {
+    if let Ok(origin) = HeaderValue::from_str(origin) {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.append(VARY, HeaderValue::from_static("Origin"));
+    }
+    if preflight {
+        if let Ok(methods) = HeaderValue::from_str(&allowed_methods.join(", ")) {
+            headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+        }
+        if let Ok(allowed_headers) = HeaderValue::from_str(&allowed_headers.join(", ")) {
+            headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allowed_headers);
+        }
+        headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(PREFLIGHT_MAX_AGE));
+    }
+}
+
+async fn add_cors_headers(
+    mut res: Response<Body>,
+    req_info: RequestInfo,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let config = config();
+    let origin = match request_origin(req_info.headers()) {
+        Some(origin) if is_origin_in(&config.cors_allowed_origins, origin) => origin,
+        _ => return Ok(res),
+    };
+    set_cors_headers(
+        res.headers_mut(),
+        origin,
+        req_info.method() == Method::OPTIONS,
+        &config.cors_allowed_methods,
+        &config.cors_allowed_headers,
+    );
+    Ok(res)
+}
+
+/// Adding the CORS headers for the allowed origins
+pub fn cors() -> Middleware<Body, Error<StorageError>> {
+    Middleware::post_with_info(add_cors_headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{
+        header::{CONNECTION, UPGRADE},
+        Request,
+    };
+
+    use super::*;
+
+    fn origins(origins: &[&str]) -> Vec<String> {
+        origins.iter().map(|origin| origin.to_string()).collect()
+    }
+
+    #[test]
+    fn origins_are_normalized() {
+        let normalized = Some(String::from("https://openxd.example.com"));
+        assert_eq!(normalize_origin("https://openxd.example.com"), normalized);
+        assert_eq!(normalize_origin("https://openxd.example.com/"), normalized);
+        assert_eq!(
+            normalize_origin(" https://OpenXD.example.com:443 "),
+            normalized
+        );
+        assert_eq!(
+            normalize_origin("https://openxd.example.com/app"),
+            normalized
+        );
+        assert_eq!(
+            normalize_origin("http://localhost:80"),
+            Some(String::from("http://localhost"))
+        );
+        assert_eq!(
+            normalize_origin("http://localhost:9000/"),
+            Some(String::from("http://localhost:9000"))
+        );
+        assert_eq!(normalize_origin("openxd.example.com"), None);
+        assert_eq!(normalize_origin("null"), None);
+        assert_eq!(normalize_origin("file:///home/user/index.html"), None);
+    }
+
+    #[test]
+    fn only_the_listed_origins_are_allowed() {
+        let allowed = origins(&["https://openxd.example.com", "http://localhost:9000"]);
+        assert!(is_origin_in(&allowed, "https://openxd.example.com"));
+        assert!(is_origin_in(&allowed, "https://openxd.example.com:443"));
+        assert!(is_origin_in(&allowed, "http://localhost:9000"));
+        assert!(!is_origin_in(&allowed, "http://openxd.example.com"));
+        assert!(!is_origin_in(&allowed, "http://localhost:9001"));
+        assert!(!is_origin_in(
+            &allowed,
+            "https://openxd.example.com.evil.example"
+        ));
+        assert!(!is_origin_in(&allowed, "null"));
+    }
+
+    #[test]
+    fn wildcard_allows_any_origin() {
+        let allowed = origins(&["*"]);
+        assert!(is_origin_in(&allowed, "https://any.example.com"));
+        assert!(is_origin_in(&allowed, "null"));
+    }
+
+    #[test]
+    fn preflight_responses_have_the_allowed_methods_and_headers() {
+        let methods = origins(&["GET", "POST"]);
+        let allowed_headers = origins(&["Authorization", "Content-Type"]);
+        let mut headers = HeaderMap::new();
+        set_cors_headers(
+            &mut headers,
+            "https://openxd.example.com",
+            true,
+            &methods,
+            &allowed_headers,
+        );
+
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://openxd.example.com"
+        );
+        assert_eq!(headers[VARY], "Origin");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "GET, POST");
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_HEADERS],
+            "Authorization, Content-Type"
+        );
+        assert_eq!(headers[ACCESS_CONTROL_MAX_AGE], "600");
+    }
+
+    #[test]
+    fn other_responses_only_allow_the_origin() {
+        let mut headers = HeaderMap::new();
+        set_cors_headers(
+            &mut headers,
+            "https://openxd.example.com",
+            false,
+            &origins(&["GET"]),
+            &origins(&["Authorization"]),
+        );
+
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://openxd.example.com"
+        );
+        assert!(!headers.contains_key(ACCESS_CONTROL_ALLOW_METHODS));
+        assert!(!headers.contains_key(ACCESS_CONTROL_ALLOW_HEADERS));
+    }
+
+    #[test]
+    fn websocket_upgrades_from_other_origins_are_rejected() {
+        let allowed = origins(&["https://openxd.example.com"]);
+        let upgrade = |origin: Option<&str>| {
+            let mut req = Request::builder()
+                .uri("/ws?ticket=abc")
+                .header(CONNECTION, "Upgrade")
+                .header(UPGRADE, "websocket");
+            if let Some(origin) = origin {
+                req = req.header(ORIGIN, origin);
+            }
+            req.body(Body::empty()).unwrap()
+        };
+
+        let rejected = check_ws_origin(upgrade(Some("https://evil.example")).headers(), &allowed);
+        assert!(matches!(
+            rejected,
+            Err(Error::OriginNotAllowed(origin)) if origin == "https://evil.example"
+        ));
+        assert!(check_ws_origin(
+            upgrade(Some("https://openxd.example.com")).headers(),
+            &allowed
+        )
+        .is_ok());
+        // Not a browser
+        assert!(check_ws_origin(upgrade(None).headers(), &allowed).is_ok());
+    }
+}
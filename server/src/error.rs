@@ -1,6 +1,10 @@
-use std::{array::TryFromSliceError, fmt::Debug};
+use std::{fmt::Debug, time::Duration};
 
-use app::external::{CreateProjectUsingExistingFileError, GetCurrentTabSnapshotError, ExportSnapshotError};
+use app::{
+    access::AccessError,
+    projects::{DeleteProjectError, RenameProjectError},
+    external::{CreateProjectUsingExistingFileError, GetCurrentTabSnapshotError, ExportSnapshotError},
+};
 use hmac::digest::InvalidLength;
 use hyper::header::ToStrError;
 
@@ -26,25 +30,73 @@ pub enum Error <SE: Debug + std::error::Error + Send + Sync> {
     #[error(transparent)]
     Auth(#[from] AuthError),
     #[error(transparent)]
+    Account(#[from] AccountError),
+    #[error(transparent)]
+    Oidc(#[from] OidcError),
+    #[error(transparent)]
+    Access(#[from] AccessError),
+    #[error(transparent)]
+    RenameProject(#[from] RenameProjectError),
+    #[error(transparent)]
+    DeleteProject(#[from] DeleteProjectError<SE>),
+    #[error(transparent)]
     CurrentSnapshot(#[from] GetCurrentTabSnapshotError),
     #[error(transparent)]
     SnapshotDownload(#[from] SnapshotDownloadError<SE>),
+    #[error("server is shutting down")]
+    ShuttingDown,
+    #[error("too many requests. retry after {retry_after:?}")]
+    RateLimited { retry_after: Duration },
+    #[error("origin is not allowed {0}")]
+    OriginNotAllowed(String),
+    #[error("only the admins can access")]
+    AdminRequired,
+    #[error("{0} filter is not valid {1}")]
+    InvalidAuditFilter(&'static str, String),
+}
+
+impl<SE: Debug + std::error::Error + Send + Sync> Error<SE> {
+    /// Name of the variant without the details. Used as a metric label
+    pub fn variant(&self) -> &'static str {
+        match self {
+            Error::Db(_) => "Db",
+            Error::Storage(_) => "Storage",
+            Error::Io(_) => "Io",
+            Error::Multer(_) => "Multer",
+            Error::WebSocketOpen(_) => "WebSocketOpen",
+            Error::CreateProject(_) => "CreateProject",
+            Error::Auth(_) => "Auth",
+            Error::Account(_) => "Account",
+            Error::Oidc(_) => "Oidc",
+            Error::Access(_) => "Access",
+            Error::RenameProject(_) => "RenameProject",
+            Error::DeleteProject(_) => "DeleteProject",
+            Error::CurrentSnapshot(_) => "CurrentSnapshot",
+            Error::SnapshotDownload(_) => "SnapshotDownload",
+            Error::ShuttingDown => "ShuttingDown",
+            Error::RateLimited { retry_after: _ } => "RateLimited",
+            Error::OriginNotAllowed(_) => "OriginNotAllowed",
+            Error::AdminRequired => "AdminRequired",
+            Error::InvalidAuditFilter(_, _) => "InvalidAuditFilter",
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum WebSocketOpenError {
-    #[error("ticket is expired {ticket_id}, ticket expired at {closed_at}.")]
+    #[error("ticket is expired {ticket_id}.")]
     ExpiredTicket {
         ticket_id: String,
-        closed_at: surrealdb::sql::Datetime
+    },
+    #[error("ticket is already used {ticket_id}.")]
+    UsedTicket {
+        ticket_id: String,
     },
     #[error("ticket is still pending {ticket_id}, ticket opened at {opened_at}.")]
     PendingTicket {
         ticket_id: String,
         opened_at: surrealdb::sql::Datetime
     },
-    #[error("ticket id is not valid")]
-    TryFromSlice(#[from] TryFromSliceError),
     #[error("ticket not found {ticket_id}.")]
     TicketNotFound {ticket_id: String},
     #[error("ticket not provided.")]
@@ -76,7 +128,57 @@ pub enum AuthError {
     #[error("can not verify the token")]
     Jwt(#[from] jwt::Error),
     #[error("token didn't matched the requirements")]
-    InvalidLength(#[from] InvalidLength)
+    InvalidLength(#[from] InvalidLength),
+    #[error("token is expired")]
+    Expired,
+    #[error("subject not provided in the token")]
+    SubjectNotProvided,
+    #[error("email or password is not correct")]
+    InvalidCredentials,
+    #[error("token is revoked")]
+    Revoked,
+    #[error("token id not provided in the token")]
+    TokenIdNotProvided,
+    #[error("refresh token is not valid")]
+    InvalidRefreshToken,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AccountError {
+    #[error("failed to read the request body")]
+    Hyper(#[from] hyper::Error),
+    #[error("request body is not valid")]
+    Body(#[from] serde_json::Error),
+    #[error("{0}")]
+    Validation(&'static str),
+    #[error("email is already registered")]
+    EmailTaken,
+    #[error("failed to hash the password")]
+    PasswordHash(#[from] argon2::password_hash::Error),
+    #[error("password hashing task failed")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum OidcError {
+    #[error("login provider not configured {0}")]
+    UnknownProvider(String),
+    #[error("url of the login provider is not valid")]
+    Url(#[from] openidconnect::url::ParseError),
+    #[error("failed to discover the login provider. {0}")]
+    Discovery(String),
+    #[error("login provider returned an error {0}")]
+    Provider(String),
+    #[error("authorization code not provided")]
+    CodeNotProvided,
+    #[error("login state is not valid or expired")]
+    InvalidState,
+    #[error("failed to exchange the authorization code. {0}")]
+    CodeExchange(String),
+    #[error("id token not provided by the login provider")]
+    IdTokenNotProvided,
+    #[error("can not verify the id token")]
+    IdToken(#[from] openidconnect::ClaimsVerificationError),
 }
 
 #[derive(thiserror::Error, Debug)]
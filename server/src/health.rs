@@ -0,0 +1,109 @@
+//! Liveness and readiness probes for the orchestrators
+
+use std::time::{Duration, Instant};
+
+use app::{model::User, storage::Storage};
+use hyper::{Body, Request, Response, StatusCode};
+use log::warn;
+use once_cell::sync::Lazy;
+use rest::system::ReadinessResponse;
+use tokio::{sync::Mutex, time::timeout};
+
+use crate::{auth::json_response, error::Error, get_db, get_storage, storage::StorageError};
+
+/// Maximum time for a single readiness check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time that the result of the storage check is reused. Probes should not write to the storage
+/// at every request
+const STORAGE_CHECK_TTL: Duration = Duration::from_secs(30);
+
+/// Status of the last storage check and when it finished
+static STORAGE_STATUS: Lazy<Mutex<Option<(Instant, &'static str)>>> = Lazy::new(Default::default);
+
+/// Process is running and serving the requests. No dependencies are checked
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "system",
+    responses((status = 200, body = String, content_type = "text/plain"))
+)]
+pub async fn healthz_handler(_req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    Ok(Response::new(Body::from("OK")))
+}
+
+async fn check_db() -> Result<(), Error<StorageError>> {
+    let mut users_res = get_db()
+        .await
+        .query("SELECT * FROM type::table($table) LIMIT 1")
+        .bind(("table", User::TABLE))
+        .await?;
+    let _users: Vec<User> = users_res.take(0)?;
+    Ok(())
+}
+
+/// Writing and removing a small file to make sure that the uploads can be saved
+async fn check_storage() -> Result<(), Error<StorageError>> {
+    let storage = get_storage();
+    let mut probe: &[u8] = b"ready";
+    let key = storage
+        .put(&mut probe, String::from("health"), String::from("txt"))
+        .await
+        .map_err(Error::Storage)?;
+    storage.delete(key).await.map_err(Error::Storage)
+}
+
+/// Status of the storage check within the `STORAGE_CHECK_TTL`, or of a new check
+///
+/// Lock is held while checking. So the requests at the same time are waiting for one check.
+async fn storage_status() -> &'static str {
+    let mut last = STORAGE_STATUS.lock().await;
+    match *last {
+        Some((checked_at, status)) if checked_at.elapsed() < STORAGE_CHECK_TTL => status,
+        _ => {
+            let status = check_status("storage", timeout(CHECK_TIMEOUT, check_storage()).await);
+            *last = Some((Instant::now(), status));
+            status
+        }
+    }
+}
+
+fn check_status<F>(name: &str, result: Result<Result<(), Error<StorageError>>, F>) -> &'static str {
+    match result {
+        Ok(Ok(())) => "ok",
+        Ok(Err(e)) => {
+            warn!("Readiness check failed for {}:- {:?}", name, e);
+            "failed"
+        }
+        Err(_) => {
+            warn!("Readiness check timed out for {}", name);
+            "timeout"
+        }
+    }
+}
+
+/// Whether the database and the storage are usable. Responding 503 when any of them not
+///
+/// Storage is checked at most once in `STORAGE_CHECK_TTL`.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "system",
+    responses(
+        (status = 200, body = ReadinessResponse),
+        (status = 503, body = ReadinessResponse),
+    )
+)]
+pub async fn readyz_handler(_req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let (db, storage) = tokio::join!(timeout(CHECK_TIMEOUT, check_db()), storage_status());
+    let response = ReadinessResponse {
+        db: check_status("database", db).to_string(),
+        storage: storage.to_string(),
+    };
+    let status = if response.db == "ok" && response.storage == "ok" {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok(json_response(status, &response))
+}
@@ -0,0 +1,145 @@
+//! Registry of the WebSocket sessions that are connected to this server
+//!
+//! Other parts of the server can use this to close the sessions of a user without knowing
+//! the connections. Eg:- after revoking all tokens of the user.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use once_cell::sync::Lazy;
+use surrealdb::sql::Thing;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+static LIVE_SESSIONS: Lazy<LiveSessions> = Lazy::new(LiveSessions::default);
+
+/// Request to close a connection
+#[derive(Clone)]
+pub struct CloseRequest {
+    /// Reason to show in the UI
+    pub reason: String,
+    /// Whether the session should be kept open to reattach with the resume ticket
+    pub resumable: bool,
+}
+
+/// Connection id, the attached session and the sender to request closing the connection
+type CloseSender = (u64, Thing, UnboundedSender<CloseRequest>);
+
+#[derive(Default)]
+struct LiveSessions {
+    next_id: AtomicU64,
+    /// Close request senders of the connections by the user id
+    users: Mutex<HashMap<String, Vec<CloseSender>>>,
+    /// Request to close the connections registered after `close_all`
+    closing: Mutex<Option<CloseRequest>>,
+}
+
+/// A connection registered in the live sessions. Unregistered when dropped
+pub struct LiveSession {
+    id: u64,
+    user_id: String,
+    receiver: UnboundedReceiver<CloseRequest>,
+}
+
+impl LiveSession {
+    /// Waiting until someone requested to close this connection
+    pub async fn close_requested(&mut self) -> Option<CloseRequest> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for LiveSession {
+    fn drop(&mut self) {
+        let mut users = LIVE_SESSIONS.users.lock().unwrap();
+        if let Some(sessions) = users.get_mut(&self.user_id) {
+            sessions.retain(|(id, _, _)| *id != self.id);
+            if sessions.is_empty() {
+                users.remove(&self.user_id);
+            }
+        }
+    }
+}
+
+/// Registering a connection of the user that attached to the session
+pub fn register(user_id: String, session: Thing) -> LiveSession {
+    let id = LIVE_SESSIONS.next_id.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = unbounded_channel();
+    if let Some(request) = LIVE_SESSIONS.closing.lock().unwrap().as_ref() {
+        let _ = sender.send(request.clone());
+    }
+    LIVE_SESSIONS
+        .users
+        .lock()
+        .unwrap()
+        .entry(user_id.clone())
+        .or_default()
+        .push((id, session, sender));
+    LiveSession {
+        id,
+        user_id,
+        receiver,
+    }
+}
+
+/// Requesting all connections of the user to close with their sessions
+///
+/// Returning the count of connections.
+pub fn close_user_sessions(user_id: &str, reason: &str) -> usize {
+    let request = CloseRequest {
+        reason: String::from(reason),
+        resumable: false,
+    };
+    let users = LIVE_SESSIONS.users.lock().unwrap();
+    match users.get(user_id) {
+        Some(sessions) => sessions
+            .iter()
+            .filter(|(_, _, sender)| sender.send(request.clone()).is_ok())
+            .count(),
+        None => 0,
+    }
+}
+
+/// Requesting all connections to close. Connections registered after this are also closed
+///
+/// Sessions are kept open, so the users can reattach to them after reconnected. Returning the
+/// count of connections requested to close.
+pub fn close_all(reason: &str) -> usize {
+    let request = CloseRequest {
+        reason: String::from(reason),
+        resumable: true,
+    };
+    *LIVE_SESSIONS.closing.lock().unwrap() = Some(request.clone());
+    let users = LIVE_SESSIONS.users.lock().unwrap();
+    users
+        .values()
+        .flatten()
+        .filter(|(_, _, sender)| sender.send(request.clone()).is_ok())
+        .count()
+}
+
+/// Sessions that are attached to the connections of this server
+pub fn sessions() -> Vec<Thing> {
+    LIVE_SESSIONS
+        .users
+        .lock()
+        .unwrap()
+        .values()
+        .flatten()
+        .map(|(_, session, _)| session.clone())
+        .collect()
+}
+
+/// Count of the connections to this server
+pub fn connection_count() -> usize {
+    LIVE_SESSIONS
+        .users
+        .lock()
+        .unwrap()
+        .values()
+        .map(|sessions| sessions.len())
+        .sum()
+}
@@ -1,39 +1,66 @@
 use std::{
-    collections::BTreeMap,
     fmt::Debug,
-    net::{IpAddr, SocketAddr},
+    net::SocketAddr,
     pin::Pin,
-    str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 use app::{
+    access::AccessError,
     external::{
         create_project_using_existing_file, export_snapshot,
         CreateProjectUsingExistingFileError, GetCurrentTabSnapshotError, get_current_tab,
     },
-    model::{thing, User},
+    model::{thing, AuditAction, AuditEvent, User},
+    projects::{define_project_indexes, DeleteProjectError, RenameProjectError},
     App,
 };
-use config::{
-    DB_NAME, DB_NAMESPACE, DB_PASSWORD, DB_URL, DB_USER, JWT_SECRET, WS_HOST, WS_PATH, WS_PORT,
+use audit::audit_events_handler;
+use auth::{
+    authenticate, login_handler, logout_all_handler, logout_handler, purge_expired_tokens,
+    refresh_handler, register_handler, ticket_handler,
+};
+use config::config;
+use error::{
+    AccountError, AuthError, CreateProjectError, Error, OidcError, SnapshotDownloadError,
+    WebSocketOpenError,
 };
-use error::{AuthError, CreateProjectError, Error, SnapshotDownloadError, WebSocketOpenError};
-use futures::{lock::Mutex, ready, TryStreamExt};
-use hmac::{Hmac, Mac};
-use hyper::{header::CONTENT_TYPE, Body, Request, Response, Server, StatusCode, Method};
-use jwt::{SignWithKey, VerifyWithKey};
-use log::{info, trace};
+use futures::{ready, TryStreamExt};
+use hyper::{header::{CONTENT_TYPE, RETRY_AFTER}, Body, Request, Response, Server, StatusCode, Method};
+use log::{info, trace, warn};
 use model::{SnapshotDownload, Ticket};
+use health::{healthz_handler, readyz_handler};
+use metrics::metrics_handler;
+use oidc::{oidc_callback_handler, oidc_login_handler};
+use projects::{
+    delete_project_handler, list_projects_handler, project_by_slug_handler,
+    rename_project_handler,
+};
+use rate_limit::TokenBucket;
+use sharing::{
+    accept_invitation_handler, change_role_handler, decline_invitation_handler,
+    invitations_handler, invite_handler, members_handler, remove_member_handler,
+};
+use ticket::{
+    check_ticket, close_ticket, extend_resume_ticket, issue_resume_ticket, open_ticket,
+    purge_expired_tickets, ticket_expiry, ticket_user_id,
+};
 use multer::Multipart;
+use openapi::openapi_handler;
 use querystring::querify;
+use rest::{
+    paths,
+    projects::{CurrentTabSnapshotResponse, OxdUploadSuccessResponse},
+    system::WebConfig,
+};
 use routerify::{prelude::RequestExt, Middleware, RouteError, Router, RouterService};
-use routerify_cors::enable_cors_all;
 use routerify_websocket::{upgrade_ws, WebSocket as RouterifyWebSocket};
-use serde::Serialize;
-use sha2::Sha256;
-use storage::{StorageError, StorageImpl};
+use storage::{new_storage, StorageError, StorageImpl};
 
 #[cfg(any(feature = "db-http", feature = "db-https"))]
 use surrealdb::engine::remote::http::Client as DbClient;
@@ -53,57 +80,79 @@ use surrealdb::opt::auth::Database as DatabaseAuth;
 use surrealdb::opt::auth::Namespace as NamespaceAuth;
 #[cfg(feature = "db-auth-root")]
 use surrealdb::opt::auth::Root as RootAuth;
+use surrealdb::sql::Thing;
 use surrealdb::Surreal;
 
 use once_cell::sync::OnceCell;
 use serde_json::ser::to_string;
 use tokio::{io::AsyncWrite, sync::OnceCell as TokioOnceCell};
 use tokio_util::io::StreamReader;
-use transport::ReceiveError;
-use ws::WebSocket;
+use transport::{
+    app::{ApplicationMessage, SessionClosingMessage},
+    ui::UIMessage,
+    Client, ReceiveError,
+};
+use ws::{WebSocket, WebSocketError};
 
+mod audit;
+mod auth;
 mod config;
+mod cors;
 mod error;
+mod health;
+mod live;
+mod metrics;
 mod model;
+mod oidc;
+mod openapi;
+mod projects;
+mod rate_limit;
+mod sharing;
+mod shutdown;
 mod storage;
+mod ticket;
 mod ws;
 
 static STORAGE: OnceCell<Arc<StorageImpl>> = OnceCell::new();
 
 pub fn get_storage() -> &'static Arc<StorageImpl> {
-    STORAGE.get_or_init(|| Arc::new(StorageImpl))
+    STORAGE.get_or_init(|| Arc::new(new_storage()))
 }
 
 static DB: TokioOnceCell<Arc<Surreal<DbClient>>> = TokioOnceCell::const_new();
 
 pub async fn get_db() -> &'static Arc<Surreal<DbClient>> {
     DB.get_or_init(|| async {
-        trace!("Connecting to DB: {}", DB_URL);
-        let db = Surreal::new::<DbConnection>(DB_URL).await.unwrap();
-        trace!("Using namespace and DB: {},{}", DB_NAMESPACE, DB_NAME);
-        db.use_ns(DB_NAMESPACE).use_db(DB_NAME).await.unwrap();
-        trace!("Authenticating:- {}", DB_USER);
+        let config = config();
+        trace!("Connecting to DB: {}", config.db_url);
+        let db = Surreal::new::<DbConnection>(config.db_url.as_str()).await.unwrap();
+        trace!("Using namespace and DB: {},{}", config.db_namespace, config.db_name);
+        db.use_ns(config.db_namespace.as_str())
+            .use_db(config.db_name.as_str())
+            .await
+            .unwrap();
+        trace!("Authenticating:- {}", config.db_user);
         #[cfg(feature = "db-auth-root")]
         db.signin(RootAuth {
-            username: DB_USER,
-            password: DB_PASSWORD,
+            username: &config.db_user,
+            password: &config.db_password,
         })
         .await
         .unwrap();
         #[cfg(feature = "db-auth-database")]
         db.signin(DatabaseAuth {
-            username: DB_USER,
-            password: DB_PASSWORD,
-            namespace: DB_NAMESPACE,
-            database: DB_NAME,
+            username: &config.db_user,
+            password: &config.db_password,
+            namespace: &config.db_namespace,
+            database: &config.db_name,
         })
         .await
         .unwrap();
         #[cfg(feature = "db-auth-namespace")]
         db.signin(NamespaceAuth {
-            username: DB_URL,
-            password: DB_PASSWORD,
-            namespace: DB_NAMESPACE,
+            username: &config.db_user,
+            password: &config.db_password,
+            namespace: &config.db_namespace,
         })
         .await
         .unwrap();
@@ -112,12 +161,12 @@ pub async fn get_db() -> &'static Arc<Surreal<DbClient>> {
     .await
 }
 
-static APP: TokioOnceCell<Mutex<App<DbClient>>> = TokioOnceCell::const_new();
+static APP: TokioOnceCell<App<DbClient>> = TokioOnceCell::const_new();
 
-pub async fn get_app() -> &'static Mutex<App<DbClient>> {
+pub async fn get_app() -> &'static App<DbClient> {
     APP.get_or_init(|| async {
         let db = get_db().await;
-        Mutex::new(App::new(db.clone()))
+        App::new(db.clone())
     })
     .await
 }
@@ -126,37 +175,140 @@ pub async fn get_app() -> &'static Mutex<App<DbClient>> {
 async fn main() {
     env_logger::init();
 
+    let config = match config::init() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration:- {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = define_indexes().await {
+        eprintln!("Could not define the DB indexes:- {:?}", e);
+        std::process::exit(1);
+    }
+
     let service = RouterService::new(router()).expect("Could not create router");
-    let socket_addr = SocketAddr::new(
-        IpAddr::from_str(WS_HOST).expect("Could not parse WS_HOST value as an IpAddr"),
-        WS_PORT
-            .parse()
-            .expect("Could not parse WS_PORT value as a u16"),
-    );
+    let socket_addr = SocketAddr::new(config.ws_host, config.ws_port);
+
+    let server = Server::bind(&socket_addr)
+        .serve(service)
+        .with_graceful_shutdown(shutdown::signal());
+
+    // Metrics are only on the internal listener, not reachable through the public port
+    let metrics_addr = SocketAddr::new(config.metrics_host, config.metrics_port);
+    let metrics_service =
+        RouterService::new(metrics_router()).expect("Could not create metrics router");
+    match Server::try_bind(&metrics_addr) {
+        Ok(metrics_server) => {
+            tokio::spawn(metrics_server.serve(metrics_service));
+            info!("Metrics are served on: {}", metrics_addr);
+        }
+        Err(e) => {
+            eprintln!("Could not listen for the metrics on {}:- {:?}", metrics_addr, e);
+            std::process::exit(1);
+        }
+    }
 
-    let server = Server::bind(&socket_addr).serve(service);
+    tokio::spawn(reap_sessions_periodically());
 
-    info!("App is running on: {}:{}", WS_HOST, WS_PORT);
+    info!("App is running on: {}", socket_addr);
     if let Err(err) = server.await {
         eprintln!("Server error: {:?}", err);
     }
+    shutdown::drain_sessions().await;
+}
+
+/// Defining the DB indexes that keep the emails and the project slugs unique
+async fn define_indexes() -> Result<(), surrealdb::Error> {
+    auth::define_account_indexes().await?;
+    define_project_indexes(get_db().await).await
+}
+
+/// Closing the sessions that nobody reattached within the abandon timeout
+///
+/// Expired tokens, tickets and idle rate limit buckets are removed in the same run.
+async fn reap_sessions_periodically() {
+    let abandon_timeout = Duration::from_secs(config().session_abandon_timeout);
+    let mut interval = tokio::time::interval(Duration::from_secs(config().session_reap_interval));
+    loop {
+        interval.tick().await;
+        let reaped = get_app()
+            .await
+            .reap_abandoned_sessions(get_storage().clone(), abandon_timeout)
+            .await;
+        match reaped {
+            Ok(0) => {}
+            Ok(reaped) => info!("Closed {} abandoned session(s)", reaped),
+            Err(e) => warn!("Failed to close the abandoned sessions:- {:?}", e),
+        }
+        if let Err(e) = purge_expired_tokens().await {
+            warn!("Failed to remove the expired tokens:- {:?}", e);
+        }
+        match purge_expired_tickets().await {
+            Ok(0) => {}
+            Ok(purged) => info!("Removed {} expired ticket(s)", purged),
+            Err(e) => warn!("Failed to remove the expired tickets:- {:?}", e),
+        }
+        rate_limit::purge_idle_buckets();
+    }
 }
 
 fn router() -> Router<Body, Error<StorageError>> {
     // Create a router and specify the path and the handler for new websocket connections.
     Router::builder()
         // It will accept websocket connections at `/ws` path with GET method type.
-        .get(WS_PATH, ws_open_handler)
-        .middleware(enable_cors_all())
+        .get(config().ws_path.as_str(), ws_open_handler)
+        .middleware(cors::cors())
         .middleware(Middleware::pre(logger))
+        .middleware(rate_limit::ip_rate_limit())
         .middleware(api_auth(&[
-            "/api/create-project",
-            "/api/current-tab-snapshot",
+            paths::CREATE_PROJECT,
+            paths::CURRENT_TAB_SNAPSHOT,
+            paths::TICKET,
+            paths::LOGOUT,
+            paths::LOGOUT_ALL,
+            paths::PROJECTS,
+            paths::PROJECT,
+            paths::PROJECT_MEMBERS,
+            paths::PROJECT_MEMBER,
+            paths::PROJECT_INVITATIONS,
+            paths::PROJECT_BY_SLUG,
+            paths::INVITATIONS,
+            paths::ACCEPT_INVITATION,
+            paths::DECLINE_INVITATION,
+            paths::AUDIT_EVENTS,
         ]))
-        .post("/api/create-project", oxd_upload_handler)
-        .get("/api/current-tab-snapshot", current_tab_snapshot_handler)
+        .middleware(rate_limit::user_rate_limit())
+        .post(paths::REGISTER, register_handler)
+        .post(paths::LOGIN, login_handler)
+        .post(paths::REFRESH, refresh_handler)
+        .post(paths::LOGOUT, logout_handler)
+        .post(paths::LOGOUT_ALL, logout_all_handler)
+        .get(paths::OIDC_LOGIN, oidc_login_handler)
+        .get(paths::OIDC_CALLBACK, oidc_callback_handler)
+        .post(paths::TICKET, ticket_handler)
+        .get(paths::PROJECTS, list_projects_handler)
+        .patch(paths::PROJECT, rename_project_handler)
+        .delete(paths::PROJECT, delete_project_handler)
+        .get(paths::PROJECT_MEMBERS, members_handler)
+        .patch(paths::PROJECT_MEMBER, change_role_handler)
+        .delete(paths::PROJECT_MEMBER, remove_member_handler)
+        .post(paths::PROJECT_INVITATIONS, invite_handler)
+        // After the other routes with two segments. `members` and `invitations` are reserved slugs
+        .get(paths::PROJECT_BY_SLUG, project_by_slug_handler)
+        .get(paths::INVITATIONS, invitations_handler)
+        .post(paths::ACCEPT_INVITATION, accept_invitation_handler)
+        .post(paths::DECLINE_INVITATION, decline_invitation_handler)
+        .get(paths::AUDIT_EVENTS, audit_events_handler)
+        .post(paths::CREATE_PROJECT, oxd_upload_handler)
+        .get(paths::CURRENT_TAB_SNAPSHOT, current_tab_snapshot_handler)
         .get("/api/test-auth", test_auth_handler)
-        .get("/api/snapshot/:downloadId", download_snapshot_handler)
+        .get(paths::SNAPSHOT, download_snapshot_handler)
+        .get(paths::WEB_CONFIG, web_config_handler)
+        .get(paths::HEALTHZ, healthz_handler)
+        .get(paths::READYZ, readyz_handler)
+        .get(paths::OPENAPI, openapi_handler)
         .get("/", |_req| async move {
             Ok(Response::new("I also serve http requests".into()))
         })
@@ -165,6 +317,15 @@ fn router() -> Router<Body, Error<StorageError>> {
         .unwrap()
 }
 
+/// Routes of the internal listener
+fn metrics_router() -> Router<Body, Error<StorageError>> {
+    Router::builder()
+        .get(paths::METRICS, metrics_handler)
+        .err_handler(error_handler)
+        .build()
+        .unwrap()
+}
+
 // A middleware which logs an http request.
 async fn logger(req: Request<Body>) -> Result<Request<Body>, Error<StorageError>> {
     trace!(
@@ -176,16 +337,91 @@ async fn logger(req: Request<Body>) -> Result<Request<Body>, Error<StorageError>
     Ok(req)
 }
 
+/// Status of the errors from the project permission checks
+fn access_error_status(access_err: &AccessError) -> Option<(StatusCode, &'static str)> {
+    match access_err {
+        AccessError::ProjectNotFound => Some((StatusCode::NOT_FOUND, "PROJECT NOT FOUND")),
+        AccessError::Forbidden { required: _ } => Some((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        AccessError::InvitationNotFound | AccessError::MemberNotFound => {
+            Some((StatusCode::NOT_FOUND, "NOT FOUND"))
+        }
+        AccessError::AlreadyMember => Some((StatusCode::CONFLICT, "ALREADY A MEMBER")),
+        AccessError::OwnerRole => Some((StatusCode::BAD_REQUEST, "VALIDATION ERROR")),
+        AccessError::Db(_) => None,
+    }
+}
+
 async fn error_handler(route_err: RouteError) -> Response<Body> {
     let err: Box<Error<StorageError>> = route_err.downcast().unwrap();
     log::error!("{:?}", &err);
+    metrics::record_error(&err);
     let mut status_code: StatusCode = StatusCode::INTERNAL_SERVER_ERROR;
     let mut err_code = "INTERNAL SERVER ERROR";
+    let mut retry_after: Option<Duration> = None;
     match err.as_ref() {
         Error::Auth(_) => {
             status_code = StatusCode::UNAUTHORIZED;
             err_code = "UNAUTHORIZED";
         }
+        Error::Account(account_err) => match account_err {
+            AccountError::Hyper(_) | AccountError::Body(_) => {
+                status_code = StatusCode::BAD_REQUEST;
+                err_code = "REQUEST BODY INVALID";
+            }
+            AccountError::Validation(_) => {
+                status_code = StatusCode::BAD_REQUEST;
+                err_code = "VALIDATION ERROR";
+            }
+            AccountError::EmailTaken => {
+                status_code = StatusCode::CONFLICT;
+                err_code = "EMAIL ALREADY REGISTERED";
+            }
+            _ => {}
+        },
+        Error::Oidc(oidc_err) => match oidc_err {
+            OidcError::UnknownProvider(_) => {
+                status_code = StatusCode::NOT_FOUND;
+                err_code = "LOGIN PROVIDER NOT FOUND";
+            }
+            OidcError::Provider(_)
+            | OidcError::CodeNotProvided
+            | OidcError::InvalidState
+            | OidcError::IdTokenNotProvided
+            | OidcError::IdToken(_) => {
+                status_code = StatusCode::UNAUTHORIZED;
+                err_code = "UNAUTHORIZED";
+            }
+            OidcError::Discovery(_) | OidcError::CodeExchange(_) => {
+                status_code = StatusCode::BAD_GATEWAY;
+                err_code = "LOGIN PROVIDER UNAVAILABLE";
+            }
+            OidcError::Url(_) => {}
+        },
+        Error::Access(access_err) => {
+            if let Some(status) = access_error_status(access_err) {
+                (status_code, err_code) = status;
+            }
+        }
+        Error::RenameProject(rename_err) => match rename_err {
+            RenameProjectError::Access(access_err) => {
+                if let Some(status) = access_error_status(access_err) {
+                    (status_code, err_code) = status;
+                }
+            }
+            RenameProjectError::NameNotProvided => {
+                status_code = StatusCode::BAD_REQUEST;
+                err_code = "VALIDATION ERROR";
+            }
+            RenameProjectError::Db(_) => {}
+        },
+        Error::DeleteProject(delete_err) => match delete_err {
+            DeleteProjectError::Access(access_err) => {
+                if let Some(status) = access_error_status(access_err) {
+                    (status_code, err_code) = status;
+                }
+            }
+            _ => {}
+        },
         Error::CreateProject(create_project_err) => match create_project_err {
             CreateProjectError::Inner(create_project_internal_err) => {
                 match create_project_internal_err {
@@ -216,14 +452,15 @@ async fn error_handler(route_err: RouteError) -> Response<Body> {
                 status_code = StatusCode::CONFLICT;
                 err_code = "TICKET STILL PENDING";
             }
-            WebSocketOpenError::ExpiredTicket {
-                ticket_id: _,
-                closed_at: _,
-            } => {
+            WebSocketOpenError::ExpiredTicket { ticket_id: _ } => {
                 status_code = StatusCode::BAD_REQUEST;
                 err_code = "EXPIRED TICKET";
             }
-            WebSocketOpenError::TicketIdNotProvided | WebSocketOpenError::TryFromSlice(_) => {
+            WebSocketOpenError::UsedTicket { ticket_id: _ } => {
+                status_code = StatusCode::CONFLICT;
+                err_code = "TICKET ALREADY USED";
+            }
+            WebSocketOpenError::TicketIdNotProvided => {
                 status_code = StatusCode::BAD_REQUEST;
                 err_code = "TICKET ID VALIDATION FAILED";
             }
@@ -240,6 +477,27 @@ async fn error_handler(route_err: RouteError) -> Response<Body> {
             }
             _ => {}
         },
+        Error::ShuttingDown => {
+            status_code = StatusCode::SERVICE_UNAVAILABLE;
+            err_code = "SERVER SHUTTING DOWN";
+        }
+        Error::AdminRequired => {
+            status_code = StatusCode::FORBIDDEN;
+            err_code = "FORBIDDEN";
+        }
+        Error::InvalidAuditFilter(_, _) => {
+            status_code = StatusCode::BAD_REQUEST;
+            err_code = "VALIDATION ERROR";
+        }
+        Error::OriginNotAllowed(_) => {
+            status_code = StatusCode::FORBIDDEN;
+            err_code = "ORIGIN NOT ALLOWED";
+        }
+        Error::RateLimited { retry_after: wait } => {
+            status_code = StatusCode::TOO_MANY_REQUESTS;
+            err_code = "TOO MANY REQUESTS";
+            retry_after = Some(*wait);
+        }
         Error::SnapshotDownload(dwnld_err) => match dwnld_err {
             SnapshotDownloadError::Invalid { download_id: _ }
             | SnapshotDownloadError::AlreadyDownloaded { download_id: _ } => {
@@ -251,19 +509,45 @@ async fn error_handler(route_err: RouteError) -> Response<Body> {
         _ => {}
     }
 
-    return Response::builder()
-        .status(status_code)
-        .body(Body::from(err_code))
-        .unwrap();
+    let mut response = Response::builder().status(status_code);
+    if let Some(retry_after) = retry_after {
+        // Rounding up to not retry before the tokens are available
+        let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        response = response.header(RETRY_AFTER, seconds);
+    }
+    return response.body(Body::from(err_code)).unwrap();
 }
 
 #[derive(Clone, Debug)]
 pub struct UserId(pub String);
 
+/// Matching a request path to a route. `:name` segments of the route are matching any value
+fn route_matches(route: &str, path: &str) -> bool {
+    let mut route_segments = route.split('/');
+    let mut path_segments = path.split('/');
+    loop {
+        match (route_segments.next(), path_segments.next()) {
+            (None, None) => return true,
+            (Some(route_segment), Some(path_segment)) => {
+                let matched = if route_segment.starts_with(':') {
+                    !path_segment.is_empty()
+                } else {
+                    route_segment == path_segment
+                };
+                if !matched {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
 pub fn api_auth(routes: &'static [&str]) -> Middleware<hyper::Body, Error<StorageError>> {
     Middleware::pre(move |req| async move {
-        let uri = req.uri();
-        if routes.contains(&&uri.to_string().as_str()) && req.method() != &Method::OPTIONS {
+        let path = req.uri().path();
+        let protected = routes.iter().any(|route| route_matches(route, path));
+        if protected && req.method() != &Method::OPTIONS {
             match req.headers().get(hyper::header::AUTHORIZATION) {
                 Some(auth_head) => {
                     let auth_head_str = auth_head
@@ -274,15 +558,9 @@ pub fn api_auth(routes: &'static [&str]) -> Middleware<hyper::Body, Error<Storag
                         if bearer.to_lowercase() == "bearer" {
                             match auth_head_split.next() {
                                 Some(token) => {
-                                    let key: Hmac<Sha256> = Hmac::new_from_slice(
-                                        JWT_SECRET.as_bytes(),
-                                    )
-                                    .map_err(|e| Error::Auth(AuthError::InvalidLength(e)))?;
-                                    let claims: BTreeMap<String, String> = token
-                                        .verify_with_key(&key)
-                                        .map_err(|e| Error::Auth(AuthError::Jwt(e)))?;
-                                    let user_id = claims["sub"].clone();
-                                    req.set_context(UserId(user_id));
+                                    let access_token = authenticate(token).await?;
+                                    req.set_context(UserId(access_token.user_id.clone()));
+                                    req.set_context(access_token);
                                     Ok(req)
                                 }
                                 None => Err(Error::Auth(AuthError::BearerNotProvided)),
@@ -302,7 +580,25 @@ pub fn api_auth(routes: &'static [&str]) -> Middleware<hyper::Body, Error<Storag
     })
 }
 
-#[derive(Serialize)]
+/// Endpoints that the web frontend should use. Same as the `config.json` of the web bundle
+#[utoipa::path(
+    get,
+    path = "/config.json",
+    tag = "system",
+    responses((status = 200, body = WebConfig))
+)]
+pub async fn web_config_handler(_req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let config = config();
+    Ok(auth::json_response(
+        StatusCode::OK,
+        &WebConfig {
+            api_url: config.public_api_url.clone(),
+            ws_url: config.public_ws_url.clone(),
+        },
+    ))
+}
+
+#[derive(serde::Serialize)]
 #[cfg(debug_assertions)]
 pub struct TestAuthResponse {
     ticket: String,
@@ -325,15 +621,9 @@ pub async fn test_auth_handler(_req: Request<Body>) -> Result<Response<Body>, Er
         new_user.pop().unwrap()
     };
 
-    let key: Hmac<Sha256> = Hmac::new_from_slice(JWT_SECRET.as_bytes())
-        .map_err(|e| Error::Auth(AuthError::InvalidLength(e)))?;
-    let mut claims = BTreeMap::new();
-    claims.insert("sub", user.id.clone().unwrap().id.to_string());
-    let token_str = claims
-        .sign_with_key(&key)
-        .map_err(|e| Error::Auth(AuthError::Jwt(e)))?;
+    let (token_str, _) = auth::issue_access_token(user.id.clone().unwrap().id.to_string()).await?;
 
-    let ticket = Ticket::new(user.id.unwrap());
+    let ticket = Ticket::new(user.id.unwrap(), ticket_expiry());
 
     let mut created_ticket: Vec<Ticket> = db.create(Ticket::TABLE).content(ticket).await?;
 
@@ -356,79 +646,46 @@ pub async fn test_auth_handler(req: Request<Body>) -> Result<Response<Body>, Err
 
 /// Handling the web socket connections
 pub async fn ws_open_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    if shutdown::is_draining() {
+        return Err(Error::ShuttingDown);
+    }
+    cors::check_ws_origin(req.headers(), &config().cors_allowed_origins)?;
     if let Some(query_str) = req.uri().query() {
         let parsed_query = querify(query_str);
         let mut query_iter = parsed_query.iter().filter(|q| q.0 == "ticket");
         let token = query_iter.next();
         if let Some((_, ticket)) = token {
             let app = get_app().await;
-            let app = app.lock().await;
             let ticket_opt: Option<Ticket> =
                 app.database().select((Ticket::TABLE, *ticket)).await?;
 
             match ticket_opt {
                 Some(ticket) => {
-                    let opened_at = ticket.opened_at.clone();
-                    if opened_at.is_some() && !ticket.allow_connect_again {
-                        return Err(Error::WebSocketOpen(
-                            error::WebSocketOpenError::ExpiredTicket {
-                                ticket_id: ticket.id.unwrap().id.to_string(),
-                                closed_at: ticket.closed_at.unwrap(),
-                            },
-                        ));
-                    }
+                    check_ticket(&ticket)?;
+                    let user_id = ticket_user_id(&ticket);
+                    let used_event = audit::request_event(&req, AuditAction::TicketUsed, &user_id)
+                        .target(ticket.id.clone().unwrap())
+                        .detail("resumed", ticket.session.is_some());
 
-                    if let Some(opened_at) = ticket.opened_at {
-                        if let Some(closed_at) = ticket.closed_at {
-                            if opened_at >= closed_at {
-                                return Err(Error::WebSocketOpen(
-                                    error::WebSocketOpenError::PendingTicket {
-                                        ticket_id: ticket.id.unwrap().id.to_string(),
-                                        opened_at,
-                                    },
-                                ));
-                            }
-                        } else {
-                            return Err(Error::WebSocketOpen(
-                                error::WebSocketOpenError::PendingTicket {
-                                    ticket_id: ticket.id.unwrap().id.to_string(),
-                                    opened_at,
-                                },
-                            ));
-                        }
-                    }
+                    let ticket_id = ticket.id.clone().unwrap();
+                    let resume_session = ticket.session.clone();
 
-                    // Converting to raw bytes. Because string not implements copy
-                    let user_id_str = ticket.user.id.to_raw();
-                    let user_id_bytes: [u8; 20] = user_id_str
-                        .as_bytes()
-                        .try_into()
-                        .map_err(|e| Error::WebSocketOpen(WebSocketOpenError::TryFromSlice(e)))?;
-
-                    let ws_handler = move |ws: RouterifyWebSocket| async move {
-                        let user_id = String::from_utf8(Vec::from(user_id_bytes)).unwrap();
-                        let local_ws = WebSocket::new(ws);
-                        let app = get_app().await;
-                        let mut app = app.lock().await;
-                        let mut session = app
-                            .create_session(user_id, local_ws, get_storage().clone())
-                            .await
-                            .unwrap();
-
-                        loop {
-                            let message = session.receive_message().await;
-                            match message {
-                                Ok(message) => {
-                                    session.handle_message(message).await;
-                                }
-                                Err(e) => match e {
-                                    ReceiveError::Terminated => {
-                                        session.close().await;
-                                        break;
-                                    }
-                                    _ => {}
-                                },
+                    // Ticket is opened only after upgraded. A failed upgrade is not using it
+                    let ws_handler = move |ws: RouterifyWebSocket| {
+                        let ticket_id = ticket_id.clone();
+                        let user_id = user_id.clone();
+                        let resume_session = resume_session.clone();
+                        let used_event = used_event.clone();
+                        async move {
+                            let ws = WebSocket::new(ws);
+                            if let Err(e) = open_ticket(ticket_id.clone()).await {
+                                warn!("Rejecting the connection of {}:- {:?}", ticket_id, e);
+                                reject_connection(ws, TICKET_OPEN_FAILED).await;
+                                return;
                             }
+                            audit::record(used_event).await;
+                            run_session(ws, user_id, resume_session).await;
+                            close_ticket(ticket_id).await;
                         }
                     };
 
@@ -450,20 +707,157 @@ pub async fn ws_open_handler(req: Request<Body>) -> Result<Response<Body>, Error
     ));
 }
 
-#[derive(Serialize)]
-pub struct OxdUploadSuccessResponse {
-    id: String,
+/// Reason sent to the UI when the session could not be started
+const SESSION_START_FAILED: &str = "Could not open the session. Please reconnect.";
+
+/// Reason sent to the UI when another connection opened the ticket after it was validated
+const TICKET_OPEN_FAILED: &str = "The ticket is already used. Please reconnect.";
+
+/// Notifying the UI with the reason and closing a connection that has no session
+async fn reject_connection(ws: WebSocket, reason: &str) {
+    let mut client: Client<UIMessage, ApplicationMessage, WebSocketError, WebSocket> =
+        Client::new(ws);
+    if let Err(e) = client.send(SessionClosingMessage::new(String::from(reason))).await {
+        warn!("Failed to notify the rejected connection:- {:?}", e);
+    }
+    if let Err(e) = client.close().await {
+        warn!("Failed to close the rejected connection:- {:?}", e);
+    }
 }
 
-impl OxdUploadSuccessResponse {
-    pub fn new(session_id: String) -> OxdUploadSuccessResponse {
-        OxdUploadSuccessResponse { id: session_id }
+/// Attaching the connection to a session and handling the messages until it closed
+///
+/// `resume_session` is reattached if it is still open. Otherwise a new session is created.
+async fn run_session(local_ws: WebSocket, user_id: String, resume_session: Option<Thing>) {
+    let app = get_app().await;
+
+    let resumable = match resume_session {
+        Some(session) => match app.resumable_session(session, user_id.clone()).await {
+            Ok(resumable) => resumable,
+            Err(e) => {
+                warn!("Failed to find the session to resume:- {:?}", e);
+                reject_connection(local_ws, SESSION_START_FAILED).await;
+                return;
+            }
+        },
+        None => None,
+    };
+    let resumed = resumable.is_some();
+    let session_data = match resumable {
+        Some(session_data) => session_data,
+        None => match app.new_session_data(user_id.clone()).await {
+            Ok(session_data) => session_data,
+            Err(e) => {
+                warn!("Failed to create a session for {}:- {:?}", user_id, e);
+                reject_connection(local_ws, SESSION_START_FAILED).await;
+                return;
+            }
+        },
+    };
+    let session_id = session_data.id.clone().unwrap();
+    let mut session = match app.attach_session(session_data, local_ws, get_storage().clone()) {
+        Ok(session) => session,
+        Err(local_ws) => {
+            warn!("Session {} is already attached to another connection", session_id);
+            reject_connection(local_ws, "The session is already opened in another window.").await;
+            return;
+        }
+    };
+    let synced = if resumed {
+        session.replay_tabs().await
+    } else {
+        session.offer_previous_session().await
+    };
+    if let Err(e) = synced {
+        warn!("Failed to start the session {}:- {:?}", session_id, e);
+        session.disconnect(String::from(SESSION_START_FAILED)).await;
+        return;
+    }
+
+    let mut live_session = live::register(user_id.clone(), session_id.clone());
+    let mut message_limit = TokenBucket::new(
+        config().ws_messages_per_second as f64,
+        config().ws_message_burst as f64,
+    );
+    let resume_ticket = match issue_resume_ticket(&user_id, session_id.clone()).await {
+        Ok(resume_ticket) => resume_ticket,
+        Err(e) => {
+            warn!("Failed to issue a resume ticket for {}:- {:?}", session_id, e);
+            session.disconnect(String::from(SESSION_START_FAILED)).await;
+            return;
+        }
+    };
+    if let Err(e) = session.send_resume_ticket(resume_ticket).await {
+        warn!("Connection dropped before started the session {}:- {:?}", session_id, e);
+        return;
+    }
+
+    loop {
+        let message = tokio::select! {
+            message = session.receive_message() => message,
+            Some(request) = live_session.close_requested() => {
+                if request.resumable {
+                    // Keeping the session open to reattach with the resume ticket
+                    extend_resume_ticket(session_id.clone()).await;
+                    session.disconnect(request.reason).await;
+                } else {
+                    session.close_with_reason(request.reason).await;
+                }
+                break;
+            }
+        };
+        if let Ok(message) = &message {
+            metrics::record_message(message);
+            if message_limit.try_take(1.0).is_err() {
+                warn!("Session {} exceeded the message rate limit", session.id());
+                session
+                    .close_with_reason(String::from("Too many messages. Please reconnect."))
+                    .await;
+                break;
+            }
+        }
+        match message {
+            Ok(UIMessage::Close) => {
+                session.close().await;
+                break;
+            }
+            Ok(message) => {
+                session.handle_message(message).await;
+            }
+            Err(e) => match e {
+                ReceiveError::Terminated => {
+                    // Keeping the session open to reattach with the resume ticket
+                    info!("Connection dropped for session {}", session.id());
+                    extend_resume_ticket(session_id.clone()).await;
+                    break;
+                }
+                _ => {}
+            },
+        }
     }
 }
 
 /// Handling the oxd file uploads
+///
+/// The `project_name` field should be sent before the `file` field.
+#[utoipa::path(
+    post,
+    path = "/api/create-project",
+    tag = "projects",
+    request_body(
+        content = inline(openapi::CreateProjectForm),
+        content_type = "multipart/form-data"
+    ),
+    responses(
+        (status = 201, body = OxdUploadSuccessResponse),
+        (status = 400, description = "VALIDATION ERROR or FILE NOT VALID"),
+        (status = 401, description = "UNAUTHORIZED"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn oxd_upload_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
     let user_id = req.context::<UserId>().unwrap();
+    let upload_event = audit::request_event(&req, AuditAction::ProjectUploaded, &user_id.0);
     let boundary = req
         .headers()
         .get(CONTENT_TYPE)
@@ -485,20 +879,35 @@ pub async fn oxd_upload_handler(req: Request<Body>) -> Result<Response<Body>, Er
                 project_name = Some(project_name_val);
             } else if field_name == "file" {
                 if let Some(project_name) = project_name {
+                    let upload_size = Arc::new(AtomicU64::new(0));
+                    let counted_size = upload_size.clone();
                     let stream_reader = StreamReader::new(
-                        field.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+                        field
+                            .inspect_ok(move |chunk| {
+                                counted_size.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                            })
+                            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
                     );
                     let project = create_project_using_existing_file(
                         get_db().await.clone(),
                         get_storage().clone(),
+                        &get_app().await.events(),
                         stream_reader,
                         project_name,
                         user_id.0.clone(),
                     )
-                    .await
-                    .map_err(|e| Error::CreateProject(CreateProjectError::Inner(e)))?;
+                    .await;
+                    metrics::record_upload(upload_size.load(Ordering::Relaxed));
+                    let project =
+                        project.map_err(|e| Error::CreateProject(CreateProjectError::Inner(e)))?;
+                    audit::record(
+                        upload_event
+                            .target(project.id.clone().unwrap())
+                            .detail("size", upload_size.load(Ordering::Relaxed)),
+                    )
+                    .await;
                     let project_id_str = project.id.unwrap().id.to_string();
-                    let success_response = OxdUploadSuccessResponse::new(project_id_str);
+                    let success_response = OxdUploadSuccessResponse { id: project_id_str };
                     let json_content = to_string(&success_response).unwrap();
                     return Ok(Response::builder()
                         .status(StatusCode::CREATED)
@@ -562,17 +971,18 @@ impl AsyncWrite for SenderWriter {
     }
 }
 
-#[derive(Serialize)]
-pub struct CurrentTabSnapshotResponse {
-    download_id: String,
-}
-
-impl CurrentTabSnapshotResponse {
-    pub fn new(download_id: String) -> CurrentTabSnapshotResponse {
-        CurrentTabSnapshotResponse { download_id }
-    }
-}
-
+/// Creating a one-time download of the snapshot of the current tab
+#[utoipa::path(
+    get,
+    path = "/api/current-tab-snapshot",
+    tag = "projects",
+    responses(
+        (status = 201, body = CurrentTabSnapshotResponse),
+        (status = 400, description = "NO TAB OR SESSION CREATED"),
+        (status = 401, description = "UNAUTHORIZED"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn current_tab_snapshot_handler(
     req: Request<Body>,
 ) -> Result<Response<Body>, Error<StorageError>> {
@@ -590,8 +1000,9 @@ pub async fn current_tab_snapshot_handler(
         .content(snapshot_download)
         .await?;
 
-    let success_response =
-        CurrentTabSnapshotResponse::new(created_downloads.pop().unwrap().id.unwrap().id.to_string());
+    let success_response = CurrentTabSnapshotResponse {
+        download_id: created_downloads.pop().unwrap().id.unwrap().id.to_string(),
+    };
     let json_content = to_string(&success_response).unwrap();
     return Ok(Response::builder()
         .status(StatusCode::CREATED)
@@ -599,11 +1010,23 @@ pub async fn current_tab_snapshot_handler(
         .unwrap());
 }
 
+/// Downloading a snapshot as an oxd file. Each download id can be used once
+#[utoipa::path(
+    get,
+    path = "/api/snapshot/{downloadId}",
+    tag = "projects",
+    params(("downloadId" = String, Path, description = "From `/api/current-tab-snapshot`")),
+    responses(
+        (status = 200, body = Vec<u8>, content_type = "application/openxd"),
+        (status = 404, description = "NOT FOUND"),
+    )
+)]
 pub async fn download_snapshot_handler(
     req: Request<Body>,
 ) -> Result<Response<Body>, Error<StorageError>> {
     let db = get_db().await;
     let download_id = req.param("downloadId").unwrap();
+    let ip = rate_limit::client_ip(&req).to_string();
 
     let snapshot_download_opt: Option<SnapshotDownload> = db
         .select(thing(SnapshotDownload::TABLE, download_id.clone()))
@@ -625,20 +1048,34 @@ pub async fn download_snapshot_handler(
             .content(snapshot_download_cpy)
             .await?;
 
+        audit::record(
+            AuditEvent::new(AuditAction::SnapshotExported, Some(snapshot_download.user.clone()))
+                .target(snapshot_download.snapshot.clone())
+                .ip(ip)
+                .detail("name", &snapshot_download.name)
+                .detail("download", download_id),
+        )
+        .await;
+
         let snapshot_id = snapshot_download.snapshot.id.to_string();
         let name = snapshot_download.name;
 
         let (sender, body) = Body::channel();
         let sender_writer = SenderWriter::new(sender);
-        tokio::spawn(async {
-            export_snapshot(
+        tokio::spawn(async move {
+            let started_at = Instant::now();
+            let exported = export_snapshot(
                 get_db().await.clone(),
                 get_storage().clone(),
                 sender_writer,
-                snapshot_id,
+                snapshot_id.clone(),
             )
-            .await
-            .unwrap();
+            .await;
+            // The writer is dropped with the failed export. So the body is closed anyway
+            match exported {
+                Ok(()) => metrics::record_export(started_at.elapsed()),
+                Err(e) => warn!("Failed to export the snapshot {}:- {:?}", snapshot_id, e),
+            }
         });
         Ok(Response::builder()
             .header("Content-Type", "application/openxd")
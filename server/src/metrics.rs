@@ -0,0 +1,156 @@
+//! Prometheus metrics of the server
+//!
+//! Counters are updated where the events are happening. Gauges that can be read from
+//! the state (Eg:- connected sessions) are updated when scraping. Served on `METRICS_PORT`,
+//! which is separate from the public API.
+
+use std::{borrow::Cow, time::Duration};
+
+use app::model::Tab;
+use hyper::{header::CONTENT_TYPE, Body, Request, Response, StatusCode};
+use log::warn;
+use once_cell::sync::Lazy;
+use prometheus::{
+    exponential_buckets, register_histogram, register_int_counter_vec, register_int_gauge,
+    Encoder, Histogram, IntCounterVec, IntGauge, TextEncoder,
+};
+use serde::Deserialize;
+use transport::ui::UIMessage;
+
+use crate::{error::Error, get_db, live, storage::StorageError};
+
+static ACTIVE_SESSIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "openxd_active_sessions",
+        "WebSocket sessions connected to this server"
+    )
+    .unwrap()
+});
+
+static OPEN_TABS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("openxd_open_tabs", "Tabs of the sessions that are not closed").unwrap()
+});
+
+static WS_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "openxd_ws_messages_total",
+        "Messages received from the WebSocket clients",
+        &["variant"]
+    )
+    .unwrap()
+});
+
+static UPLOAD_SIZES: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "openxd_upload_size_bytes",
+        "Sizes of the uploaded project files",
+        // 1KiB to 256MiB
+        exponential_buckets(1024.0, 4.0, 10).unwrap()
+    )
+    .unwrap()
+});
+
+static EXPORT_DURATIONS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "openxd_export_duration_seconds",
+        "Time taken to export the snapshots",
+        exponential_buckets(0.05, 2.0, 12).unwrap()
+    )
+    .unwrap()
+});
+
+static ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "openxd_errors_total",
+        "Errors returned by the HTTP handlers",
+        &["variant"]
+    )
+    .unwrap()
+});
+
+/// Name of the message to use as a label. Payloads are not included
+///
+/// RPCs are labelled with the operation. Eg:- `Rpc::OpenFile`
+fn message_variant(message: &UIMessage) -> Cow<'static, str> {
+    match message {
+        UIMessage::Ping => Cow::Borrowed("Ping"),
+        UIMessage::Close => Cow::Borrowed("Close"),
+        UIMessage::Error(_) => Cow::Borrowed("Error"),
+        UIMessage::Resize(_, _) => Cow::Borrowed("Resize"),
+        UIMessage::Rpc(request) => Cow::Owned(format!("Rpc::{}", request.operation())),
+        UIMessage::Heartbeat => Cow::Borrowed("Heartbeat"),
+    }
+}
+
+pub fn record_message(message: &UIMessage) {
+    WS_MESSAGES
+        .with_label_values(&[&message_variant(message)])
+        .inc();
+}
+
+pub fn record_upload(bytes: u64) {
+    UPLOAD_SIZES.observe(bytes as f64);
+}
+
+pub fn record_export(duration: Duration) {
+    EXPORT_DURATIONS.observe(duration.as_secs_f64());
+}
+
+pub fn record_error(err: &Error<StorageError>) {
+    ERRORS.with_label_values(&[err.variant()]).inc();
+}
+
+#[derive(Deserialize)]
+struct Count {
+    count: i64,
+}
+
+async fn count_open_tabs() -> Result<i64, surrealdb::Error> {
+    let mut tabs_res = get_db()
+        .await
+        .query("SELECT count() FROM type::table($table) WHERE exited_at = NONE AND session.closed_at = NONE GROUP ALL")
+        .bind(("table", Tab::TABLE))
+        .await?;
+    let count: Option<Count> = tabs_res.take(0)?;
+    Ok(count.map(|count| count.count).unwrap_or(0))
+}
+
+/// Metrics in the Prometheus text format. Only served by the internal listener
+pub async fn metrics_handler(_req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    ACTIVE_SESSIONS.set(live::connection_count() as i64);
+    match count_open_tabs().await {
+        Ok(count) => OPEN_TABS.set(count),
+        // Keeping the previous value. Other metrics are still useful while the DB is down
+        Err(e) => warn!("Failed to count the open tabs:- {:?}", e),
+    }
+
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    encoder.encode(&prometheus::gather(), &mut buffer).unwrap();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, encoder.format_type())
+        .body(Body::from(buffer))
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use transport::rpc::RpcRequest;
+
+    use super::*;
+
+    #[test]
+    fn rpcs_are_labelled_by_the_operation() {
+        let open_file = UIMessage::Rpc(RpcRequest::OpenFile {
+            project_id: String::from("landing-page"),
+        });
+        assert_eq!(message_variant(&open_file), "Rpc::OpenFile");
+        let commit_tab = UIMessage::Rpc(RpcRequest::CommitTab {
+            tab_id: String::from("tab"),
+            message: String::from("Changed the colors"),
+        });
+        assert_eq!(message_variant(&commit_tab), "Rpc::CommitTab");
+        assert_eq!(message_variant(&UIMessage::Heartbeat), "Heartbeat");
+    }
+}
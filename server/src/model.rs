@@ -1,6 +1,167 @@
+use app::model::User;
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::{Datetime, Thing};
 
+/// A registered user with the login credentials
+///
+/// Stored in the same table with `app::model::User`. Users created without an account
+/// (eg:- by the standalone app) are not having an email or a password.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserAccount {
+    pub id: Option<Thing>,
+    pub name: String,
+    pub email: Option<String>,
+    /// Argon2 hash in PHC string format
+    pub password_hash: Option<String>,
+    pub created_at: Option<Datetime>,
+}
+
+impl UserAccount {
+    pub const TABLE: &str = User::TABLE;
+
+    pub fn new(name: String, email: String, password_hash: String) -> UserAccount {
+        UserAccount {
+            id: None,
+            name,
+            email: Some(email),
+            password_hash: Some(password_hash),
+            created_at: Some(Datetime::default()),
+        }
+    }
+
+    /// Creating an account for a user signed in with an external provider
+    pub fn new_external(name: String, email: Option<String>) -> UserAccount {
+        UserAccount {
+            id: None,
+            name,
+            email,
+            password_hash: None,
+            created_at: Some(Datetime::default()),
+        }
+    }
+}
+
+/// A user of an external OpenID Connect provider linked to a `User`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserIdentity {
+    pub id: Option<Thing>,
+    pub issuer: String,
+    /// `sub` claim of the ID token. Unique only within the issuer
+    pub subject: String,
+    pub user: Thing,
+    pub created_at: Datetime,
+}
+
+impl UserIdentity {
+    pub const TABLE: &str = "useridentities";
+
+    pub fn new(issuer: String, subject: String, user: Thing) -> UserIdentity {
+        UserIdentity {
+            id: None,
+            issuer,
+            subject,
+            user,
+            created_at: Datetime::default(),
+        }
+    }
+}
+
+/// A login started with an external provider. Stored using the CSRF state as the id
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OidcState {
+    pub id: Option<Thing>,
+    /// Name of the provider in the configuration
+    pub provider: String,
+    pub pkce_verifier: String,
+    pub nonce: String,
+    /// Unix timestamp in seconds
+    pub expires_at: u64,
+}
+
+impl OidcState {
+    pub const TABLE: &str = "oidcstates";
+
+    pub fn new(
+        id: Thing,
+        provider: String,
+        pkce_verifier: String,
+        nonce: String,
+        expires_at: u64,
+    ) -> OidcState {
+        OidcState {
+            id: Some(id),
+            provider,
+            pkce_verifier,
+            nonce,
+            expires_at,
+        }
+    }
+}
+
+/// A refresh token issued at login. Stored using the SHA-256 hash of the token as the id
+///
+/// A refresh token can be used only once. Using it is revoking it and issuing a new one.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RefreshToken {
+    pub id: Option<Thing>,
+    pub user: Thing,
+    pub created_at: Datetime,
+    /// Unix timestamp in seconds
+    pub expires_at: u64,
+    pub revoked_at: Option<Datetime>,
+    /// The token issued when this token was used
+    pub replaced_by: Option<Thing>,
+}
+
+impl RefreshToken {
+    pub const TABLE: &str = "refreshtokens";
+
+    pub fn new(id: Thing, user: Thing, expires_at: u64) -> RefreshToken {
+        RefreshToken {
+            id: Some(id),
+            user,
+            created_at: Datetime::default(),
+            expires_at,
+            revoked_at: None,
+            replaced_by: None,
+        }
+    }
+}
+
+/// An access token revoked before it expired. Stored using the `jti` claim as the id
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RevokedToken {
+    pub id: Option<Thing>,
+    pub user: Thing,
+    /// Expiry of the access token as a unix timestamp. Can be removed after this
+    pub expires_at: u64,
+}
+
+impl RevokedToken {
+    pub const TABLE: &str = "revokedtokens";
+
+    pub fn new(id: Thing, user: Thing, expires_at: u64) -> RevokedToken {
+        RevokedToken {
+            id: Some(id),
+            user,
+            expires_at,
+        }
+    }
+}
+
+/// Access tokens of a user issued in an older generation are revoked. Stored using the user id
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TokenRevocation {
+    pub id: Option<Thing>,
+    /// Incremented every time all tokens of the user revoked
+    #[serde(default)]
+    pub generation: u64,
+}
+
+impl TokenRevocation {
+    pub const TABLE: &str = "tokenrevocations";
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Ticket {
     pub id: Option<Thing>,
@@ -9,22 +170,39 @@ pub struct Ticket {
     pub closed_at: Option<Datetime>,
     pub allow_connect_again: bool,
     pub user: Thing,
+    /// Session to reattach when connecting with this ticket
+    pub session: Option<Thing>,
+    /// Unix timestamp in seconds. The ticket can not be opened after this
+    pub expires_at: Option<u64>,
 }
 
 impl Ticket {
     pub const TABLE: &str = "tickets";
 
-    pub fn new(user: Thing) -> Ticket {
+    pub fn new(user: Thing, expires_at: u64) -> Ticket {
         Ticket {
             id: None,
             created_at: Datetime::default(),
             opened_at: None,
             closed_at: None,
             allow_connect_again: false,
-            user
+            user,
+            session: None,
+            expires_at: Some(expires_at),
         }
     }
 
+    /// Creating a ticket to reattach to an existing session after a connection drop
+    ///
+    /// Resumable tickets can be opened many times until they expire. They are removed earlier
+    /// when the session closed or a new ticket issued for the session.
+    pub fn new_resumable(user: Thing, session: Thing, expires_at: u64) -> Ticket {
+        let mut ticket = Ticket::new(user, expires_at);
+        ticket.session = Some(session);
+        ticket.make_allow_connect_again();
+        ticket
+    }
+
     pub fn make_allow_connect_again(&mut self) {
         self.allow_connect_again = true;
     }
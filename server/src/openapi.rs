@@ -0,0 +1,222 @@
+//! OpenAPI 3 document of the REST API
+//!
+//! Generated from the `utoipa::path` attributes of the handlers and the types in the `rest`
+//! crate. Served at `/api/openapi.json`, so the third parties can generate their clients.
+
+use hyper::{header::CONTENT_TYPE, Body, Request, Response};
+use once_cell::sync::Lazy;
+use rest::{
+    audit::{AuditAction, AuditEventResponse, AuditPageResponse},
+    auth::{
+        LoginRequest, LogoutRequest, RefreshRequest, RegisterRequest, RegisterResponse,
+        TicketResponse, TokenResponse,
+    },
+    projects::{
+        CurrentTabSnapshotResponse, OxdUploadSuccessResponse, ProjectPage, ProjectSummary,
+        RenameProjectRequest, RenameProjectResponse, Role,
+    },
+    sharing::{ChangeRoleRequest, InvitationResponse, InviteRequest, ProjectMemberResponse},
+    system::{ReadinessResponse, WebConfig},
+};
+use utoipa::{
+    openapi::{
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        Server,
+    },
+    Modify, OpenApi, ToSchema,
+};
+
+use crate::{config::config, error::Error, storage::StorageError};
+
+/// Name of the security scheme used by the `security` of the paths
+const BEARER_AUTH: &str = "bearer";
+
+/// Multipart body to upload an oxd file. `project_name` should be sent before the `file`
+#[derive(ToSchema)]
+// Only describing the request. Fields are read by `oxd_upload_handler` one by one
+#[allow(dead_code)]
+pub struct CreateProjectForm {
+    project_name: String,
+    /// Content of the oxd file
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+/// Access tokens from `/api/auth/login` in the `Authorization: Bearer <token>` header
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                BEARER_AUTH,
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .bearer_format("JWT")
+                        .build(),
+                ),
+            );
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "OpenXD",
+        description = "REST API of the OpenXD server. Errors are responded with a plain text \
+            code in the body. Eg:- `UNAUTHORIZED`. The editor itself is using a WebSocket \
+            connection opened with a ticket from `/api/auth/ticket`, which is not described here."
+    ),
+    paths(
+        crate::auth::register_handler,
+        crate::auth::login_handler,
+        crate::auth::refresh_handler,
+        crate::auth::logout_handler,
+        crate::auth::logout_all_handler,
+        crate::auth::ticket_handler,
+        crate::oidc::oidc_login_handler,
+        crate::oidc::oidc_callback_handler,
+        crate::projects::list_projects_handler,
+        crate::projects::rename_project_handler,
+        crate::projects::delete_project_handler,
+        crate::projects::project_by_slug_handler,
+        crate::sharing::members_handler,
+        crate::sharing::change_role_handler,
+        crate::sharing::remove_member_handler,
+        crate::sharing::invite_handler,
+        crate::sharing::invitations_handler,
+        crate::sharing::accept_invitation_handler,
+        crate::sharing::decline_invitation_handler,
+        crate::audit::audit_events_handler,
+        crate::oxd_upload_handler,
+        crate::current_tab_snapshot_handler,
+        crate::download_snapshot_handler,
+        crate::web_config_handler,
+        crate::health::healthz_handler,
+        crate::health::readyz_handler,
+    ),
+    components(schemas(
+        RegisterRequest,
+        RegisterResponse,
+        LoginRequest,
+        RefreshRequest,
+        LogoutRequest,
+        TokenResponse,
+        TicketResponse,
+        Role,
+        ProjectSummary,
+        ProjectPage,
+        RenameProjectRequest,
+        RenameProjectResponse,
+        OxdUploadSuccessResponse,
+        CurrentTabSnapshotResponse,
+        ProjectMemberResponse,
+        InviteRequest,
+        ChangeRoleRequest,
+        InvitationResponse,
+        AuditAction,
+        AuditEventResponse,
+        AuditPageResponse,
+        WebConfig,
+        ReadinessResponse,
+    )),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "auth", description = "Accounts, tokens and the WebSocket tickets"),
+        (name = "projects", description = "Projects and the snapshot downloads"),
+        (name = "sharing", description = "Members and the invitations of the projects"),
+        (name = "admin", description = "Only for the users in `ADMIN_USER_IDS`"),
+        (name = "system", description = "Probes and the web config"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Serialized once. The server URL is taken from the config
+static DOCUMENT: Lazy<String> = Lazy::new(|| {
+    let mut openapi = ApiDoc::openapi();
+    openapi.servers = Some(vec![Server::new(config().public_api_url.as_str())]);
+    openapi
+        .to_json()
+        .expect("OpenAPI document should be serializable")
+});
+
+/// The OpenAPI document of this server
+pub async fn openapi_handler(_req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(DOCUMENT.as_str()))
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use rest::paths;
+
+    use super::*;
+
+    /// Path in the OpenAPI form. Eg:- `/api/projects/{projectId}`
+    fn openapi_path(route: &str) -> String {
+        route
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => format!("{{{}}}", name),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    #[test]
+    fn documents_every_route() {
+        let openapi = ApiDoc::openapi();
+        for route in [
+            paths::REGISTER,
+            paths::LOGIN,
+            paths::REFRESH,
+            paths::LOGOUT,
+            paths::LOGOUT_ALL,
+            paths::OIDC_LOGIN,
+            paths::OIDC_CALLBACK,
+            paths::TICKET,
+            paths::PROJECTS,
+            paths::PROJECT,
+            paths::PROJECT_MEMBERS,
+            paths::PROJECT_MEMBER,
+            paths::PROJECT_INVITATIONS,
+            paths::PROJECT_BY_SLUG,
+            paths::INVITATIONS,
+            paths::ACCEPT_INVITATION,
+            paths::DECLINE_INVITATION,
+            paths::AUDIT_EVENTS,
+            paths::CREATE_PROJECT,
+            paths::CURRENT_TAB_SNAPSHOT,
+            paths::SNAPSHOT,
+            paths::WEB_CONFIG,
+            paths::HEALTHZ,
+            paths::READYZ,
+        ] {
+            assert!(
+                openapi.paths.paths.contains_key(&openapi_path(route)),
+                "{} is not documented",
+                route
+            );
+        }
+    }
+
+    #[test]
+    fn references_only_the_listed_schemas() {
+        let openapi = ApiDoc::openapi();
+        let schemas = &openapi.components.as_ref().unwrap().schemas;
+        let json = openapi.to_json().unwrap();
+        for reference in json.split("\"#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(
+                schemas.contains_key(name),
+                "{} is not in the components",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Endpoints to browse, rename and delete the projects
+
+use std::collections::HashMap;
+
+use app::{
+    model::{thing, AuditAction, Project},
+    projects::{self, delete_project, find_project_by_slug, list_projects, rename_project},
+};
+use hyper::{Body, Request, Response, StatusCode};
+use openidconnect::url::form_urlencoded;
+use rest::projects::{ProjectPage, ProjectSummary, RenameProjectRequest, RenameProjectResponse};
+use routerify::prelude::RequestExt;
+
+use crate::{
+    audit::{self, request_event},
+    auth::{json_body, json_response},
+    error::Error,
+    get_app, get_db, get_storage,
+    storage::StorageError,
+    UserId,
+};
+
+/// Projects in a page when the `per_page` parameter not provided
+const DEFAULT_PER_PAGE: usize = 20;
+
+fn summary_response(summary: projects::ProjectSummary) -> ProjectSummary {
+    ProjectSummary {
+        id: summary.id,
+        name: summary.name,
+        slug: summary.slug,
+        owner: summary.owner,
+        role: summary.role,
+        created_at: summary.created_at.to_raw(),
+        last_opened_at: summary.last_opened_at.map(|opened_at| opened_at.to_raw()),
+    }
+}
+
+fn page_response(page: projects::ProjectPage) -> ProjectPage {
+    ProjectPage {
+        projects: page.projects.into_iter().map(summary_response).collect(),
+        total: page.total,
+        page: page.page,
+        per_page: page.per_page,
+    }
+}
+
+/// Listing the projects of the user
+///
+/// Query parameters:- `search`, `page` (starting from 1) and `per_page`.
+#[utoipa::path(
+    get,
+    path = "/api/projects",
+    tag = "projects",
+    params(
+        ("search" = Option<String>, Query, description = "Part of the project name"),
+        ("page" = Option<usize>, Query, description = "Starting from 1"),
+        ("per_page" = Option<usize>, Query, description = "20 by default"),
+    ),
+    responses(
+        (status = 200, body = ProjectPage),
+        (status = 401, description = "UNAUTHORIZED"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_projects_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let params: HashMap<String, String> =
+        form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+            .into_owned()
+            .collect();
+    let page = params
+        .get("page")
+        .and_then(|page| page.parse().ok())
+        .unwrap_or(1);
+    let per_page = params
+        .get("per_page")
+        .and_then(|per_page| per_page.parse().ok())
+        .unwrap_or(DEFAULT_PER_PAGE);
+
+    let projects = list_projects(
+        get_db().await,
+        &user_id.0,
+        params.get("search").cloned(),
+        page,
+        per_page,
+    )
+    .await?;
+    Ok(json_response(StatusCode::OK, &page_response(projects)))
+}
+
+/// Finding a project by the owner id and the slug
+#[utoipa::path(
+    get,
+    path = "/api/projects/{ownerId}/{slug}",
+    tag = "projects",
+    params(
+        ("ownerId" = String, Path, description = "Id of the user who owns the project"),
+        ("slug" = String, Path, description = "Slug of the project name"),
+    ),
+    responses(
+        (status = 200, body = ProjectSummary),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 404, description = "PROJECT NOT FOUND"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn project_by_slug_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let owner_id = req.param("ownerId").unwrap();
+    let slug = req.param("slug").unwrap();
+    let project = find_project_by_slug(get_db().await, owner_id, slug, &user_id.0).await?;
+    Ok(json_response(StatusCode::OK, &summary_response(project)))
+}
+
+/// Renaming a project. The slug is changed with the name
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{projectId}",
+    tag = "projects",
+    params(("projectId" = String, Path, description = "Id of the project")),
+    request_body = RenameProjectRequest,
+    responses(
+        (status = 200, body = RenameProjectResponse),
+        (status = 400, description = "VALIDATION ERROR"),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 403, description = "FORBIDDEN"),
+        (status = 404, description = "PROJECT NOT FOUND"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn rename_project_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let project_id = req.param("projectId").unwrap().clone();
+    let body: RenameProjectRequest = json_body(req).await?;
+
+    let events = get_app().await.events();
+    let project =
+        rename_project(get_db().await, &events, &project_id, &user_id.0, body.name).await?;
+    Ok(json_response(
+        StatusCode::OK,
+        &RenameProjectResponse {
+            id: project.id.unwrap().id.to_raw(),
+            name: project.name,
+            slug: project.slug,
+        },
+    ))
+}
+
+/// Deleting a project with all of its data
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{projectId}",
+    tag = "projects",
+    params(("projectId" = String, Path, description = "Id of the project")),
+    responses(
+        (status = 204),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 403, description = "FORBIDDEN"),
+        (status = 404, description = "PROJECT NOT FOUND"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete_project_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let project_id = req.param("projectId").unwrap();
+    delete_project(
+        get_db().await,
+        get_storage().as_ref(),
+        project_id,
+        &user_id.0,
+    )
+    .await?;
+    audit::record(
+        request_event(&req, AuditAction::ProjectDeleted, &user_id.0)
+            .target(thing(Project::TABLE, project_id.as_str())),
+    )
+    .await;
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
@@ -0,0 +1,264 @@
+//! Token bucket rate limits for the HTTP requests and the WebSocket messages
+//!
+//! Buckets are kept in the memory of each server. So the effective limit is multiplied by
+//! the count of servers behind the load balancer.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use hyper::{Body, Method, Request};
+use once_cell::sync::Lazy;
+use rest::paths;
+use routerify::{prelude::RequestExt, Middleware};
+
+use crate::{config::config, error::Error, route_matches, storage::StorageError, UserId};
+
+/// Tokens taken by the most expensive request. Bursts should be at least this
+pub const MAX_REQUEST_COST: u32 = 20;
+
+/// Tokens taken by the requests that are expensive for the server. Other requests take one
+const EXPENSIVE_ROUTES: [(&str, u32); 5] = [
+    (paths::REGISTER, 10),
+    (paths::LOGIN, 10),
+    (paths::TICKET, 10),
+    (paths::CREATE_PROJECT, MAX_REQUEST_COST),
+    (paths::SNAPSHOT, 10),
+];
+
+/// Tokens taken to open a WebSocket connection
+const WS_OPEN_COST: u32 = 10;
+
+/// Routes that are not doing any work. Others, including `/readyz`, are limited
+const UNLIMITED_ROUTES: [&str; 1] = [paths::HEALTHZ];
+
+static IP_LIMITER: Lazy<RateLimiter> = Lazy::new(|| {
+    RateLimiter::new(
+        config().rate_limit_ip_per_minute as f64 / 60.0,
+        config().rate_limit_ip_burst as f64,
+    )
+});
+
+static USER_LIMITER: Lazy<RateLimiter> = Lazy::new(|| {
+    RateLimiter::new(
+        config().rate_limit_user_per_minute as f64 / 60.0,
+        config().rate_limit_user_burst as f64,
+    )
+});
+
+pub struct TokenBucket {
+    /// Tokens added in a second
+    rate: f64,
+    /// Maximum tokens in the bucket
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// A bucket that is full at the start
+    pub fn new(rate: f64, capacity: f64) -> TokenBucket {
+        TokenBucket {
+            rate,
+            capacity,
+            tokens: capacity,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// Taking the tokens if available. Returning the time to wait for them otherwise
+    pub fn try_take_at(&mut self, cost: f64, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= cost {
+            self.tokens -= cost;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((cost - self.tokens) / self.rate))
+        }
+    }
+
+    pub fn try_take(&mut self, cost: f64) -> Result<(), Duration> {
+        self.try_take_at(cost, Instant::now())
+    }
+
+    fn is_full_at(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.capacity
+    }
+}
+
+/// Buckets by a key. Eg:- IP address or user id
+struct RateLimiter {
+    rate: f64,
+    capacity: f64,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    fn new(rate: f64, capacity: f64) -> RateLimiter {
+        RateLimiter {
+            rate,
+            capacity,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn check(&self, key: String, cost: f64) -> Result<(), Duration> {
+        self.buckets
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| TokenBucket::new(self.rate, self.capacity))
+            .try_take(cost)
+    }
+
+    /// Removing the buckets that are full again. Those are same as the new buckets
+    fn purge_idle(&self) -> usize {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let before = buckets.len();
+        buckets.retain(|_, bucket| !bucket.is_full_at(now));
+        before - buckets.len()
+    }
+}
+
+/// Removing the buckets of the clients that stopped sending requests
+pub fn purge_idle_buckets() -> usize {
+    IP_LIMITER.purge_idle() + USER_LIMITER.purge_idle()
+}
+
+/// Address of the client. The `X-Forwarded-For` address is used behind a trusted proxy
+pub fn client_ip(req: &Request<Body>) -> IpAddr {
+    if config().rate_limit_trust_forwarded {
+        let forwarded = req
+            .headers()
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(forwarded_ip);
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+    req.remote_addr().ip()
+}
+
+/// Address that the trusted proxy appended to the `X-Forwarded-For` header
+///
+/// Clients can send the header with any addresses. So only the right-most one is trusted.
+fn forwarded_ip(header: &str) -> Option<IpAddr> {
+    header
+        .rsplit(',')
+        .next()
+        .and_then(|ip| ip.trim().parse().ok())
+}
+
+fn request_cost(path: &str) -> u32 {
+    if path == config().ws_path {
+        return WS_OPEN_COST;
+    }
+    EXPENSIVE_ROUTES
+        .iter()
+        .find(|(route, _)| route_matches(route, path))
+        .map(|(_, cost)| *cost)
+        .unwrap_or(1)
+}
+
+/// Whether the request is counted and the tokens it takes
+fn limited_cost(req: &Request<Body>) -> Option<f64> {
+    let path = req.uri().path();
+    if req.method() == Method::OPTIONS || UNLIMITED_ROUTES.contains(&path) {
+        None
+    } else {
+        Some(request_cost(path) as f64)
+    }
+}
+
+/// Limiting the requests per client IP
+///
+/// Should be added before `api_auth` to not verify the tokens of the limited clients.
+pub fn ip_rate_limit() -> Middleware<Body, Error<StorageError>> {
+    Middleware::pre(|req| async move {
+        if let Some(cost) = limited_cost(&req) {
+            IP_LIMITER
+                .check(client_ip(&req).to_string(), cost)
+                .map_err(|retry_after| Error::RateLimited { retry_after })?;
+        }
+        Ok(req)
+    })
+}
+
+/// Limiting the requests per user from all addresses
+///
+/// Should be added after `api_auth` to know the user of the request.
+pub fn user_rate_limit() -> Middleware<Body, Error<StorageError>> {
+    Middleware::pre(|req| async move {
+        if let (Some(cost), Some(user_id)) = (limited_cost(&req), req.context::<UserId>()) {
+            USER_LIMITER
+                .check(user_id.0, cost)
+                .map_err(|retry_after| Error::RateLimited { retry_after })?;
+        }
+        Ok(req)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_tokens_until_empty_and_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, 4.0);
+        assert!(bucket.try_take_at(3.0, start).is_ok());
+        assert!(bucket.try_take_at(1.0, start).is_ok());
+
+        let retry_after = bucket.try_take_at(1.0, start).unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(500));
+
+        assert!(bucket.try_take_at(1.0, start + Duration::from_millis(500)).is_ok());
+    }
+
+    #[test]
+    fn uses_the_address_appended_by_the_proxy() {
+        assert_eq!(forwarded_ip("203.0.113.7"), "203.0.113.7".parse().ok());
+        assert_eq!(
+            forwarded_ip("1.1.1.1, 10.0.0.1 , 203.0.113.7"),
+            "203.0.113.7".parse().ok()
+        );
+        assert_eq!(forwarded_ip("203.0.113.7, spoofed"), None);
+    }
+
+    #[test]
+    fn does_not_refill_over_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10.0, 2.0);
+        let later = start + Duration::from_secs(60);
+        assert!(bucket.is_full_at(later));
+        assert!(bucket.try_take_at(2.0, later).is_ok());
+        assert!(bucket.try_take_at(1.0, later).is_err());
+    }
+
+    #[test]
+    fn only_the_liveness_probe_is_not_limited() {
+        let request = |method: Method, path: &str| {
+            Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert_eq!(limited_cost(&request(Method::GET, paths::HEALTHZ)), None);
+        assert_eq!(limited_cost(&request(Method::OPTIONS, paths::LOGIN)), None);
+        // Readiness probe is writing to the storage
+        assert!(!UNLIMITED_ROUTES.contains(&paths::READYZ));
+    }
+}
@@ -0,0 +1,262 @@
+//! Endpoints to share the projects with other users
+//!
+//! Permission checks are done by `app::access`, so the editor and these endpoints are
+//! agreeing on who can access a project.
+
+use app::{
+    access::{
+        change_member_role, invite_to_project, pending_invitations, project_members,
+        remove_member, respond_to_invitation,
+    },
+    model::{thing, AuditAction, Project, ProjectInvitation, Role},
+};
+use hyper::{Body, Request, Response, StatusCode};
+use rest::sharing::{ChangeRoleRequest, InvitationResponse, InviteRequest, ProjectMemberResponse};
+use routerify::prelude::RequestExt;
+
+use crate::{
+    audit::{self, request_event},
+    auth::{json_body, json_response},
+    error::Error,
+    get_db,
+    storage::StorageError,
+    UserId,
+};
+
+fn invitation_response(invitation: ProjectInvitation) -> InvitationResponse {
+    InvitationResponse {
+        invitation_id: invitation.id.unwrap().id.to_raw(),
+        project_id: invitation.project.id.to_raw(),
+        role: invitation.role,
+        invited_by: invitation.invited_by.id.to_raw(),
+    }
+}
+
+/// Same name as in the requests. Eg:- `editor`
+fn role_name(role: Role) -> String {
+    serde_json::to_value(role)
+        .ok()
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+fn no_content() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Listing the users who can access the project with their roles
+#[utoipa::path(
+    get,
+    path = "/api/projects/{projectId}/members",
+    tag = "sharing",
+    params(("projectId" = String, Path, description = "Id of the project")),
+    responses(
+        (status = 200, body = [ProjectMemberResponse]),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 404, description = "PROJECT NOT FOUND"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn members_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let project_id = req.param("projectId").unwrap();
+    let members: Vec<ProjectMemberResponse> =
+        project_members(get_db().await, project_id, &user_id.0)
+            .await?
+            .into_iter()
+            .map(|access| ProjectMemberResponse {
+                user_id: access.user_id,
+                role: access.role,
+            })
+            .collect();
+    Ok(json_response(StatusCode::OK, &members))
+}
+
+/// Inviting a user to the project. Only the owner can invite
+#[utoipa::path(
+    post,
+    path = "/api/projects/{projectId}/invitations",
+    tag = "sharing",
+    params(("projectId" = String, Path, description = "Id of the project")),
+    request_body = InviteRequest,
+    responses(
+        (status = 201, body = InvitationResponse),
+        (status = 400, description = "VALIDATION ERROR"),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 403, description = "FORBIDDEN"),
+        (status = 404, description = "PROJECT NOT FOUND"),
+        (status = 409, description = "ALREADY A MEMBER"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn invite_handler(req: Request<Body>) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let project_id = req.param("projectId").unwrap().clone();
+    let event = request_event(&req, AuditAction::MemberInvited, &user_id.0);
+    let body: InviteRequest = json_body(req).await?;
+
+    let invitation =
+        invite_to_project(get_db().await, &project_id, &user_id.0, &body.user_id, body.role)
+            .await?;
+    audit::record(
+        event
+            .target(invitation.project.clone())
+            .detail("user", &body.user_id)
+            .detail("role", role_name(body.role)),
+    )
+    .await;
+    Ok(json_response(
+        StatusCode::CREATED,
+        &invitation_response(invitation),
+    ))
+}
+
+/// Listing the invitations that the user not responded yet
+#[utoipa::path(
+    get,
+    path = "/api/invitations",
+    tag = "sharing",
+    responses(
+        (status = 200, body = [InvitationResponse]),
+        (status = 401, description = "UNAUTHORIZED"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn invitations_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let invitations: Vec<InvitationResponse> = pending_invitations(get_db().await, &user_id.0)
+        .await?
+        .into_iter()
+        .map(invitation_response)
+        .collect();
+    Ok(json_response(StatusCode::OK, &invitations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/invitations/{invitationId}/accept",
+    tag = "sharing",
+    params(("invitationId" = String, Path, description = "Id of the invitation")),
+    responses(
+        (status = 204),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 404, description = "NOT FOUND"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn accept_invitation_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let invitation_id = req.param("invitationId").unwrap();
+    respond_to_invitation(get_db().await, invitation_id, &user_id.0, true).await?;
+    audit::record(
+        request_event(&req, AuditAction::InvitationAccepted, &user_id.0)
+            .target(thing(ProjectInvitation::TABLE, invitation_id.as_str())),
+    )
+    .await;
+    Ok(no_content())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/invitations/{invitationId}/decline",
+    tag = "sharing",
+    params(("invitationId" = String, Path, description = "Id of the invitation")),
+    responses(
+        (status = 204),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 404, description = "NOT FOUND"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn decline_invitation_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let invitation_id = req.param("invitationId").unwrap();
+    respond_to_invitation(get_db().await, invitation_id, &user_id.0, false).await?;
+    audit::record(
+        request_event(&req, AuditAction::InvitationDeclined, &user_id.0)
+            .target(thing(ProjectInvitation::TABLE, invitation_id.as_str())),
+    )
+    .await;
+    Ok(no_content())
+}
+
+/// Changing the role of a member. Only the owner can change the roles
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{projectId}/members/{userId}",
+    tag = "sharing",
+    params(
+        ("projectId" = String, Path, description = "Id of the project"),
+        ("userId" = String, Path, description = "Id of the member"),
+    ),
+    request_body = ChangeRoleRequest,
+    responses(
+        (status = 204),
+        (status = 400, description = "VALIDATION ERROR"),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 403, description = "FORBIDDEN"),
+        (status = 404, description = "NOT FOUND"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn change_role_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let project_id = req.param("projectId").unwrap().clone();
+    let member_id = req.param("userId").unwrap().clone();
+    let event = request_event(&req, AuditAction::MemberRoleChanged, &user_id.0);
+    let body: ChangeRoleRequest = json_body(req).await?;
+
+    change_member_role(get_db().await, &project_id, &user_id.0, &member_id, body.role).await?;
+    audit::record(
+        event
+            .target(thing(Project::TABLE, project_id))
+            .detail("user", member_id)
+            .detail("role", role_name(body.role)),
+    )
+    .await;
+    Ok(no_content())
+}
+
+/// Removing a member. Members can also use this to leave the project
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{projectId}/members/{userId}",
+    tag = "sharing",
+    params(
+        ("projectId" = String, Path, description = "Id of the project"),
+        ("userId" = String, Path, description = "Id of the member"),
+    ),
+    responses(
+        (status = 204),
+        (status = 401, description = "UNAUTHORIZED"),
+        (status = 403, description = "FORBIDDEN"),
+        (status = 404, description = "NOT FOUND"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn remove_member_handler(
+    req: Request<Body>,
+) -> Result<Response<Body>, Error<StorageError>> {
+    let user_id = req.context::<UserId>().unwrap();
+    let project_id = req.param("projectId").unwrap();
+    let member_id = req.param("userId").unwrap();
+    remove_member(get_db().await, project_id, &user_id.0, member_id).await?;
+    audit::record(
+        request_event(&req, AuditAction::MemberRemoved, &user_id.0)
+            .target(thing(Project::TABLE, project_id.as_str()))
+            .detail("user", member_id),
+    )
+    .await;
+    Ok(no_content())
+}
@@ -0,0 +1,110 @@
+//! Graceful shutdown of the server
+//!
+//! On SIGTERM (or Ctrl+C) the server stops issuing tickets and asks every WebSocket connection
+//! to close. Sessions are finishing the message that they are handling, notifying the client
+//! with a `SessionClosing` message and closing the connection. The sessions are kept open in
+//! the DB, so the users can reattach to them after the server restarted.
+//!
+//! Sessions that did not finish within the shutdown timeout are closed in the DB.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use app::model::Session;
+use log::{info, warn};
+use surrealdb::sql::Thing;
+use tokio::time::{sleep, Instant};
+
+use crate::{config::config, get_db, live};
+
+/// Reason sent to the clients with the `SessionClosing` message
+const SHUTDOWN_REASON: &str = "Server is restarting. Please reconnect.";
+
+/// Interval to check whether all sessions are closed
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+/// Whether the server is shutting down. New connections should be rejected
+pub fn is_draining() -> bool {
+    DRAINING.load(Ordering::Relaxed)
+}
+
+async fn terminate_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM:- {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    std::future::pending::<()>().await;
+}
+
+/// Waiting for a shutdown signal and starting to drain the sessions
+///
+/// Resolves after the sessions are requested to close, so the HTTP server can stop
+/// accepting the connections.
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C:- {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    tokio::select! {
+        _ = terminate_signal() => {}
+        _ = ctrl_c => {}
+    }
+    DRAINING.store(true, Ordering::Relaxed);
+    let closing = live::close_all(SHUTDOWN_REASON);
+    info!("Shutting down. Disconnecting {} session(s)", closing);
+}
+
+/// Waiting until all connections are closed or the shutdown timeout is exceeded
+///
+/// The server does not track the upgraded WebSocket connections. So this should be awaited
+/// after the server stopped, before returning from `main`. Sessions of the connections that
+/// are still open after the timeout are closed in the DB.
+pub async fn drain_sessions() {
+    let deadline = Instant::now() + Duration::from_secs(config().shutdown_timeout);
+    loop {
+        let remaining = live::connection_count();
+        if remaining == 0 {
+            info!("All connections are closed");
+            return;
+        }
+        if Instant::now() >= deadline {
+            warn!(
+                "{} session(s) not closed within the shutdown timeout. Closing those in the DB",
+                remaining
+            );
+            if let Err(e) = close_sessions(live::sessions()).await {
+                warn!("Failed to close the remaining sessions:- {:?}", e);
+            }
+            return;
+        }
+        sleep(DRAIN_POLL_INTERVAL).await;
+    }
+}
+
+/// Marking the sessions as closed. Their connections may be still handling a message
+async fn close_sessions(sessions: Vec<Thing>) -> Result<(), surrealdb::Error> {
+    let mut closed_res = get_db()
+        .await
+        .query("UPDATE type::table($table) SET closed_at = time::now() WHERE id IN $sessions AND closed_at IS none")
+        .bind(("table", Session::TABLE))
+        .bind(("sessions", sessions))
+        .await?;
+    let _closed: Vec<Session> = closed_res.take(0)?;
+    Ok(())
+}
@@ -1,12 +1,12 @@
-#[cfg(feature = "storage-fs")]
-pub mod fs;
 #[cfg(feature = "storage-s3")]
 pub mod s3;
+#[cfg(feature = "storage-s3")]
+mod sigv4;
 
 #[cfg(feature = "storage-fs")]
-pub use fs::FileSystemStorage as StorageImpl;
+pub use app::storage::fs::FileSystemStorage as StorageImpl;
 #[cfg(feature = "storage-fs")]
-pub use fs::StorageError;
+pub use app::storage::fs::StorageError;
 #[cfg(feature = "storage-fs")]
 pub use std::path::PathBuf as StorageId;
 #[cfg(feature = "storage-s3")]
@@ -15,3 +15,15 @@ pub use s3::S3Storage as StorageImpl;
 pub use s3::StorageError;
 #[cfg(feature = "storage-s3")]
 pub use s3::S3Key as StorageId;
+
+/// Storage selected by the cargo features
+#[cfg(feature = "storage-fs")]
+pub fn new_storage() -> StorageImpl {
+    StorageImpl::new(crate::config::config().storage_fs_root.clone())
+}
+
+/// Storage selected by the cargo features
+#[cfg(feature = "storage-s3")]
+pub fn new_storage() -> StorageImpl {
+    StorageImpl::from_config(crate::config::config())
+}
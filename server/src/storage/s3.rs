@@ -1,3 +1,769 @@
-pub struct S3Storage;
+//! Amazon S3 and the compatible services as the storage. Eg:- MinIO
+//!
+//! Objects are uploaded in parts with the multipart uploads, so the whole file is never kept in
+//! the memory. Downloads are streamed from the response body.
 
-pub struct S3Key;
+use std::{io, path::Path};
+
+use app::storage::{Storage, StorageObjInfo};
+use async_trait::async_trait;
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use hyper::body::Bytes;
+use log::warn;
+use reqwest::{header::CONTENT_LENGTH, Client, Method, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::StreamReader;
+use uuid::Uuid;
+
+use crate::config::Config;
+
+use super::sigv4::{self, Credentials, SigningRequest};
+
+/// Bytes in a MiB. Part sizes are configured in MiB
+const MIB: usize = 1024 * 1024;
+
+pub struct S3Settings {
+    pub bucket: String,
+    /// Prepended to the keys of all objects. Eg:- `openxd/`
+    pub prefix: String,
+    pub region: String,
+    /// Eg:- `https://s3.us-east-1.amazonaws.com` or `http://localhost:9000`
+    pub endpoint: Url,
+    /// Sending the bucket in the path instead of the host name. Most S3 compatible services
+    /// are expecting this
+    pub path_style: bool,
+    pub credentials: Credentials,
+    /// Bytes in an uploaded part, except the last part
+    pub part_size: usize,
+}
+
+pub struct S3Storage {
+    client: Client,
+    settings: S3Settings,
+}
+
+/// Key of an object in the bucket, with the prefix
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct S3Key(String);
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("could not send the request to S3. {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("S3 responded with {status}. {body}")]
+    Response { status: StatusCode, body: String },
+    #[error("failed to read or write some data")]
+    Io(#[from] io::Error),
+    #[error("S3 responded with an unexpected body. {0}")]
+    InvalidResponse(&'static str),
+}
+
+/// Text of the first element with the name. Eg:- `UploadId`
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    Some(
+        xml[start..end]
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Host with the port, only when the port is not the default of the scheme.
+/// Same as the `Host` header sent by the client
+fn authority(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// Reading until the part is full or the reader ended
+async fn read_part<I: AsyncRead + Unpin + Send>(
+    reader: &mut I,
+    part_size: usize,
+) -> Result<Vec<u8>, io::Error> {
+    let mut part = Vec::with_capacity(part_size);
+    reader.take(part_size as u64).read_to_end(&mut part).await?;
+    Ok(part)
+}
+
+impl S3Storage {
+    pub fn new(settings: S3Settings) -> S3Storage {
+        S3Storage {
+            client: Client::new(),
+            settings,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> S3Storage {
+        S3Storage::new(S3Settings {
+            bucket: config.storage_s3_bucket.clone(),
+            prefix: config.storage_s3_prefix.clone(),
+            region: config.storage_s3_region.clone(),
+            endpoint: Url::parse(&config.storage_s3_endpoint)
+                .expect("STORAGE_S3_ENDPOINT should be validated"),
+            path_style: config.storage_s3_path_style,
+            credentials: Credentials {
+                access_key_id: config.storage_s3_access_key_id.clone(),
+                secret_access_key: config.storage_s3_secret_access_key.clone(),
+                session_token: config.storage_s3_session_token.clone(),
+            },
+            part_size: config.storage_s3_part_size as usize * MIB,
+        })
+    }
+
+    fn url(&self, key: &str, query: &str) -> Url {
+        let endpoint = &self.settings.endpoint;
+        let host = authority(endpoint);
+        let key = sigv4::uri_encode(key, false);
+        let (host, path) = if self.settings.path_style {
+            (host, format!("/{}/{}", self.settings.bucket, key))
+        } else {
+            (
+                format!("{}.{}", self.settings.bucket, host),
+                format!("/{}", key),
+            )
+        };
+        let mut url = format!("{}://{}{}", endpoint.scheme(), host, path);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query);
+        }
+        Url::parse(&url).expect("S3 URL should be valid")
+    }
+
+    /// Signing and sending a request. Responses without a success status are returned as errors
+    ///
+    /// `body` should be `None` only for the requests that never have a body. Eg:- `GET`
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        amz_headers: &[(&str, String)],
+        body: Option<Vec<u8>>,
+    ) -> Result<Response, StorageError> {
+        let query = sigv4::canonical_query(query);
+        let url = self.url(key, &query);
+        let time = chrono::Utc::now();
+        let payload_hash = match &body {
+            Some(body) => sigv4::sha256_hex(body),
+            None => String::from(sigv4::EMPTY_PAYLOAD_HASH),
+        };
+
+        let mut headers = vec![
+            (String::from("host"), authority(&url)),
+            (String::from("x-amz-content-sha256"), payload_hash.clone()),
+            (String::from("x-amz-date"), sigv4::amz_date(time)),
+        ];
+        if let Some(session_token) = &self.settings.credentials.session_token {
+            headers.push((String::from("x-amz-security-token"), session_token.clone()));
+        }
+        headers.extend(
+            amz_headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone())),
+        );
+        let authorization = sigv4::authorization(
+            &self.settings.credentials,
+            &self.settings.region,
+            &SigningRequest {
+                method: method.as_str(),
+                path: url.path(),
+                query: &query,
+                headers: &headers,
+                payload_hash: &payload_hash,
+            },
+            time,
+        );
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("authorization", authorization);
+        // `host` is set by the client from the URL
+        for (name, value) in headers.into_iter().filter(|(name, _)| name != "host") {
+            request = request.header(name, value);
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(StorageError::Response {
+                status: response.status(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+        Ok(response)
+    }
+
+    /// Body of a response that can be an error even with the success status.
+    /// Eg:- CopyObject and CompleteMultipartUpload
+    async fn checked_body(response: Response) -> Result<String, StorageError> {
+        let status = response.status();
+        let body = response.text().await?;
+        if body.contains("<Error>") {
+            return Err(StorageError::Response { status, body });
+        }
+        Ok(body)
+    }
+
+    async fn upload_parts<I: AsyncRead + Unpin + Send>(
+        &self,
+        reader: &mut I,
+        key: &str,
+        upload_id: &str,
+        first_part: Vec<u8>,
+    ) -> Result<(), StorageError> {
+        let mut etags = vec![];
+        let mut part = first_part;
+        while !part.is_empty() {
+            let part_number = (etags.len() + 1).to_string();
+            let response = self
+                .send(
+                    Method::PUT,
+                    key,
+                    &[("partNumber", &part_number), ("uploadId", upload_id)],
+                    &[],
+                    Some(part),
+                )
+                .await?;
+            let etag = response
+                .headers()
+                .get("etag")
+                .and_then(|etag| etag.to_str().ok())
+                .ok_or(StorageError::InvalidResponse(
+                    "no ETag for the uploaded part",
+                ))?;
+            etags.push(etag.to_string());
+            part = read_part(reader, self.settings.part_size).await?;
+        }
+
+        let parts: String = etags
+            .iter()
+            .enumerate()
+            .map(|(i, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    i + 1,
+                    xml_escape(etag)
+                )
+            })
+            .collect();
+        let body = format!(
+            "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+            parts
+        );
+        let response = self
+            .send(
+                Method::POST,
+                key,
+                &[("uploadId", upload_id)],
+                &[],
+                Some(body.into_bytes()),
+            )
+            .await?;
+        S3Storage::checked_body(response).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage<StorageError, S3Key> for S3Storage {
+    type Read = StreamReader<BoxStream<'static, io::Result<Bytes>>, Bytes>;
+
+    /// Saving a file to storage
+    ///
+    /// Files smaller than a part are uploaded with a single request.
+    async fn put<'a, I: AsyncRead + Unpin + Send>(
+        &self,
+        reader: &'a mut I,
+        namespace: String,
+        ext: String,
+    ) -> Result<S3Key, StorageError> {
+        let key = format!(
+            "{}{}/{}.{}",
+            self.settings.prefix,
+            namespace,
+            Uuid::new_v4(),
+            ext
+        );
+        let first_part = read_part(reader, self.settings.part_size).await?;
+        if first_part.len() < self.settings.part_size {
+            self.send(Method::PUT, &key, &[], &[], Some(first_part))
+                .await?;
+            return Ok(S3Key(key));
+        }
+
+        let response = self
+            .send(Method::POST, &key, &[("uploads", "")], &[], Some(vec![]))
+            .await?;
+        let upload_id = xml_element(&response.text().await?, "UploadId").ok_or(
+            StorageError::InvalidResponse("no UploadId for the multipart upload"),
+        )?;
+        if let Err(e) = self
+            .upload_parts(reader, &key, &upload_id, first_part)
+            .await
+        {
+            // Otherwise the uploaded parts are kept and charged until a lifecycle rule removes
+            let aborted = self
+                .send(Method::DELETE, &key, &[("uploadId", &upload_id)], &[], None)
+                .await;
+            if let Err(abort_e) = aborted {
+                warn!(
+                    "Failed to abort the multipart upload of {}:- {:?}",
+                    key, abort_e
+                );
+            }
+            return Err(e);
+        }
+        Ok(S3Key(key))
+    }
+
+    /// Retrieving the saved file from the storage
+    async fn get(&self, key: S3Key) -> Result<Self::Read, StorageError> {
+        let response = self.send(Method::GET, &key.0, &[], &[], None).await?;
+        let stream = response.bytes_stream().map_err(io::Error::other).boxed();
+        Ok(StreamReader::new(stream))
+    }
+
+    /// Removing a saved file
+    async fn delete(&self, key: S3Key) -> Result<(), StorageError> {
+        self.send(Method::DELETE, &key.0, &[], &[], None).await?;
+        Ok(())
+    }
+
+    /// Retrieve the information of a storage object
+    async fn info(&self, key: S3Key) -> Result<StorageObjInfo, StorageError> {
+        let response = self.send(Method::HEAD, &key.0, &[], &[], None).await?;
+        // Not using the size hint of the body, since a HEAD response has no body
+        let size = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse().ok())
+            .ok_or(StorageError::InvalidResponse(
+                "no Content-Length for the object",
+            ))?;
+
+        Ok(StorageObjInfo {
+            ext: Path::new(&key.0)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(String::from),
+            size,
+        })
+    }
+
+    /// Duplicating a storage object. Copied by S3 without downloading
+    async fn duplicate(&self, key: S3Key) -> Result<S3Key, StorageError> {
+        let file_name = match Path::new(&key.0).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => format!("{}.{}", Uuid::new_v4(), ext),
+            None => Uuid::new_v4().to_string(),
+        };
+        let new_key = match key.0.rsplit_once('/') {
+            Some((dir, _)) => format!("{}/{}", dir, file_name),
+            None => file_name,
+        };
+        let copy_source = format!(
+            "/{}/{}",
+            self.settings.bucket,
+            sigv4::uri_encode(&key.0, false)
+        );
+        let response = self
+            .send(
+                Method::PUT,
+                &new_key,
+                &[],
+                &[("x-amz-copy-source", copy_source)],
+                Some(vec![]),
+            )
+            .await?;
+        S3Storage::checked_body(response).await?;
+        Ok(S3Key(new_key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        convert::Infallible,
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+    };
+
+    use chrono::{NaiveDateTime, TimeZone, Utc};
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Request, Server,
+    };
+
+    use super::*;
+
+    const BUCKET: &str = "openxd";
+
+    const PART_SIZE: usize = 5;
+
+    fn credentials() -> Credentials {
+        Credentials {
+            access_key_id: String::from("minio-access-key"),
+            secret_access_key: String::from("minio-secret-key"),
+            session_token: None,
+        }
+    }
+
+    /// Objects and the uploads of a stand-in for MinIO. Only serving the path style requests
+    #[derive(Default)]
+    struct MockS3 {
+        objects: HashMap<String, Vec<u8>>,
+        uploads: HashMap<String, BTreeMap<u32, Vec<u8>>>,
+        aborted: Vec<String>,
+        /// Responding to this part with an internal error
+        fail_part: Option<u32>,
+    }
+
+    /// Same verification as S3. Signature is computed again from the signed headers
+    fn verify_signature(req: &Request<Body>, body: &[u8]) -> bool {
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        if header("x-amz-content-sha256") != sigv4::sha256_hex(body) && !body.is_empty() {
+            return false;
+        }
+        let authorization = header("authorization");
+        let signed_headers = match authorization.split("SignedHeaders=").nth(1) {
+            Some(signed_headers) => signed_headers.split(',').next().unwrap(),
+            None => return false,
+        };
+        let headers: Vec<(String, String)> = signed_headers
+            .split(';')
+            .map(|name| (name.to_string(), header(name)))
+            .collect();
+        let time = match NaiveDateTime::parse_from_str(&header("x-amz-date"), "%Y%m%dT%H%M%SZ") {
+            Ok(time) => Utc.from_utc_datetime(&time),
+            Err(_) => return false,
+        };
+        let expected = sigv4::authorization(
+            &credentials(),
+            "us-east-1",
+            &SigningRequest {
+                method: req.method().as_str(),
+                path: req.uri().path(),
+                query: req.uri().query().unwrap_or_default(),
+                headers: &headers,
+                payload_hash: &header("x-amz-content-sha256"),
+            },
+            time,
+        );
+        authorization == expected
+    }
+
+    fn xml_response(status: StatusCode, xml: String) -> hyper::Response<Body> {
+        hyper::Response::builder()
+            .status(status)
+            .body(Body::from(xml))
+            .unwrap()
+    }
+
+    fn error_response(status: StatusCode, code: &str) -> hyper::Response<Body> {
+        xml_response(status, format!("<Error><Code>{}</Code></Error>", code))
+    }
+
+    async fn handle(state: Arc<Mutex<MockS3>>, req: Request<Body>) -> hyper::Response<Body> {
+        let (parts, body) = req.into_parts();
+        let body = hyper::body::to_bytes(body).await.unwrap().to_vec();
+        let req = Request::from_parts(parts, Body::empty());
+        if !verify_signature(&req, &body) {
+            return error_response(StatusCode::FORBIDDEN, "SignatureDoesNotMatch");
+        }
+
+        let key = match req.uri().path().strip_prefix(&format!("/{}/", BUCKET)) {
+            Some(key) => key.to_string(),
+            None => return error_response(StatusCode::NOT_FOUND, "NoSuchBucket"),
+        };
+        let query: HashMap<String, String> = req
+            .uri()
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .map(|param| {
+                let (name, value) = param.split_once('=').unwrap_or((param, ""));
+                (name.to_string(), value.replace("%26", "&"))
+            })
+            .collect();
+        let copy_source = req
+            .headers()
+            .get("x-amz-copy-source")
+            .map(|source| source.to_str().unwrap().to_string());
+
+        let mut state = state.lock().unwrap();
+        match (req.method().clone(), query.get("uploadId")) {
+            (Method::POST, None) if query.contains_key("uploads") => {
+                let upload_id = format!("upload&{}", state.uploads.len() + 1);
+                state.uploads.insert(upload_id.clone(), BTreeMap::new());
+                xml_response(
+                    StatusCode::OK,
+                    format!(
+                        "<InitiateMultipartUploadResult><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+                        xml_escape(&upload_id)
+                    ),
+                )
+            }
+            (Method::PUT, Some(upload_id)) => {
+                let part_number: u32 = query["partNumber"].parse().unwrap();
+                if state.fail_part == Some(part_number) {
+                    return error_response(StatusCode::INTERNAL_SERVER_ERROR, "InternalError");
+                }
+                match state.uploads.get_mut(upload_id) {
+                    Some(upload) => {
+                        upload.insert(part_number, body);
+                        hyper::Response::builder()
+                            .header("etag", format!("\"part-{}\"", part_number))
+                            .body(Body::empty())
+                            .unwrap()
+                    }
+                    None => error_response(StatusCode::NOT_FOUND, "NoSuchUpload"),
+                }
+            }
+            (Method::POST, Some(upload_id)) => {
+                let upload = match state.uploads.remove(upload_id) {
+                    Some(upload) => upload,
+                    None => return error_response(StatusCode::NOT_FOUND, "NoSuchUpload"),
+                };
+                let xml = String::from_utf8(body).unwrap();
+                let mut object = vec![];
+                for (i, part) in xml.split("<Part>").skip(1).enumerate() {
+                    let part_number: u32 =
+                        xml_element(part, "PartNumber").unwrap().parse().unwrap();
+                    let etag = xml_element(part, "ETag").unwrap();
+                    if part_number != i as u32 + 1 || etag != format!("\"part-{}\"", part_number) {
+                        // S3 is responding the errors of the completion with 200
+                        return error_response(StatusCode::OK, "InvalidPart");
+                    }
+                    object.extend(&upload[&part_number]);
+                }
+                state.objects.insert(key, object);
+                xml_response(
+                    StatusCode::OK,
+                    String::from("<CompleteMultipartUploadResult></CompleteMultipartUploadResult>"),
+                )
+            }
+            (Method::DELETE, Some(upload_id)) => {
+                state.uploads.remove(upload_id);
+                state.aborted.push(upload_id.clone());
+                hyper::Response::builder()
+                    .status(StatusCode::NO_CONTENT)
+                    .body(Body::empty())
+                    .unwrap()
+            }
+            (Method::PUT, None) => match copy_source {
+                Some(source) => {
+                    let source_key = source.strip_prefix(&format!("/{}/", BUCKET)).unwrap();
+                    match state.objects.get(source_key).cloned() {
+                        Some(object) => {
+                            state.objects.insert(key, object);
+                            xml_response(
+                                StatusCode::OK,
+                                String::from("<CopyObjectResult></CopyObjectResult>"),
+                            )
+                        }
+                        None => error_response(StatusCode::NOT_FOUND, "NoSuchKey"),
+                    }
+                }
+                None => {
+                    state.objects.insert(key, body);
+                    hyper::Response::new(Body::empty())
+                }
+            },
+            (Method::GET, None) => match state.objects.get(&key) {
+                Some(object) => hyper::Response::new(Body::from(object.clone())),
+                None => error_response(StatusCode::NOT_FOUND, "NoSuchKey"),
+            },
+            (Method::HEAD, None) => match state.objects.get(&key) {
+                Some(object) => hyper::Response::builder()
+                    .header(CONTENT_LENGTH, object.len())
+                    .body(Body::empty())
+                    .unwrap(),
+                None => hyper::Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::empty())
+                    .unwrap(),
+            },
+            (Method::DELETE, None) => {
+                state.objects.remove(&key);
+                hyper::Response::builder()
+                    .status(StatusCode::NO_CONTENT)
+                    .body(Body::empty())
+                    .unwrap()
+            }
+            _ => error_response(StatusCode::METHOD_NOT_ALLOWED, "MethodNotAllowed"),
+        }
+    }
+
+    async fn start_mock() -> (S3Storage, Arc<Mutex<MockS3>>) {
+        let state = Arc::new(Mutex::new(MockS3::default()));
+        let make_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = make_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(handle(state, req).await) }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let endpoint = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        let storage = S3Storage::new(S3Settings {
+            bucket: String::from(BUCKET),
+            prefix: String::from("test/"),
+            region: String::from("us-east-1"),
+            endpoint: Url::parse(&endpoint).unwrap(),
+            path_style: true,
+            credentials: credentials(),
+            part_size: PART_SIZE,
+        });
+        (storage, state)
+    }
+
+    async fn read_all(storage: &S3Storage, key: S3Key) -> Vec<u8> {
+        let mut object = vec![];
+        let mut reader = storage.get(key).await.unwrap();
+        reader.read_to_end(&mut object).await.unwrap();
+        object
+    }
+
+    #[test]
+    fn reads_xml_elements() {
+        let xml = "<Result><UploadId>a&amp;b&lt;c</UploadId></Result>";
+        assert_eq!(xml_element(xml, "UploadId"), Some(String::from("a&b<c")));
+        assert_eq!(xml_element(xml, "Key"), None);
+    }
+
+    #[tokio::test]
+    async fn puts_small_objects_in_one_request() {
+        let (storage, state) = start_mock().await;
+        let mut file: &[u8] = b"tiny";
+        let key = storage
+            .put(&mut file, String::from("assets"), String::from("png"))
+            .await
+            .unwrap();
+
+        assert!(key.0.starts_with("test/assets/") && key.0.ends_with(".png"));
+        assert!(state.lock().unwrap().uploads.is_empty());
+        assert_eq!(read_all(&storage, key.clone()).await, b"tiny");
+        let info = storage.info(key).await.unwrap();
+        assert_eq!(info.ext.as_deref(), Some("png"));
+        assert_eq!(info.size, 4);
+    }
+
+    #[tokio::test]
+    async fn puts_large_objects_in_parts() {
+        let (storage, state) = start_mock().await;
+        let content = b"0123456789abcdefghij-last".to_vec();
+        let key = storage
+            .put(
+                &mut content.as_slice(),
+                String::from("assets"),
+                String::from("svg"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(state.lock().unwrap().objects[&key.0], content);
+        assert_eq!(read_all(&storage, key).await, content);
+    }
+
+    #[tokio::test]
+    async fn aborts_failed_multipart_uploads() {
+        let (storage, state) = start_mock().await;
+        state.lock().unwrap().fail_part = Some(2);
+        let content = b"0123456789abcdefghij".to_vec();
+        let result = storage
+            .put(
+                &mut content.as_slice(),
+                String::from("assets"),
+                String::from("svg"),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(StorageError::Response {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                ..
+            })
+        ));
+        let state = state.lock().unwrap();
+        assert_eq!(state.aborted, vec![String::from("upload&1")]);
+        assert!(state.uploads.is_empty());
+        assert!(state.objects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn duplicates_and_deletes_objects() {
+        let (storage, state) = start_mock().await;
+        let mut file: &[u8] = b"copy";
+        let key = storage
+            .put(&mut file, String::from("assets"), String::from("jpg"))
+            .await
+            .unwrap();
+        let copy = storage.duplicate(key.clone()).await.unwrap();
+
+        assert_ne!(copy, key);
+        assert!(copy.0.starts_with("test/assets/") && copy.0.ends_with(".jpg"));
+        assert_eq!(read_all(&storage, copy.clone()).await, b"copy");
+
+        storage.delete(key.clone()).await.unwrap();
+        assert!(!state.lock().unwrap().objects.contains_key(&key.0));
+        assert!(matches!(
+            storage.info(key).await,
+            Err(StorageError::Response {
+                status: StatusCode::NOT_FOUND,
+                ..
+            })
+        ));
+        assert!(state.lock().unwrap().objects.contains_key(&copy.0));
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_credentials() {
+        let (mut storage, _) = start_mock().await;
+        storage.settings.credentials.secret_access_key = String::from("wrong");
+        let mut file: &[u8] = b"denied";
+        let result = storage
+            .put(&mut file, String::from("assets"), String::from("png"))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(StorageError::Response {
+                status: StatusCode::FORBIDDEN,
+                ..
+            })
+        ));
+    }
+}
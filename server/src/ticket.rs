@@ -0,0 +1,198 @@
+//! State of the WebSocket tickets
+//!
+//! A one-time ticket can be opened once within `TICKET_TTL` seconds from creation. A
+//! resumable ticket can be opened again after the previous connection with it closed, until
+//! the session is abandoned. Only the latest resumable ticket of a session can be opened.
+
+use app::model::{thing, User};
+use log::warn;
+use surrealdb::sql::Thing;
+
+use crate::{
+    auth::now_secs,
+    config::config,
+    error::{Error, WebSocketOpenError},
+    get_db,
+    model::Ticket,
+    storage::StorageError,
+};
+
+/// Raw id of the user that the ticket issued to. User ids are not having a fixed length
+pub fn ticket_user_id(ticket: &Ticket) -> String {
+    ticket.user.id.to_raw()
+}
+
+/// Expiry of a new one-time ticket as a unix timestamp
+pub fn ticket_expiry() -> u64 {
+    now_secs() + config().ticket_ttl
+}
+
+/// Expiry of a new resumable ticket. Sessions are closed after abandoned for this time anyway
+fn resume_ticket_expiry() -> u64 {
+    now_secs() + config().session_abandon_timeout
+}
+
+/// Issuing a new resumable ticket for the session and removing the previous ones
+///
+/// So a leaked ticket can not be used after the user reconnected. Returning the raw ticket id.
+pub async fn issue_resume_ticket(
+    user_id: &str,
+    session: Thing,
+) -> Result<String, surrealdb::Error> {
+    let mut rotated_res = get_db()
+        .await
+        .query("DELETE type::table($table) WHERE allow_connect_again = true AND session = $session RETURN BEFORE")
+        .bind(("table", Ticket::TABLE))
+        .bind(("session", session.clone()))
+        .await?;
+    let _rotated: Vec<Ticket> = rotated_res.take(0)?;
+
+    let ticket = Ticket::new_resumable(
+        thing(User::TABLE, user_id),
+        session,
+        resume_ticket_expiry(),
+    );
+    let mut created: Vec<Ticket> = get_db()
+        .await
+        .create(Ticket::TABLE)
+        .content(ticket)
+        .await?;
+    Ok(created.pop().unwrap().id.unwrap().id.to_string())
+}
+
+/// Extending the expiry of the resumable tickets of the session after its connection dropped
+///
+/// So the ticket is valid as long as the disconnected session can be reattached.
+pub async fn extend_resume_ticket(session: Thing) {
+    let extended_res = get_db()
+        .await
+        .query("UPDATE type::table($table) SET expires_at = $expires_at WHERE allow_connect_again = true AND session = $session")
+        .bind(("table", Ticket::TABLE))
+        .bind(("session", session.clone()))
+        .bind(("expires_at", resume_ticket_expiry()))
+        .await;
+    let extended: Result<Vec<Ticket>, surrealdb::Error> =
+        extended_res.and_then(|mut res| res.take(0));
+    if let Err(e) = extended {
+        warn!("Failed to extend the resume ticket of {}:- {:?}", session, e);
+    }
+}
+
+/// Validating the ticket before upgrading the connection. It is marked by `open_ticket` after
+/// the connection upgraded
+pub fn check_ticket(ticket: &Ticket) -> Result<(), Error<StorageError>> {
+    let ticket_id = ticket.id.clone().unwrap();
+
+    match ticket.expires_at {
+        Some(expires_at) if expires_at > now_secs() => {}
+        _ => {
+            return Err(WebSocketOpenError::ExpiredTicket {
+                ticket_id: ticket_id.id.to_raw(),
+            }
+            .into())
+        }
+    }
+
+    if ticket.allow_connect_again {
+        if let Some(opened_at) = ticket.opened_at.clone() {
+            let closed_after_opened = match &ticket.closed_at {
+                Some(closed_at) => *closed_at > opened_at,
+                None => false,
+            };
+            if !closed_after_opened {
+                return Err(WebSocketOpenError::PendingTicket {
+                    ticket_id: ticket_id.id.to_raw(),
+                    opened_at,
+                }
+                .into());
+            }
+        }
+    } else if ticket.opened_at.is_some() {
+        return Err(WebSocketOpenError::UsedTicket {
+            ticket_id: ticket_id.id.to_raw(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Marking the ticket as opened. A connection that failed to upgrade is not using the ticket
+///
+/// Marking is done in a single conditional update. So only one connection can open the same
+/// ticket even if both requests validated it at the same time.
+pub async fn open_ticket(ticket_id: Thing) -> Result<(), Error<StorageError>> {
+    let mut opened_res = get_db()
+        .await
+        .query("UPDATE $ticket SET opened_at = time::now() WHERE expires_at > $now AND ((allow_connect_again = false AND opened_at IS none) OR (allow_connect_again = true AND (opened_at IS none OR closed_at > opened_at)))")
+        .bind(("ticket", ticket_id.clone()))
+        .bind(("now", now_secs()))
+        .await?;
+    let opened: Vec<Ticket> = opened_res.take(0)?;
+    if opened.is_empty() {
+        // Another connection opened it after the validation
+        return Err(WebSocketOpenError::UsedTicket {
+            ticket_id: ticket_id.id.to_raw(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Marking the ticket as closed after the connection ended
+pub async fn close_ticket(ticket: Thing) {
+    // Condition is preventing to recreate a ticket that removed while connected
+    let closed_res = get_db()
+        .await
+        .query("UPDATE $ticket SET closed_at = time::now() WHERE opened_at IS NOT none")
+        .bind(("ticket", ticket.clone()))
+        .await;
+    let closed: Result<Vec<Ticket>, surrealdb::Error> =
+        closed_res.and_then(|mut res| res.take(0));
+    if let Err(e) = closed {
+        warn!("Failed to mark the ticket {} as closed:- {:?}", ticket, e);
+    }
+}
+
+/// Removing the tickets that can not be opened anymore. Returning the count of removed tickets
+///
+/// Resumable tickets are removed after expired or the session closed, even if a connection
+/// is still using it.
+pub async fn purge_expired_tickets() -> Result<usize, surrealdb::Error> {
+    let mut purged_res = get_db()
+        .await
+        .query("DELETE type::table($table) WHERE (allow_connect_again = false AND (expires_at IS none OR expires_at < $now) AND (opened_at IS none OR closed_at IS NOT none)) OR (allow_connect_again = true AND (expires_at IS none OR expires_at < $now OR session.closed_at IS NOT none)) RETURN BEFORE")
+        .bind(("table", Ticket::TABLE))
+        .bind(("now", now_secs()))
+        .await?;
+    let purged: Vec<Ticket> = purged_res.take(0)?;
+    Ok(purged.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use surrealdb::sql::Id;
+
+    use super::*;
+
+    #[test]
+    fn user_ids_of_any_length_are_parsed() {
+        let user_ids = [
+            "a",
+            "designer",
+            "testusertestusertest",
+            "f47ac10b-58cc-4372-a567-0e02b2c3d479",
+            "designer.with.a.long.name@example.com",
+        ];
+        for user_id in user_ids {
+            let ticket = Ticket::new(thing(User::TABLE, user_id), 0);
+            assert_eq!(ticket_user_id(&ticket), user_id);
+        }
+    }
+
+    #[test]
+    fn generated_user_ids_are_parsed() {
+        let ticket = Ticket::new(thing(User::TABLE, Id::rand()), 0);
+        let user_id = ticket_user_id(&ticket);
+        assert_eq!(thing(User::TABLE, user_id), ticket.user);
+    }
+}
@@ -1,25 +1,52 @@
-use std::task::Poll;
+use std::{future::Future, pin::Pin, task::Poll, time::Duration};
 
 use bincode::serialize as to_bin;
-use futures::{Sink, Stream};
+use futures::{ready, Sink, Stream};
+use log::warn;
 use pin_project::pin_project;
 use routerify_websocket::{
     Message, WebSocket as RouterifyWebSocket, WebsocketError as RouterifyError,
 };
+use tokio::time::{sleep, Instant, Sleep};
 use transport::ui::UIMessage;
 
+use crate::config::config;
+
+/// WebSocket connection of a session
+///
+/// Pinging the client when the connection is idle for the heartbeat interval. The
+/// connection is terminating if the client not answered within the heartbeat timeout.
 #[pin_project]
-pub struct WebSocket(#[pin] RouterifyWebSocket);
+pub struct WebSocket {
+    #[pin]
+    internal: RouterifyWebSocket,
+    /// Firing when the connection was idle
+    idle: Pin<Box<Sleep>>,
+    /// Whether a ping sent and still waiting for a message from the client
+    awaiting_pong: bool,
+}
 
 #[derive(Debug)]
 pub struct WebSocketError(RouterifyError);
 
 impl WebSocket {
     pub fn new(internal: RouterifyWebSocket) -> WebSocket {
-        WebSocket(internal)
+        WebSocket {
+            internal,
+            idle: Box::pin(sleep(heartbeat_interval())),
+            awaiting_pong: false,
+        }
     }
 }
 
+fn heartbeat_interval() -> Duration {
+    Duration::from_secs(config().heartbeat_interval)
+}
+
+fn heartbeat_timeout() -> Duration {
+    Duration::from_secs(config().heartbeat_timeout)
+}
+
 impl Sink<Vec<u8>> for WebSocket {
     type Error = WebSocketError;
 
@@ -28,13 +55,13 @@ impl Sink<Vec<u8>> for WebSocket {
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
         let this = self.project();
-        let polled: Poll<Result<(), RouterifyError>> = this.0.poll_ready(cx);
+        let polled: Poll<Result<(), RouterifyError>> = this.internal.poll_ready(cx);
         polled.map(|r| r.map_err(|e| WebSocketError(e)))
     }
 
     fn start_send(self: std::pin::Pin<&mut Self>, item: Vec<u8>) -> Result<(), Self::Error> {
         let this = self.project();
-        let result: Result<(), RouterifyError> = this.0.start_send(Message::binary(item));
+        let result: Result<(), RouterifyError> = this.internal.start_send(Message::binary(item));
         result.map_err(|e| WebSocketError(e))
     }
 
@@ -43,7 +70,7 @@ impl Sink<Vec<u8>> for WebSocket {
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
         let this = self.project();
-        let polled = this.0.poll_flush(cx);
+        let polled = this.internal.poll_flush(cx);
         polled.map(|r| r.map_err(|e| WebSocketError(e)))
     }
 
@@ -52,7 +79,7 @@ impl Sink<Vec<u8>> for WebSocket {
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
         let this = self.project();
-        let polled = this.0.poll_close(cx);
+        let polled = this.internal.poll_close(cx);
         polled.map(|r| r.map_err(|e| WebSocketError(e)))
     }
 }
@@ -64,25 +91,59 @@ impl Stream for WebSocket {
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        let this = self.project();
-        let polled: Poll<Option<Result<Message, RouterifyError>>> = this.0.poll_next(cx);
-        polled.map(|o| {
-            o.map(|r| match r {
-                Ok(m) => {
+        let mut this = self.project();
+        loop {
+            let polled: Poll<Option<Result<Message, RouterifyError>>> =
+                this.internal.as_mut().poll_next(cx);
+            match polled {
+                Poll::Ready(Some(Ok(m))) => {
+                    *this.awaiting_pong = false;
+                    this.idle
+                        .as_mut()
+                        .reset(Instant::now() + heartbeat_interval());
                     if m.is_close() {
-                        to_bin(&UIMessage::Close).unwrap()
+                        return Poll::Ready(Some(to_bin(&UIMessage::Close).unwrap()));
                     } else if m.is_binary() {
-                        m.into_bytes()
-                    } else {
-                        unreachable!()
+                        return Poll::Ready(Some(m.into_bytes()));
+                    } else if m.is_pong() {
+                        return Poll::Ready(Some(to_bin(&UIMessage::Heartbeat).unwrap()));
                     }
+                    // Pings are answering by the underlying library and text messages
+                    // are not a part of our protocol
+                    continue;
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    return Poll::Ready(Some(to_bin(&UIMessage::Error(e.to_string())).unwrap()));
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {}
+            }
+
+            ready!(this.idle.as_mut().poll(cx));
+
+            if *this.awaiting_pong {
+                warn!("Client not answered to the heartbeat. Terminating the connection");
+                return Poll::Ready(None);
+            }
+
+            match ready!(this.internal.as_mut().poll_ready(cx)) {
+                Ok(()) => {
+                    if let Err(e) = this.internal.as_mut().start_send(Message::ping(vec![])) {
+                        return Poll::Ready(Some(to_bin(&UIMessage::Error(e.to_string())).unwrap()));
+                    }
+                    // Flushing is completing on the next send if the sink is busy now
+                    let _ = this.internal.as_mut().poll_flush(cx);
+                }
+                Err(e) => {
+                    return Poll::Ready(Some(to_bin(&UIMessage::Error(e.to_string())).unwrap()));
                 }
-                Err(e) => to_bin(&UIMessage::Error(e.to_string())).unwrap(),
-            })
-        })
+            }
+            *this.awaiting_pong = true;
+            this.idle.as_mut().reset(Instant::now() + heartbeat_timeout());
+        }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.0.size_hint()
+        self.internal.size_hint()
     }
 }
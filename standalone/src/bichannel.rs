@@ -1,26 +1,165 @@
-use futures::{
-    self,
-    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender, SendError},
-    Sink, Stream
+//! In-memory connection between the UI and the app
+//!
+//! Each direction is a queue that can be bounded. A sender is waiting in `poll_ready` while
+//! a bounded queue is full, so a busy app is slowing down the UI instead of growing the
+//! memory. Messages that only the latest one matters (eg:- window size) can be coalesced
+//! using a `Coalesce` policy. A coalesced message is removing the waiting message of the
+//! same kind and taking its place at the back of the queue. So it is not taking a new slot
+//! and it is not overtaking the messages sent before it.
+
+use bincode::serialize as to_bin;
+use futures::{Sink, Stream};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
 };
-use pin_project::pin_project;
-use std::task::Poll;
+use transport::ui::UIMessage;
+
+/// Deciding which messages can be replaced by a newer message of the same kind
+pub trait Coalesce<T>: Send + Sync {
+    /// Messages with the same key are replaced by the newest one while they are waiting
+    /// in the queue. `None` for messages that should be delivered one by one.
+    fn key(&self, item: &T) -> Option<u64>;
+}
+
+/// Delivering every message
+pub struct NoCoalesce;
+
+impl<T> Coalesce<T> for NoCoalesce {
+    fn key(&self, _item: &T) -> Option<u64> {
+        None
+    }
+}
+
+/// Length of the variant tag that bincode is writing before the fields of an enum
+const VARIANT_TAG_LEN: usize = 4;
+
+/// Coalescing the serialized UI messages that only the latest value matters
+///
+/// Messages are recognized by the variant tag at the start of the encoding. So the messages
+/// are not decoded while sending.
+pub struct UIMessageCoalesce {
+    resize_tag: Vec<u8>,
+}
+
+impl Default for UIMessageCoalesce {
+    fn default() -> Self {
+        let resize =
+            to_bin(&UIMessage::Resize(0, 0)).expect("Could not serialize a resize message");
+        UIMessageCoalesce {
+            resize_tag: resize[..VARIANT_TAG_LEN].to_vec(),
+        }
+    }
+}
+
+impl Coalesce<Vec<u8>> for UIMessageCoalesce {
+    fn key(&self, item: &Vec<u8>) -> Option<u64> {
+        if item.starts_with(&self.resize_tag) {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
 
 #[derive(Debug)]
-pub struct BiChannelError(SendError);
+pub enum BiChannelError {
+    /// Other side of the channel dropped
+    Closed,
+}
+
+struct Queue<T> {
+    items: VecDeque<(Option<u64>, T)>,
+    /// `None` for an unbounded queue
+    capacity: Option<usize>,
+    /// Sender closed or dropped
+    sender_closed: bool,
+    receiver_dropped: bool,
+    receiver_waker: Option<Waker>,
+    sender_waker: Option<Waker>,
+}
+
+impl<T> Queue<T> {
+    fn new(capacity: Option<usize>) -> Queue<T> {
+        Queue {
+            items: VecDeque::new(),
+            capacity,
+            sender_closed: false,
+            receiver_dropped: false,
+            receiver_waker: None,
+            sender_waker: None,
+        }
+    }
+
+    fn wake_receiver(&mut self) {
+        if let Some(waker) = self.receiver_waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn wake_sender(&mut self) {
+        if let Some(waker) = self.sender_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+struct Sender<T> {
+    queue: Arc<Mutex<Queue<T>>>,
+    policy: Arc<dyn Coalesce<T>>,
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.sender_closed = true;
+        queue.wake_receiver();
+    }
+}
+
+struct Receiver<T> {
+    queue: Arc<Mutex<Queue<T>>>,
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.receiver_dropped = true;
+        queue.wake_sender();
+    }
+}
+
+fn channel<T>(capacity: Option<usize>, policy: Arc<dyn Coalesce<T>>) -> (Sender<T>, Receiver<T>) {
+    let queue = Arc::new(Mutex::new(Queue::new(capacity)));
+    (
+        Sender {
+            queue: queue.clone(),
+            policy,
+        },
+        Receiver { queue },
+    )
+}
 
-#[pin_project]
 pub struct BiChannel<I, O> {
-    #[pin]
-    receiver: UnboundedReceiver<I>,
-    #[pin]
-    sender: UnboundedSender<O>,
+    receiver: Receiver<I>,
+    sender: Sender<O>,
 }
 
 impl<I, O> BiChannel<I, O> {
-    pub fn new<T, Y>() -> (BiChannel<T, Y>, BiChannel<Y, T>) {
-        let (sender1, receiver1) = unbounded();
-        let (sender2, receiver2) = unbounded();
+    /// Creating a connected pair
+    ///
+    /// First channel is sending `Y` messages coalesced by `first_policy` and the second
+    /// channel is sending `T` messages coalesced by `second_policy`. Each direction is
+    /// bounded by its capacity. `None` is leaving the direction unbounded.
+    pub fn new<T, Y>(
+        first_capacity: Option<usize>,
+        first_policy: Arc<dyn Coalesce<Y>>,
+        second_capacity: Option<usize>,
+        second_policy: Arc<dyn Coalesce<T>>,
+    ) -> (BiChannel<T, Y>, BiChannel<Y, T>) {
+        let (sender1, receiver1) = channel(first_capacity, first_policy);
+        let (sender2, receiver2) = channel(second_capacity, second_policy);
 
         (
             BiChannel {
@@ -38,12 +177,19 @@ impl<I, O> BiChannel<I, O> {
 impl<I, O> Stream for BiChannel<I, O> {
     type Item = I;
 
-    fn poll_next(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
-        let this = self.project();
-        this.receiver.poll_next(cx)
+    fn poll_next(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut queue = self.receiver.queue.lock().unwrap();
+        match queue.items.pop_front() {
+            Some((_, item)) => {
+                queue.wake_sender();
+                Poll::Ready(Some(item))
+            }
+            None if queue.sender_closed => Poll::Ready(None),
+            None => {
+                queue.receiver_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
     }
 }
 
@@ -51,27 +197,113 @@ impl<I, O> Sink<O> for BiChannel<I, O> {
     type Error = BiChannelError;
 
     fn start_send(self: std::pin::Pin<&mut Self>, item: O) -> Result<(), Self::Error> {
-        let this = self.project();
-        let result: Result<(), SendError> = this.sender.start_send(item);
-        result.map_err(|e|BiChannelError(e))
+        let key = self.sender.policy.key(&item);
+        let mut queue = self.sender.queue.lock().unwrap();
+        if queue.receiver_dropped {
+            return Err(BiChannelError::Closed);
+        }
+
+        if key.is_some() {
+            queue.items.retain(|(k, _)| *k != key);
+        }
+        queue.items.push_back((key, item));
+        queue.wake_receiver();
+        Ok(())
     }
 
-    fn poll_ready(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let this = self.project();
-        let polled: Poll<Result<(), SendError>> = this.sender.poll_ready(cx);
-        polled.map(|r|r.map_err(|e| BiChannelError(e)))
+    fn poll_ready(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut queue = self.sender.queue.lock().unwrap();
+        let has_room = match queue.capacity {
+            Some(capacity) => queue.items.len() < capacity,
+            None => true,
+        };
+        if queue.receiver_dropped {
+            Poll::Ready(Err(BiChannelError::Closed))
+        } else if has_room {
+            Poll::Ready(Ok(()))
+        } else {
+            queue.sender_waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
     }
 
-    fn poll_flush(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let this = self.project();
-        let polled: Poll<Result<(), SendError>> = this.sender.poll_flush(cx);
-        polled.map(|r|r.map_err(|e| BiChannelError(e)))
+    fn poll_flush(self: std::pin::Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
     }
 
-    fn poll_close(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let this = self.project();
-        let polled: Poll<Result<(), SendError>> = this.sender.poll_close(cx);
-        polled.map(|r|r.map_err(|e| BiChannelError(e)))
+    fn poll_close(self: std::pin::Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut queue = self.sender.queue.lock().unwrap();
+        queue.sender_closed = true;
+        queue.wake_receiver();
+        Poll::Ready(Ok(()))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bincode::serialize as to_bin;
+    use futures::{executor::block_on, FutureExt, SinkExt, StreamExt};
+    use transport::ui::UIMessage;
+
+    use super::{BiChannel, NoCoalesce, UIMessageCoalesce};
+
+    #[test]
+    fn sender_waits_while_the_queue_is_full() {
+        let (mut ui, mut app) = BiChannel::<u8, u8>::new::<u8, u8>(
+            Some(2),
+            Arc::new(NoCoalesce),
+            Some(2),
+            Arc::new(NoCoalesce),
+        );
+        block_on(ui.send(1)).unwrap();
+        block_on(ui.send(2)).unwrap();
+        assert!(ui.send(3).now_or_never().is_none());
+
+        assert_eq!(block_on(app.next()), Some(1));
+        block_on(ui.send(3)).unwrap();
+        assert_eq!(block_on(app.next()), Some(2));
+        assert_eq!(block_on(app.next()), Some(3));
+    }
+
+    #[test]
+    fn unbounded_direction_never_waits() {
+        let (mut ui, mut app) = BiChannel::<u8, u8>::new::<u8, u8>(
+            Some(1),
+            Arc::new(NoCoalesce),
+            None,
+            Arc::new(NoCoalesce),
+        );
+        for i in 0..10 {
+            block_on(app.send(i)).unwrap();
+        }
+        drop(app);
+
+        let received: Vec<u8> = block_on(ui.by_ref().collect());
+        assert_eq!(received, (0..10).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn resize_messages_are_coalesced() {
+        let (mut ui, mut app) = BiChannel::<Vec<u8>, Vec<u8>>::new::<Vec<u8>, Vec<u8>>(
+            Some(8),
+            Arc::new(UIMessageCoalesce::default()),
+            Some(8),
+            Arc::new(NoCoalesce),
+        );
+        block_on(ui.send(to_bin(&UIMessage::Resize(10, 10)).unwrap())).unwrap();
+        block_on(ui.send(to_bin(&UIMessage::Ping).unwrap())).unwrap();
+        block_on(ui.send(to_bin(&UIMessage::Resize(20, 20)).unwrap())).unwrap();
+        drop(ui);
+
+        let received: Vec<Vec<u8>> = block_on(app.by_ref().collect());
+        assert_eq!(
+            received,
+            vec![
+                to_bin(&UIMessage::Ping).unwrap(),
+                to_bin(&UIMessage::Resize(20, 20)).unwrap()
+            ]
+        );
+    }
+}
@@ -1,5 +1,4 @@
 mod bichannel;
-mod fs;
 mod mock_api;
 mod standalone_app;
 
@@ -7,13 +6,13 @@ use std::path::Path;
 use std::{borrow::Borrow, sync::Arc};
 
 use app::model::User;
+use app::projects::define_project_indexes;
+use app::storage::fs::FileSystemStorage;
 use app::App;
-use bichannel::BiChannel;
+use bichannel::{BiChannel, NoCoalesce, UIMessageCoalesce};
 use dirs::data_local_dir;
 use eframe::{run_native, NativeOptions};
-use fs::FileSystemStorage;
-use futures::lock::Mutex;
-use log::{debug, info};
+use log::{debug, info, warn};
 use standalone_app::StandaloneApp;
 use surrealdb::Surreal;
 use tokio::spawn;
@@ -25,6 +24,8 @@ use surrealdb::engine::local::RocksDb as DbConnection;
 use transport::ReceiveError;
 
 pub static USER_ID: &str = "currentuser";
+/// Count of UI messages that can wait for the app
+const CHANNEL_CAPACITY: usize = 256;
 
 #[tokio::main]
 async fn main() {
@@ -44,6 +45,7 @@ async fn main() {
     let db = Arc::new(Surreal::new::<DbConnection>(db_path).await.unwrap());
 
     db.use_ns("default").use_db("default").await.unwrap();
+    define_project_indexes(&db).await.unwrap();
 
     let mut exist_user: Option<User> = db.select((User::TABLE, USER_ID)).await.unwrap();
     if exist_user.is_none() {
@@ -54,16 +56,26 @@ async fn main() {
             .unwrap();
     }
 
-    let app = Arc::new(Mutex::new(App::new(db.clone())));
-    let (uichannel, appchannel) = BiChannel::<Vec<u8>, Vec<u8>>::new::<Vec<u8>, Vec<u8>>();
+    let app = Arc::new(App::new(db.clone()));
+    let events = app.events();
+    // UI is reading the app messages only in its event loop. So the app should not wait
+    // for the UI to make a room
+    let (uichannel, appchannel) = BiChannel::<Vec<u8>, Vec<u8>>::new::<Vec<u8>, Vec<u8>>(
+        Some(CHANNEL_CAPACITY),
+        Arc::new(UIMessageCoalesce::default()),
+        None,
+        Arc::new(NoCoalesce),
+    );
     let fs_el = fs.clone();
     spawn(async move {
-        let app = app.clone();
-        let mut app_locked = app.lock().await;
-        let mut session = app_locked
+        let mut session = app
             .create_session(String::from(USER_ID), appchannel, fs_el)
             .await
             .unwrap();
+        if let Err(e) = session.offer_previous_session().await {
+            // Previous tabs are still in the database. Offering again in the next launch
+            warn!("Failed to offer the previous session:- {:?}", e);
+        }
 
         loop {
             let message = session.receive_message().await;
@@ -91,7 +103,15 @@ async fn main() {
     run_native(
         "OpenXD",
         options,
-        Box::new(move |cc| Box::new(StandaloneApp::new(cc, uichannel, db.clone(), fs.clone()))),
+        Box::new(move |cc| {
+            Box::new(StandaloneApp::new(
+                cc,
+                uichannel,
+                db.clone(),
+                fs.clone(),
+                events.clone(),
+            ))
+        }),
     )
     .unwrap();
 }
@@ -1,23 +1,29 @@
 use std::{fmt::Debug, path::Path, sync::Arc};
 
-use app::external::{
-    create_project_using_existing_file, export_snapshot, get_current_tab,
-    CreateProjectUsingExistingFileError, ExportSnapshotError, GetCurrentTabSnapshotError,
+use app::{
+    events::DocumentEvents,
+    external::{
+        create_project_using_existing_file, export_snapshot, get_current_tab,
+        CreateProjectUsingExistingFileError, ExportSnapshotError, GetCurrentTabSnapshotError,
+    },
+    projects::list_projects,
+    storage::fs::{FileSystemStorage, StorageError},
 };
 use async_trait::async_trait;
 use rfd::{AsyncFileDialog, FileHandle};
 use surrealdb::{engine::local::Db, Surreal};
 use tokio::fs::OpenOptions;
-use ui::external::External;
+use ui::external::{External, RecentProject};
 
-use crate::{
-    fs::{FileSystemStorage, StorageError},
-    USER_ID,
-};
+use crate::USER_ID;
+
+/// Count of projects to list in the "Open recent" menu
+const RECENT_PROJECTS_COUNT: usize = 10;
 
 pub struct MockApi {
     db: Arc<Surreal<Db>>,
     storage: Arc<FileSystemStorage>,
+    events: DocumentEvents,
 }
 
 #[derive(Debug)]
@@ -26,6 +32,7 @@ pub enum MockApiError<SE: Debug + std::error::Error + Send + Sync> {
     GetCurrentTabSnapshot(GetCurrentTabSnapshotError),
     ExportSnapshot(ExportSnapshotError<SE>),
     Io(tokio::io::Error),
+    Db(surrealdb::Error),
 }
 
 impl<SE: Debug + std::error::Error + Send + Sync> From<MockApiError<SE>> for String {
@@ -56,9 +63,23 @@ impl<SE: Debug + std::error::Error + Send + Sync> From<ExportSnapshotError<SE>>
     }
 }
 
+impl<SE: Debug + std::error::Error + Send + Sync> From<surrealdb::Error> for MockApiError<SE> {
+    fn from(value: surrealdb::Error) -> Self {
+        MockApiError::Db(value)
+    }
+}
+
 impl MockApi {
-    pub fn new(db: Arc<Surreal<Db>>, storage: Arc<FileSystemStorage>) -> MockApi {
-        MockApi { db, storage }
+    pub fn new(
+        db: Arc<Surreal<Db>>,
+        storage: Arc<FileSystemStorage>,
+        events: DocumentEvents,
+    ) -> MockApi {
+        MockApi {
+            db,
+            storage,
+            events,
+        }
     }
 }
 
@@ -74,6 +95,7 @@ impl External for MockApi {
         let project = create_project_using_existing_file(
             self.db.clone(),
             self.storage.clone(),
+            &self.events,
             &mut buf_reader,
             file_name,
             userid,
@@ -140,4 +162,18 @@ impl External for MockApi {
 
         Ok(())
     }
+
+    async fn recent_projects(&self) -> Result<Vec<RecentProject>, String> {
+        let page = list_projects(&self.db, USER_ID, None, 1, RECENT_PROJECTS_COUNT)
+            .await
+            .map_err(MockApiError::<StorageError>::from)?;
+        Ok(page
+            .projects
+            .into_iter()
+            .map(|project| RecentProject {
+                id: project.id,
+                name: project.name,
+            })
+            .collect())
+    }
 }
@@ -1,12 +1,15 @@
 use std::sync::Arc;
 
+use app::{
+    events::DocumentEvents,
+    storage::fs::{FileSystemStorage, StorageError},
+};
 use eframe::{App, CreationContext};
 use surrealdb::{engine::local::Db, Surreal};
 use ui::{ui::Ui, client::ClientImpl};
 
 use crate::{
     bichannel::{BiChannel, BiChannelError},
-    fs::{FileSystemStorage, StorageError},
     mock_api::{MockApi, MockApiError},
 };
 
@@ -20,9 +23,10 @@ impl StandaloneApp {
         internal: BiChannel<Vec<u8>, Vec<u8>>,
         db: Arc<Surreal<Db>>,
         storage: Arc<FileSystemStorage>,
+        events: DocumentEvents,
     ) -> StandaloneApp {
-        let client = ClientImpl::new(internal);
-        let external = MockApi::new(db, storage);
+        let client = ClientImpl::new(internal, &cc.egui_ctx);
+        let external = MockApi::new(db, storage, events);
         let wgpu_render_state = cc.wgpu_render_state.as_ref().unwrap();
         StandaloneApp {
             ui: Ui::new(&cc.egui_ctx, wgpu_render_state, Box::new(client), Box::new(external)),
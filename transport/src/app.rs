@@ -1,12 +1,24 @@
 use serde::{Serialize, Deserialize};
 
-use crate::vo::Screen;
+use crate::{
+    rpc::RpcResponse,
+    ui::UIMessage,
+    vo::{Screen, ScreenPatch},
+};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum ApplicationMessage {
-    Error(String),
-    TabCreated(TabCreatedMessage),
-    Pong
+    Error(ErrorMessage),
+    Pong,
+    ScreensChanged(ScreensChangedMessage),
+    TabRenamed(TabRenamedMessage),
+    CommitCreated(CommitCreatedMessage),
+    SessionClosing(SessionClosingMessage),
+    ResumeTicket(ResumeTicketMessage),
+    SessionResumed(SessionResumedMessage),
+    PreviousSessionFound(PreviousSessionFoundMessage),
+    /// Response for a typed request declared in the `rpc` module
+    Rpc(RpcResponse),
 }
 
 
@@ -16,36 +28,70 @@ pub struct TabCreatedMessage {
     pub tab_id: String,
     pub screens: Vec<Screen>,
     pub zoom: f64,
+    /// User can only view the project in this tab
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 impl TabCreatedMessage {
-    pub fn new(tab_name: String, tab_id: String, screens: Vec<Screen>, zoom: f64) -> TabCreatedMessage {
-        TabCreatedMessage { tab_name, tab_id, screens, zoom }
+    pub fn new(
+        tab_name: String,
+        tab_id: String,
+        screens: Vec<Screen>,
+        zoom: f64,
+        read_only: bool,
+    ) -> TabCreatedMessage {
+        TabCreatedMessage { tab_name, tab_id, screens, zoom, read_only }
     }
 }
 
-impl TryFrom<ApplicationMessage> for TabCreatedMessage {
-    type Error = ();
-
-    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
-        match value {
-            ApplicationMessage::TabCreated(inner) => Ok(inner),
-            _ => Err(())
-        }
-    }
+/// Kind of an error happened in the app side
+///
+/// UI should use this to choose the message to display instead of the raw error.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Failed to read/write data to database
+    Database,
+    /// Failed to upload/download files to storage
+    Storage,
+    /// The requested project not exists
+    ProjectNotFound,
+    /// The requested tab not exists
+    TabNotFound,
+    /// The tab is opened by a user who can not edit the project
+    ReadOnlyTab,
+    /// The requested session not exists or already closed
+    SessionNotFound,
+    /// Any other unexpected error
+    Internal,
+    /// Connection to the app dropped before the response received
+    ConnectionLost,
 }
 
-impl Into<ApplicationMessage> for TabCreatedMessage {
-    fn into(self) -> ApplicationMessage {
-        ApplicationMessage::TabCreated(self)
-    }
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ErrorMessage {
+    pub code: ErrorCode,
+    /// Human readable message in english
+    pub message: String,
+    /// The request that caused the error
+    pub request: Option<UIMessage>,
+    /// Whether sending the same request again can succeed
+    pub retryable: bool,
 }
 
-pub struct ErrorMessage(String);
-
 impl ErrorMessage {
-    pub fn new(err: String) -> ErrorMessage {
-        ErrorMessage(err)
+    pub fn new(
+        code: ErrorCode,
+        message: String,
+        request: Option<UIMessage>,
+        retryable: bool,
+    ) -> ErrorMessage {
+        ErrorMessage {
+            code,
+            message,
+            request,
+            retryable,
+        }
     }
 }
 
@@ -53,7 +99,7 @@ impl TryFrom<ApplicationMessage> for ErrorMessage {
     type Error = ();
     fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
         match value {
-            ApplicationMessage::Error(err)=> Ok(ErrorMessage::new(err)),
+            ApplicationMessage::Error(err)=> Ok(err),
             _ => Err(())
         }
     }
@@ -61,7 +107,7 @@ impl TryFrom<ApplicationMessage> for ErrorMessage {
 
 impl Into<ApplicationMessage> for ErrorMessage {
     fn into(self) -> ApplicationMessage {
-        ApplicationMessage::Error(self.0)
+        ApplicationMessage::Error(self)
     }
 }
 
@@ -88,3 +134,241 @@ impl Into<ApplicationMessage> for PongMessage {
         ApplicationMessage::Pong
     }
 }
+
+/// Screens of an opened tab changed on the app side
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScreensChangedMessage {
+    pub tab_id: String,
+    pub patches: Vec<ScreenPatch>,
+}
+
+impl ScreensChangedMessage {
+    pub fn new(tab_id: String, patches: Vec<ScreenPatch>) -> ScreensChangedMessage {
+        ScreensChangedMessage { tab_id, patches }
+    }
+}
+
+impl TryFrom<ApplicationMessage> for ScreensChangedMessage {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::ScreensChanged(inner) => Ok(inner),
+            _ => Err(())
+        }
+    }
+}
+
+impl Into<ApplicationMessage> for ScreensChangedMessage {
+    fn into(self) -> ApplicationMessage {
+        ApplicationMessage::ScreensChanged(self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TabRenamedMessage {
+    pub tab_id: String,
+    pub tab_name: String,
+}
+
+impl TabRenamedMessage {
+    pub fn new(tab_id: String, tab_name: String) -> TabRenamedMessage {
+        TabRenamedMessage { tab_id, tab_name }
+    }
+}
+
+impl TryFrom<ApplicationMessage> for TabRenamedMessage {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::TabRenamed(inner) => Ok(inner),
+            _ => Err(())
+        }
+    }
+}
+
+impl Into<ApplicationMessage> for TabRenamedMessage {
+    fn into(self) -> ApplicationMessage {
+        ApplicationMessage::TabRenamed(self)
+    }
+}
+
+/// Pending changes of a tab committed to its branch
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CommitCreatedMessage {
+    pub tab_id: String,
+    pub commit_id: String,
+    pub message: String,
+}
+
+impl CommitCreatedMessage {
+    pub fn new(tab_id: String, commit_id: String, message: String) -> CommitCreatedMessage {
+        CommitCreatedMessage { tab_id, commit_id, message }
+    }
+}
+
+impl TryFrom<ApplicationMessage> for CommitCreatedMessage {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::CommitCreated(inner) => Ok(inner),
+            _ => Err(())
+        }
+    }
+}
+
+impl Into<ApplicationMessage> for CommitCreatedMessage {
+    fn into(self) -> ApplicationMessage {
+        ApplicationMessage::CommitCreated(self)
+    }
+}
+
+/// The app is going to close the session. UI should stop sending new actions
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SessionClosingMessage {
+    pub reason: String,
+}
+
+impl SessionClosingMessage {
+    pub fn new(reason: String) -> SessionClosingMessage {
+        SessionClosingMessage { reason }
+    }
+}
+
+impl TryFrom<ApplicationMessage> for SessionClosingMessage {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::SessionClosing(inner) => Ok(inner),
+            _ => Err(())
+        }
+    }
+}
+
+impl Into<ApplicationMessage> for SessionClosingMessage {
+    fn into(self) -> ApplicationMessage {
+        ApplicationMessage::SessionClosing(self)
+    }
+}
+
+/// Ticket to reattach to the same session if the connection dropped
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ResumeTicketMessage {
+    pub ticket: String,
+}
+
+impl ResumeTicketMessage {
+    pub fn new(ticket: String) -> ResumeTicketMessage {
+        ResumeTicketMessage { ticket }
+    }
+}
+
+impl TryFrom<ApplicationMessage> for ResumeTicketMessage {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::ResumeTicket(inner) => Ok(inner),
+            _ => Err(())
+        }
+    }
+}
+
+impl Into<ApplicationMessage> for ResumeTicketMessage {
+    fn into(self) -> ApplicationMessage {
+        ApplicationMessage::ResumeTicket(self)
+    }
+}
+
+/// An existing session reattached to the UI with the tabs opened in it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SessionResumedMessage {
+    pub tabs: Vec<TabCreatedMessage>,
+    pub current_tab: Option<String>,
+}
+
+impl SessionResumedMessage {
+    pub fn new(tabs: Vec<TabCreatedMessage>, current_tab: Option<String>) -> SessionResumedMessage {
+        SessionResumedMessage { tabs, current_tab }
+    }
+}
+
+impl TryFrom<ApplicationMessage> for SessionResumedMessage {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::SessionResumed(inner) => Ok(inner),
+            _ => Err(())
+        }
+    }
+}
+
+impl Into<ApplicationMessage> for SessionResumedMessage {
+    fn into(self) -> ApplicationMessage {
+        ApplicationMessage::SessionResumed(self)
+    }
+}
+
+/// An unclosed session of the user found with tabs that can be restored
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PreviousSessionFoundMessage {
+    pub session_id: String,
+    /// Names of the tabs opened in the previous session
+    pub tabs: Vec<String>,
+    /// Count of actions that not committed in those tabs
+    pub unsaved_actions: usize,
+}
+
+impl PreviousSessionFoundMessage {
+    pub fn new(session_id: String, tabs: Vec<String>, unsaved_actions: usize) -> PreviousSessionFoundMessage {
+        PreviousSessionFoundMessage { session_id, tabs, unsaved_actions }
+    }
+}
+
+impl TryFrom<ApplicationMessage> for PreviousSessionFoundMessage {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::PreviousSessionFound(inner) => Ok(inner),
+            _ => Err(())
+        }
+    }
+}
+
+impl Into<ApplicationMessage> for PreviousSessionFoundMessage {
+    fn into(self) -> ApplicationMessage {
+        ApplicationMessage::PreviousSessionFound(self)
+    }
+}
+
+/// Messages that the app sends to the UI without a request from the UI
+#[derive(Clone, Debug)]
+pub enum PushMessage {
+    ScreensChanged(ScreensChangedMessage),
+    TabRenamed(TabRenamedMessage),
+    CommitCreated(CommitCreatedMessage),
+    SessionClosing(SessionClosingMessage),
+    SessionResumed(SessionResumedMessage),
+    PreviousSessionFound(PreviousSessionFoundMessage),
+}
+
+impl TryFrom<ApplicationMessage> for PushMessage {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::ScreensChanged(inner) => Ok(PushMessage::ScreensChanged(inner)),
+            ApplicationMessage::TabRenamed(inner) => Ok(PushMessage::TabRenamed(inner)),
+            ApplicationMessage::CommitCreated(inner) => Ok(PushMessage::CommitCreated(inner)),
+            ApplicationMessage::SessionClosing(inner) => Ok(PushMessage::SessionClosing(inner)),
+            ApplicationMessage::SessionResumed(inner) => Ok(PushMessage::SessionResumed(inner)),
+            ApplicationMessage::PreviousSessionFound(inner) => Ok(PushMessage::PreviousSessionFound(inner)),
+            _ => Err(())
+        }
+    }
+}
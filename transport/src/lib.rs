@@ -1,9 +1,10 @@
 use bincode::{deserialize as from_bin, serialize as to_bin, ErrorKind as BincodeError};
-use futures::{Sink, SinkExt, Stream, StreamExt};
+use futures::{FutureExt, Sink, SinkExt, Stream, StreamExt};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{fmt::Debug, marker::PhantomData, pin::Pin};
 
 pub mod app;
+pub mod rpc;
 pub mod ui;
 pub mod vo;
 
@@ -112,6 +113,43 @@ impl<
         }
     }
 
+    /// Receive a message of the given type only if it is already available
+    ///
+    /// This will not wait for the transport. Messages of other types received in
+    /// the meantime are kept for the next `receive` calls.
+    pub fn try_receive<IT: TryFrom<I, Error = ()>>(&mut self) -> Result<Option<IT>, ReceiveError> {
+        for (i, pending_message) in self.pending.iter().enumerate() {
+            if let Ok(converted_message) = IT::try_from(pending_message.clone()) {
+                self.pending.remove(i);
+                return Ok(Some(converted_message));
+            }
+        }
+
+        if self.terminated {
+            return Err(ReceiveError::Terminated);
+        }
+
+        while let Some(bin_message_opt) = self.internal.next().now_or_never() {
+            match bin_message_opt {
+                Some(bin_message) => {
+                    let response_message = from_bin::<I>(&bin_message)
+                        .map_err(|e| ReceiveError::Deserialize(*e))?;
+                    if let Ok(converted_message) = IT::try_from(response_message.clone()) {
+                        return Ok(Some(converted_message));
+                    } else {
+                        self.pending.push(response_message);
+                    }
+                }
+                None => {
+                    self.terminated = true;
+                    return Err(ReceiveError::Terminated);
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Send a message without caring about a response
     pub async fn send<OT: Into<O>>(&mut self, message: OT) -> Result<(), SendError<E>> {
         let mut pin_internal: Pin<&mut T> = Pin::new(&mut self.internal);
@@ -123,4 +161,9 @@ impl<
             Err(e) => Err(SendError::Serialize(*e)),
         }
     }
+
+    /// Flushing the pending messages and closing the internal transport
+    pub async fn close(&mut self) -> Result<(), SendError<E>> {
+        self.internal.close().await.map_err(|e| SendError::Send(e))
+    }
 }
@@ -0,0 +1,178 @@
+//! Typed request/response calls from the UI to the app
+//!
+//! Every operation is declared once in the `rpc!` block at the bottom of this module.
+//! The declaration is generating,
+//!
+//! - A variant in `RpcRequest` with the arguments and a variant in `RpcResponse` with the result.
+//! - A method in `RpcHandler` that the app should implement. `RpcHandler::dispatch` is calling
+//!   the correct method for a request.
+//! - A method in `RpcClient` that the UI can call directly. Implementors only need to provide
+//!   `RpcClient::call` to send a request and wait for the response.
+//!
+//! Errors are carrying as typed results. Failures of the operation are returned as
+//! `RpcError::Remote` and failures of the transport as `RpcError::Transport`.
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use futures::{Sink, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{ApplicationMessage, ErrorMessage, TabCreatedMessage},
+    ui::UIMessage,
+    Client, SendAndReceiveError,
+};
+
+/// Error of a call made through `RpcClient`
+#[derive(Debug)]
+pub enum RpcError<R: Debug, T: Debug> {
+    /// App failed to complete the operation
+    Remote(R),
+    /// Failed to deliver the request or receive the response
+    Transport(T),
+    /// App answered with a response for an another operation
+    UnexpectedResponse,
+}
+
+macro_rules! rpc {
+    ($(
+        $(#[$meta:meta])*
+        $variant:ident => fn $name:ident($($arg:ident: $arg_ty:ty),*) -> Result<$ok:ty, $err:ty>;
+    )*) => {
+        /// Requests that UI is sending to the app
+        #[derive(Serialize, Deserialize, Clone, Debug)]
+        pub enum RpcRequest {
+            $($variant { $($arg: $arg_ty),* },)*
+        }
+
+        /// Responses that the app is sending back for each request
+        #[derive(Serialize, Deserialize, Clone, Debug)]
+        pub enum RpcResponse {
+            $($variant(Result<$ok, $err>),)*
+        }
+
+        impl RpcRequest {
+            /// Name of the operation without the arguments. Eg:- `OpenFile`
+            pub fn operation(&self) -> &'static str {
+                match self {
+                    $(RpcRequest::$variant { .. } => stringify!($variant),)*
+                }
+            }
+
+            /// Failed response for this request
+            ///
+            /// Transports are using this to answer the requests that the app can not answer
+            /// anymore.
+            pub fn error_response(&self, error: ErrorMessage) -> RpcResponse {
+                match self {
+                    $(RpcRequest::$variant { .. } => {
+                        RpcResponse::$variant(Err(<$err as From<ErrorMessage>>::from(error)))
+                    })*
+                }
+            }
+        }
+
+        /// App side implementation of the operations
+        #[async_trait]
+        pub trait RpcHandler: Send {
+            $(
+                $(#[$meta])*
+                async fn $name(&mut self, $($arg: $arg_ty),*) -> Result<$ok, $err>;
+            )*
+
+            /// Calling the method related to the request and wrapping the result as a response
+            async fn dispatch(&mut self, request: RpcRequest) -> RpcResponse {
+                match request {
+                    $(RpcRequest::$variant { $($arg),* } => {
+                        RpcResponse::$variant(self.$name($($arg),*).await)
+                    })*
+                }
+            }
+        }
+
+        /// UI side stub of the operations
+        #[async_trait]
+        pub trait RpcClient: Send {
+            type Error: Debug + Send;
+
+            /// Sending the request and waiting for the response
+            async fn call(&mut self, request: RpcRequest) -> Result<RpcResponse, Self::Error>;
+
+            $(
+                $(#[$meta])*
+                #[allow(unreachable_patterns)]
+                async fn $name(&mut self, $($arg: $arg_ty),*) -> Result<$ok, RpcError<$err, Self::Error>> {
+                    match self
+                        .call(RpcRequest::$variant { $($arg),* })
+                        .await
+                        .map_err(RpcError::Transport)?
+                    {
+                        RpcResponse::$variant(res) => res.map_err(RpcError::Remote),
+                        _ => Err(RpcError::UnexpectedResponse),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+rpc! {
+    /// Opening an existing project in a new tab
+    OpenFile => fn open_file(project_id: String) -> Result<TabCreatedMessage, ErrorMessage>;
+    /// Creating an empty project and opening it in a new tab
+    NewProject => fn new_project(project_name: String) -> Result<TabCreatedMessage, ErrorMessage>;
+    /// Closing a tab and dropping the uncommitted changes
+    CloseTab => fn close_tab(tab_id: String) -> Result<(), ErrorMessage>;
+    /// Committing the changes of a tab to its branch
+    CommitTab => fn commit_tab(tab_id: String, message: String) -> Result<(), ErrorMessage>;
+    /// Moving the tabs of a previous session to the current session
+    RestoreSession => fn restore_session(session_id: String) -> Result<(), ErrorMessage>;
+    /// Closing a previous session without restoring the tabs
+    DiscardSession => fn discard_session(session_id: String) -> Result<(), ErrorMessage>;
+}
+
+impl Into<UIMessage> for RpcRequest {
+    fn into(self) -> UIMessage {
+        UIMessage::Rpc(self)
+    }
+}
+
+impl TryFrom<UIMessage> for RpcRequest {
+    type Error = ();
+
+    fn try_from(value: UIMessage) -> Result<Self, Self::Error> {
+        match value {
+            UIMessage::Rpc(inner) => Ok(inner),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Into<ApplicationMessage> for RpcResponse {
+    fn into(self) -> ApplicationMessage {
+        ApplicationMessage::Rpc(self)
+    }
+}
+
+impl TryFrom<ApplicationMessage> for RpcResponse {
+    type Error = ();
+
+    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+        match value {
+            ApplicationMessage::Rpc(inner) => Ok(inner),
+            _ => Err(()),
+        }
+    }
+}
+
+#[async_trait]
+impl<E: Debug + Send, T: Stream<Item = Vec<u8>> + Sink<Vec<u8>, Error = E> + Unpin + Send>
+    RpcClient for Client<ApplicationMessage, UIMessage, E, T>
+{
+    type Error = SendAndReceiveError<E>;
+
+    async fn call(&mut self, request: RpcRequest) -> Result<RpcResponse, Self::Error> {
+        self.send_and_receive(request).await
+    }
+}
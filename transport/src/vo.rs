@@ -2,19 +2,19 @@
 
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Point2D {
     pub x: u32,
     pub y: u32
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Rect2D {
     pub min: Point2D,
     pub max: Point2D,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Screen {
     pub kind: ScreenKind,
     pub rect: Rect2D,
@@ -22,10 +22,21 @@ pub struct Screen {
     pub index: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum ScreenKind {
     Proxy {
         proxy_image: String,
     },
     Full,
 }
+
+/// A single change to the screens of an opened tab
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ScreenPatch {
+    /// A new screen appended to the tab
+    Added(Screen),
+    /// An existing screen replaced by the given one. Matched by `Screen::index`
+    Updated(Screen),
+    /// The screen at the index removed from the tab
+    Removed(usize),
+}
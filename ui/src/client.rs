@@ -1,12 +1,19 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll, Waker};
 
 use async_trait::async_trait;
+use egui::Context;
+use futures::task::{waker, ArcWake};
 use futures::{Sink, Stream};
-use transport::app::{ApplicationMessage, TabCreatedMessage};
-use transport::ui::{CloseTabMessage, NewProjectMessage, OpenFileMessage, UIMessage};
+use log::warn;
+use transport::app::{ApplicationMessage, ErrorCode, ErrorMessage, PushMessage};
+use transport::rpc::{RpcClient, RpcError, RpcRequest, RpcResponse};
+use transport::ui::UIMessage;
 use transport::{
-    Client as InternalClient, SendAndReceiveError as InternalSendAndReceiveError,
+    Client as InternalClient, ReceiveError, SendAndReceiveError as InternalSendAndReceiveError,
     SendError as InternalSendError,
 };
 
@@ -21,162 +28,185 @@ impl<E: Debug + Send, T> ClientTransport<E> for T where
 {
 }
 
-/// Response type that expect either error or a success response
-pub struct ResultResponse<T: TryFrom<ApplicationMessage, Error = ()>>(Result<T, String>);
-
-impl<T: TryFrom<ApplicationMessage, Error = ()>> ResultResponse<T> {
-    pub fn error(err: String) -> Self {
-        ResultResponse(Err(err))
-    }
-
-    pub fn ok(ok: T) -> Self {
-        ResultResponse(Ok(ok))
-    }
+#[derive(Debug)]
+pub enum SendAndReceiveError {
+    ResponseError(ErrorMessage),
+    SendAndReceiveError(InternalSendAndReceiveError<String>),
+    UnexpectedResponse,
 }
 
-impl<T: TryFrom<ApplicationMessage, Error = ()>> TryFrom<ApplicationMessage> for ResultResponse<T> {
-    type Error = ();
-    fn try_from(value: ApplicationMessage) -> Result<Self, Self::Error> {
+impl From<RpcError<ErrorMessage, InternalSendAndReceiveError<String>>> for SendAndReceiveError {
+    fn from(value: RpcError<ErrorMessage, InternalSendAndReceiveError<String>>) -> Self {
         match value {
-            ApplicationMessage::Error(err) => Ok(ResultResponse::error(err)),
-            _ => T::try_from(value).map(|r| ResultResponse::ok(r)),
+            RpcError::Remote(err) => SendAndReceiveError::ResponseError(err),
+            RpcError::Transport(err) => SendAndReceiveError::SendAndReceiveError(err),
+            RpcError::UnexpectedResponse => SendAndReceiveError::UnexpectedResponse,
         }
     }
 }
 
-impl<T: TryFrom<ApplicationMessage, Error = ()>> Into<Result<T, String>> for ResultResponse<T> {
-    fn into(self) -> Result<T, String> {
-        self.0
+impl SendAndReceiveError {
+    /// Message to display to the user
+    pub fn user_message(&self) -> String {
+        match self {
+            SendAndReceiveError::ResponseError(err) => String::from(error_code_message(err.code)),
+            SendAndReceiveError::SendAndReceiveError(InternalSendAndReceiveError::Receive(
+                ReceiveError::Terminated,
+            )) => String::from("Lost the connection to the application."),
+            SendAndReceiveError::SendAndReceiveError(_) | SendAndReceiveError::UnexpectedResponse => {
+                String::from("Failed to communicate with the application.")
+            }
+        }
     }
-}
-
-#[derive(Debug)]
-pub enum SendAndReceiveError {
-    ResponseError(String),
-    SendAndReceiveError(InternalSendAndReceiveError<String>),
-}
 
-#[derive(Debug)]
-pub struct SendError(InternalSendError<String>);
-
-impl<E: Debug> From<InternalSendError<E>> for SendError {
-    fn from(value: InternalSendError<E>) -> Self {
-        match value {
-            InternalSendError::Serialize(ser) => {
-                SendError(InternalSendError::Serialize(ser))
-            }
-            InternalSendError::Send(e) => {
-                SendError(InternalSendError::Send(format!("{:?}", e)))
+    /// Whether the user can try the same operation again
+    pub fn retryable(&self) -> bool {
+        match self {
+            SendAndReceiveError::ResponseError(err) => err.retryable,
+            SendAndReceiveError::SendAndReceiveError(_) | SendAndReceiveError::UnexpectedResponse => {
+                false
             }
         }
     }
 }
 
-fn convert_res<R: TryFrom<ApplicationMessage, Error = ()>, E: Debug>(
-    err: Result<ResultResponse<R>, InternalSendAndReceiveError<E>>,
-) -> Result<R, SendAndReceiveError> {
-    match err {
-        Ok(res) => match res.0 {
-            Ok(sr) => Ok(sr),
-            Err(e) => Err(SendAndReceiveError::ResponseError(e)),
-        },
-        Err(e) => match e {
-            InternalSendAndReceiveError::Send(se) => match se {
-                InternalSendError::Send(sei) => {
-                    Err(SendAndReceiveError::SendAndReceiveError(
-                        InternalSendAndReceiveError::Send(InternalSendError::Send(format!("{:?}", sei))),
-                    ))
-                }
-                InternalSendError::Serialize(ser) => {
-                    Err(SendAndReceiveError::SendAndReceiveError(
-                        InternalSendAndReceiveError::Send(InternalSendError::Serialize(ser)),
-                    ))
-                }
-            },
-            InternalSendAndReceiveError::Receive(re) => {
-                Err(SendAndReceiveError::SendAndReceiveError(
-                    InternalSendAndReceiveError::Receive(re),
-                ))
-            }
-        },
+/// Localized message for an error reported by the app
+pub fn error_code_message(code: ErrorCode) -> &'static str {
+    match code {
+        ErrorCode::Database => "Could not read or write your data.",
+        ErrorCode::Storage => "Could not read or write the project files.",
+        ErrorCode::ProjectNotFound => "The project does not exist or has been removed.",
+        ErrorCode::TabNotFound => "The tab is not opened anymore.",
+        ErrorCode::ReadOnlyTab => "You can only view this project.",
+        ErrorCode::SessionNotFound => "The previous session is already closed.",
+        ErrorCode::Internal => "Something went wrong in the application.",
+        ErrorCode::ConnectionLost => "Lost the connection before the application answered.",
     }
 }
 
-#[async_trait]
-pub trait Client: Send + 'static {
-    async fn file_open(
-        &mut self,
-        project_id: String,
-    ) -> Result<TabCreatedMessage, SendAndReceiveError>;
-
-    async fn create_new_project(
-        &mut self,
-        project_name: String,
-    ) -> Result<TabCreatedMessage, SendAndReceiveError>;
+/// Converting the transport error to a type that not depend on the connection
+fn stringify_error<E: Debug>(err: InternalSendAndReceiveError<E>) -> InternalSendAndReceiveError<String> {
+    match err {
+        InternalSendAndReceiveError::Send(InternalSendError::Send(e)) => {
+            InternalSendAndReceiveError::Send(InternalSendError::Send(format!("{:?}", e)))
+        }
+        InternalSendAndReceiveError::Send(InternalSendError::Serialize(ser)) => {
+            InternalSendAndReceiveError::Send(InternalSendError::Serialize(ser))
+        }
+        InternalSendAndReceiveError::Receive(re) => InternalSendAndReceiveError::Receive(re),
+    }
+}
 
-    async fn close_tab(&mut self, tab_id: String) -> Result<(), SendError>;
+/// Client that UI is using to communicate with the app
+///
+/// Operations are provided by the `RpcClient` stub.
+pub trait Client: RpcClient<Error = InternalSendAndReceiveError<String>> + 'static {
+    /// Draining the messages that app pushed without a request
+    ///
+    /// This will not wait for new messages. Returns an empty list if nothing received yet.
+    fn pushed_messages(&mut self) -> Vec<PushMessage>;
 }
 
 #[async_trait]
-impl<E: Debug + Send + 'static, T: ClientTransport<E>> Client for ClientImpl<E, T> {
-    async fn file_open(
-        &mut self,
-        project_id: String,
-    ) -> Result<TabCreatedMessage, SendAndReceiveError> {
-        convert_res(self._file_open(project_id).await)
-    }
+impl<E: Debug + Send + 'static, T: ClientTransport<E>> RpcClient for ClientImpl<E, T> {
+    type Error = InternalSendAndReceiveError<String>;
 
-    async fn create_new_project(
-        &mut self,
-        project_name: String,
-    ) -> Result<TabCreatedMessage, SendAndReceiveError> {
-        convert_res(self._create_new_project(project_name).await)
+    async fn call(&mut self, request: RpcRequest) -> Result<RpcResponse, Self::Error> {
+        self.internal
+            .send_and_receive::<RpcRequest, RpcResponse>(request)
+            .await
+            .map_err(stringify_error)
     }
+}
 
-    async fn close_tab(&mut self, tab_id: String) -> Result<(), SendError> {
-        self._close_tab(tab_id).await.map_err(|e|e.into())
+impl<E: Debug + Send + 'static, T: ClientTransport<E>> Client for ClientImpl<E, T> {
+    fn pushed_messages(&mut self) -> Vec<PushMessage> {
+        let mut messages = vec![];
+        loop {
+            match self.internal.try_receive::<PushMessage>() {
+                Ok(Some(message)) => messages.push(message),
+                Ok(None) | Err(ReceiveError::Terminated) => break,
+                Err(e) => {
+                    warn!("Failed to receive a pushed message:- {:?}", e);
+                    break;
+                }
+            }
+        }
+        messages
     }
 }
 
 /// Main transport media between UI and application logics
 pub struct ClientImpl<E: Debug + Send, T: ClientTransport<E>> {
-    internal: InternalClient<ApplicationMessage, UIMessage, E, T>,
+    internal: InternalClient<ApplicationMessage, UIMessage, E, RepaintingTransport<T>>,
     _phantom: PhantomData<E>,
 }
 
 impl<E: Debug + Send, T: ClientTransport<E>> ClientImpl<E, T> {
-    pub fn new(internal: T) -> ClientImpl<E, T> {
+    /// Creating the client. `ctx` is repainted when a message arrived to the transport
+    pub fn new(internal: T, ctx: &Context) -> ClientImpl<E, T> {
         ClientImpl {
-            internal: InternalClient::new(internal),
+            internal: InternalClient::new(RepaintingTransport {
+                internal,
+                ctx: ctx.clone(),
+            }),
             _phantom: PhantomData,
         }
     }
+}
 
-    /// Opening a cached file
-    async fn _file_open(
-        &mut self,
-        project_id: String,
-    ) -> Result<ResultResponse<TabCreatedMessage>, InternalSendAndReceiveError<E>> {
-        self.internal
-            .send_and_receive::<OpenFileMessage, ResultResponse<TabCreatedMessage>>(
-                OpenFileMessage::new(project_id),
-            )
-            .await
+/// Waking the egui event loop with the task that is waiting for the transport
+struct RepaintWaker {
+    ctx: Context,
+    waker: Waker,
+}
+
+impl ArcWake for RepaintWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.ctx.request_repaint();
+        arc_self.waker.wake_by_ref();
     }
+}
 
-    /// Creating a new project
-    async fn _create_new_project(
-        &mut self,
-        project_name: String,
-    ) -> Result<ResultResponse<TabCreatedMessage>, InternalSendAndReceiveError<E>> {
-        self.internal
-            .send_and_receive::<NewProjectMessage, ResultResponse<TabCreatedMessage>>(
-                NewProjectMessage::new(project_name),
-            )
-            .await
+/// Transport that is repainting the UI when a new message is available
+///
+/// Pushed messages are read in the egui event loop. But egui is not running the loop
+/// without a user input. So the transport has to wake it, otherwise a pushed message is
+/// waiting until the user moved the mouse.
+struct RepaintingTransport<T> {
+    internal: T,
+    ctx: Context,
+}
+
+impl<T: Stream<Item = Vec<u8>> + Unpin> Stream for RepaintingTransport<T> {
+    type Item = Vec<u8>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let repaint_waker = waker(Arc::new(RepaintWaker {
+            ctx: this.ctx.clone(),
+            waker: cx.waker().clone(),
+        }));
+        Pin::new(&mut this.internal).poll_next(&mut TaskContext::from_waker(&repaint_waker))
+    }
+}
+
+impl<E, T: Sink<Vec<u8>, Error = E> + Unpin> Sink<Vec<u8>> for RepaintingTransport<T> {
+    type Error = E;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<(), E>> {
+        Pin::new(&mut self.get_mut().internal).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Vec<u8>) -> Result<(), E> {
+        Pin::new(&mut self.get_mut().internal).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<(), E>> {
+        Pin::new(&mut self.get_mut().internal).poll_flush(cx)
     }
 
-    async fn _close_tab(&mut self, tab_id: String) -> Result<(), InternalSendError<E>> {
-        self.internal.send(CloseTabMessage::new(tab_id)).await
+    fn poll_close(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<(), E>> {
+        Pin::new(&mut self.get_mut().internal).poll_close(cx)
     }
 }
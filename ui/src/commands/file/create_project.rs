@@ -1,13 +1,14 @@
 use poll_promise::Promise;
-use transport::{app::TabCreatedMessage, vo::Screen};
+use transport::{app::TabCreatedMessage, rpc::RpcClient, vo::Screen};
 
 use crate::{
-    commands::Command, scopes::ApplicationScope,
+    client::SendAndReceiveError, commands::Command, scopes::ApplicationScope, state::Severity,
 };
 
 pub struct CreateProjectCommand {
     app_scope: ApplicationScope,
-    create_project_promise: Promise<Result<TabCreatedMessage, String>>,
+    project_name: String,
+    create_project_promise: Promise<Result<TabCreatedMessage, SendAndReceiveError>>,
 }
 
 impl CreateProjectCommand {
@@ -20,13 +21,17 @@ impl CreateProjectCommand {
             .set_status_message(format!("Creating Project: {}", &project_name));
 
         let client = app_scope.client();
+        let cloned_project_name = project_name.clone();
 
         CreateProjectCommand {
             app_scope,
+            project_name,
             create_project_promise: Promise::spawn_async(async move {
                 let mut client_locked = client.lock().await;
-                let result = client_locked.create_new_project(project_name).await;
-                result.map_err(|e|format!("{:?}", e))
+                client_locked
+                    .new_project(cloned_project_name)
+                    .await
+                    .map_err(SendAndReceiveError::from)
             }),
         }
     }
@@ -38,7 +43,25 @@ impl CreateProjectCommand {
         zoom: f64,
         screens: Vec<Screen>,
     ) {
-        self.app_scope.add_project(tab_id, tab_name, zoom, screens);
+        // Creator is the owner of the project
+        self.app_scope.add_project(tab_id, tab_name, zoom, screens, false);
+    }
+
+    pub fn project_creation_failed(&mut self, err_msg: String, retryable: bool) {
+        let mut state_mut = self.app_scope.state_mut();
+        state_mut.clear_status_message();
+        let dialog = state_mut.add_dialog(
+            Severity::Error,
+            format!("Failed to create the project. {}", err_msg),
+        );
+
+        if retryable {
+            let app_scope = self.app_scope.clone();
+            let project_name = self.project_name.clone();
+            dialog
+                .add_button(Severity::Info, "Retry")
+                .on_click(move || Box::new(CreateProjectCommand::new(app_scope, project_name)));
+        }
     }
 }
 
@@ -56,9 +79,7 @@ impl Command for CreateProjectCommand {
                     self.app_scope.state_mut().clear_status_message();
                 }
                 Err(e) => {
-                    let mut state_mut = self.app_scope.state_mut();
-                    state_mut.add_dialog(crate::state::Severity::Error, e);
-                    state_mut.clear_status_message();
+                    self.project_creation_failed(e.user_message(), e.retryable());
                 }
             }
             true
@@ -1,3 +1,4 @@
 pub mod open_file;
 pub mod create_project;
+pub mod recent_projects;
 pub mod save_snapshot;
@@ -1,13 +1,17 @@
 use poll_promise::Promise;
-use transport::app::TabCreatedMessage;
+use transport::{app::TabCreatedMessage, rpc::RpcClient};
 
-use crate::{commands::Command, scopes::ApplicationScope};
+use crate::{
+    client::SendAndReceiveError, commands::Command, scopes::ApplicationScope, state::Severity,
+};
 
 pub struct FileOpenCommand {
     app_scope: ApplicationScope,
     file_dialog_promise: Option<Promise<Option<(Vec<u8>, String)>>>,
     opened_file_cache_promise: Option<Promise<Result<String, String>>>,
-    file_open_promise: Option<Promise<Result<TabCreatedMessage, String>>>,
+    file_open_promise: Option<Promise<Result<TabCreatedMessage, SendAndReceiveError>>>,
+    /// Id of the cached project that opening
+    cache_id: Option<String>,
 }
 
 impl FileOpenCommand {
@@ -33,10 +37,26 @@ impl FileOpenCommand {
             app_scope,
             file_dialog_promise: Some(file_dialog_promise),
             opened_file_cache_promise: None::<Promise<Result<String, String>>>,
-            file_open_promise: None::<Promise<Result<TabCreatedMessage, String>>>,
+            file_open_promise: None,
+            cache_id: None,
         }
     }
 
+    /// Opening an already cached file without asking the file from user
+    pub fn open_cached(app_scope: ApplicationScope, cache_id: String) -> Self {
+        app_scope.state_mut().disable_main_ui();
+
+        let mut cmd = FileOpenCommand {
+            app_scope,
+            file_dialog_promise: None,
+            opened_file_cache_promise: None,
+            file_open_promise: None,
+            cache_id: None,
+        };
+        cmd.open_file(cache_id);
+        cmd
+    }
+
     pub fn file_dialog_cancel(&mut self) {
         let mut state_mut = self.app_scope.state_mut();
         state_mut.enable_main_ui();
@@ -79,12 +99,15 @@ impl FileOpenCommand {
             .state_mut()
             .set_status_message("Opening the file");
         let client = self.app_scope.client();
+        self.cache_id = Some(cache_id.clone());
         let _ = self
             .file_open_promise
             .insert(Promise::spawn_async(async move {
                 let mut client_locked = client.lock().await;
-                let res = client_locked.file_open(cache_id).await;
-                res.map_err(|e| format!("{:?}", e))
+                client_locked
+                    .open_file(cache_id)
+                    .await
+                    .map_err(SendAndReceiveError::from)
             }));
     }
 
@@ -97,18 +120,24 @@ impl FileOpenCommand {
             tab_created_message.tab_name,
             tab_created_message.zoom,
             tab_created_message.screens,
+            tab_created_message.read_only,
         );
     }
 
-    pub fn file_open_failed(&mut self, err: String) {
+    pub fn file_open_failed(&mut self, err_msg: String, retryable: bool) {
         let mut state_mut = self.app_scope.state_mut();
-        state_mut.add_dialog(
-            crate::state::Severity::Error,
-            format!(
-                "Error occured during opening the project. Original error:- {}",
-                err
-            ),
+        let dialog = state_mut.add_dialog(
+            Severity::Error,
+            format!("Error occured during opening the project. {}", err_msg),
         );
+
+        if let (true, Some(cache_id)) = (retryable, self.cache_id.clone()) {
+            let app_scope = self.app_scope.clone();
+            dialog
+                .add_button(Severity::Info, "Retry")
+                .on_click(move || Box::new(FileOpenCommand::open_cached(app_scope, cache_id)));
+        }
+
         state_mut.enable_main_ui();
         state_mut.clear_status_message();
     }
@@ -159,7 +188,11 @@ impl Command for FileOpenCommand {
                         done = true;
                     }
                     Err(file_open_err) => {
-                        self.file_open_failed(file_open_err.clone());
+                        self.file_open_failed(
+                            file_open_err.user_message(),
+                            file_open_err.retryable(),
+                        );
+                        done = true;
                     }
                 }
             } else {
@@ -0,0 +1,43 @@
+use poll_promise::Promise;
+
+use crate::{
+    commands::Command, external::RecentProject, scopes::ApplicationScope, state::RecentProjects,
+};
+
+/// Loading the projects to list in the "Open recent" menu
+pub struct LoadRecentProjectsCommand {
+    app_scope: ApplicationScope,
+    recent_projects_promise: Promise<Result<Vec<RecentProject>, String>>,
+}
+
+impl LoadRecentProjectsCommand {
+    pub fn new(app_scope: ApplicationScope) -> LoadRecentProjectsCommand {
+        app_scope
+            .state_mut()
+            .set_recent_projects(RecentProjects::Loading);
+        let external_client = app_scope.external_client();
+        LoadRecentProjectsCommand {
+            app_scope,
+            recent_projects_promise: Promise::spawn_async(async move {
+                external_client.recent_projects().await
+            }),
+        }
+    }
+}
+
+impl Command for LoadRecentProjectsCommand {
+    fn update(&mut self) -> bool {
+        if let Some(recent_projects_res) = self.recent_projects_promise.ready() {
+            let recent_projects = match recent_projects_res {
+                Ok(projects) => RecentProjects::Loaded(projects.clone()),
+                Err(e) => RecentProjects::Failed(e.clone()),
+            };
+            self.app_scope
+                .state_mut()
+                .set_recent_projects(recent_projects);
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -3,6 +3,7 @@
 //! Those commands are triggering from the UI by the user. All the commands are async and
 //! using the `poll_promise` crate to track about the commands.
 pub mod file;
+pub mod session;
 pub mod tab;
 pub mod nope;
 
@@ -0,0 +1,55 @@
+use poll_promise::Promise;
+use transport::rpc::RpcClient;
+
+use crate::{
+    client::SendAndReceiveError, commands::Command, scopes::ApplicationScope,
+};
+
+/// Closing a previous session and dropping its tabs
+pub struct DiscardSessionCommand {
+    app_scope: ApplicationScope,
+    promise: Promise<Result<(), String>>,
+}
+
+impl DiscardSessionCommand {
+    pub fn new(app_scope: ApplicationScope, session_id: String) -> DiscardSessionCommand {
+        app_scope.state_mut().set_status_message("Discarding the previous session");
+
+        let client = app_scope.client();
+        let promise = Promise::spawn_async(async move {
+            let mut client = client.lock().await;
+            client
+                .discard_session(session_id)
+                .await
+                .map_err(|e| SendAndReceiveError::from(e).user_message())
+        });
+        DiscardSessionCommand { app_scope, promise }
+    }
+
+    pub fn discard_failed(&mut self, err_msg: String) {
+        let mut state_mut = self.app_scope.state_mut();
+        state_mut.add_dialog(
+            crate::state::Severity::Error,
+            format!(
+                "Error occured during discarding the previous session. Original error:- {}",
+                err_msg
+            ),
+        );
+        state_mut.clear_status_message();
+    }
+}
+
+impl Command for DiscardSessionCommand {
+    fn update(&mut self) -> bool {
+        if let Some(res) = self.promise.ready() {
+            if let Err(msg) = res {
+                self.discard_failed(msg.clone());
+            } else {
+                self.app_scope.state_mut().clear_status_message();
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
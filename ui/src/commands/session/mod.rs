@@ -0,0 +1,2 @@
+pub mod discard_session;
+pub mod restore_session;
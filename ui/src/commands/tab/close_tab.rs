@@ -1,7 +1,8 @@
 use poll_promise::Promise;
+use transport::rpc::RpcClient;
 
 use crate::{
-    commands::Command, scopes::ApplicationScope,
+    client::SendAndReceiveError, commands::Command, scopes::ApplicationScope,
 };
 
 pub struct TabCloseCommand {
@@ -24,7 +25,7 @@ impl TabCloseCommand {
             client
                 .close_tab(tab_id)
                 .await
-                .map_err(|e| format!("{:?}", e))
+                .map_err(|e| SendAndReceiveError::from(e).user_message())
         });
         TabCloseCommand {
             app_scope,
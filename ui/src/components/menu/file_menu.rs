@@ -1,13 +1,21 @@
 use egui::Ui;
 
 use crate::{
-    commands::file::{open_file::FileOpenCommand, save_snapshot::SaveSnapshotCommand},
+    commands::file::{
+        open_file::FileOpenCommand, recent_projects::LoadRecentProjectsCommand,
+        save_snapshot::SaveSnapshotCommand,
+    },
     components::UIComponent,
     scopes::ApplicationScope,
+    state::RecentProjects,
 };
 
 pub enum FileMenuComponentEvent {
     OpenFileClicked,
+    /// Opened the recent projects submenu before loading them
+    RecentProjectsRequested,
+    /// Clicked a project in the recent projects. Project id is the argument
+    RecentProjectClicked(String),
     NewProjectClicked,
     SaveClicked,
 }
@@ -26,6 +34,16 @@ impl FileMenuComponent {
             FileMenuComponentEvent::OpenFileClicked => {
                 self.app_scope.execute(FileOpenCommand::new(self.app_scope.clone()));
             }
+            FileMenuComponentEvent::RecentProjectsRequested => {
+                self.app_scope
+                    .execute(LoadRecentProjectsCommand::new(self.app_scope.clone()));
+            }
+            FileMenuComponentEvent::RecentProjectClicked(project_id) => {
+                self.app_scope.execute(FileOpenCommand::open_cached(
+                    self.app_scope.clone(),
+                    project_id,
+                ));
+            }
             FileMenuComponentEvent::NewProjectClicked => {
                 self.app_scope.state_mut().create_project_window_mut().open();
             }
@@ -36,6 +54,39 @@ impl FileMenuComponent {
     }
 }
 
+impl FileMenuComponent {
+    fn draw_recent_projects(&mut self, ui: &mut Ui) {
+        // Cloning to release the state before executing the commands
+        let recent_projects = self.app_scope.state().recent_projects().clone();
+        match recent_projects {
+            RecentProjects::NotLoaded => {
+                self.on(FileMenuComponentEvent::RecentProjectsRequested);
+                ui.label("Loading...");
+            }
+            RecentProjects::Loading => {
+                ui.label("Loading...");
+            }
+            RecentProjects::Failed(_) => {
+                ui.label("Could not load the projects");
+                if ui.button("Retry").clicked() {
+                    self.on(FileMenuComponentEvent::RecentProjectsRequested);
+                }
+            }
+            RecentProjects::Loaded(projects) => {
+                if projects.is_empty() {
+                    ui.label("No projects");
+                }
+                for project in projects {
+                    if ui.button(project.name).clicked() {
+                        self.on(FileMenuComponentEvent::RecentProjectClicked(project.id));
+                        ui.close_menu();
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl UIComponent for FileMenuComponent {
     fn draw(&mut self, ui: &mut Ui) {
         ui.menu_button("File", |ui| {
@@ -43,6 +94,9 @@ impl UIComponent for FileMenuComponent {
                 self.on(FileMenuComponentEvent::OpenFileClicked);
                 ui.close_menu();
             }
+            ui.menu_button("Open recent", |ui| {
+                self.draw_recent_projects(ui);
+            });
             if ui.button("New").clicked() {
                 self.on(FileMenuComponentEvent::NewProjectClicked);
                 ui.close_menu();
@@ -43,6 +43,11 @@ impl egui_dock::TabViewer for ProjectsTabViewer {
                 self.canvas_component.change_tab(*tab_idx);
             }
         }
+        if let Some(tab) = self.app_scope.state().tab(*tab_idx) {
+            if tab.borrow_mut().take_screens_changed() {
+                self.canvas_component.change_tab(*tab_idx);
+            }
+        }
         Frame::canvas(ui.style()).show(ui, |ui| {
             self.canvas_component.draw(ui);
         });
@@ -53,7 +58,10 @@ impl egui_dock::TabViewer for ProjectsTabViewer {
         match tab_found {
             Some(tab_found) => {
                 let tab_borrowed = tab_found.borrow();
-                let tab_title = tab_borrowed.title().clone();
+                let mut tab_title = tab_borrowed.title().clone();
+                if tab_borrowed.read_only() {
+                    tab_title = format!("{} (read-only)", tab_title);
+                }
                 let saved = tab_borrowed.saved();
                 if saved {
                     tab_title.into()
@@ -2,6 +2,13 @@
 
 use async_trait::async_trait;
 
+/// A project that the user opened before
+#[derive(Clone)]
+pub struct RecentProject {
+    pub id: String,
+    pub name: String,
+}
+
 #[cfg_attr(target_arch="wasm32",async_trait(?Send))]
 #[cfg_attr(not(target_arch="wasm32"),async_trait)]
 pub trait External: Send + Sync + 'static {
@@ -9,4 +16,7 @@ pub trait External: Send + Sync + 'static {
     async fn create_project_using_existing_file(&self, buf: Vec<u8>, project_name: String) -> Result<String, String>;
 
     async fn save_current_snapshot(&self) -> Result<(), String>;
+
+    /// Listing the projects of the user. Recently opened projects first
+    async fn recent_projects(&self) -> Result<Vec<RecentProject>, String>;
 }
@@ -7,14 +7,17 @@ use std::{sync::Arc, cell::{RefCell, Ref, RefMut}, rc::Rc};
 
 use egui_dock::DockState;
 use futures::lock::Mutex;
-use transport::vo::Screen;
+use transport::{app::PushMessage, vo::Screen};
 
 use crate::{
     client::Client,
-    commands::{Command, Executor},
+    commands::{
+        session::{discard_session::DiscardSessionCommand, restore_session::RestoreSessionCommand},
+        Command, Executor,
+    },
     components::tabs::{LeftPanelTabKind, RightPanelTabKind},
     external::External,
-    state::{AppState, CreateProjectWindowState},
+    state::{AppState, CreateProjectWindowState, Severity},
 };
 
 /// Application wide scope
@@ -93,8 +96,8 @@ impl ApplicationScope {
     }
 
     /// Adding a project as a tab
-    pub fn add_project(&self, id: String, title: String, zoom: f64, screens: Vec<Screen>) {
-        self.state.borrow_mut().add_project(id, title, zoom, screens);
+    pub fn add_project(&self, id: String, title: String, zoom: f64, screens: Vec<Screen>, read_only: bool) {
+        self.state.borrow_mut().add_project(id, title, zoom, screens, read_only);
         let count = self.state.borrow().tab_count();
         self.projects_tree.borrow_mut().push_to_first_leaf(count - 1);
     }
@@ -117,6 +120,103 @@ impl ApplicationScope {
 
         self.state.borrow_mut().remove_tab(tab_idx);
     }
+
+    /// Making the tab with the given id as the active tab
+    pub fn focus_tab(&self, tab_id: &str) {
+        let tab_idx = (0..self.state().tab_count()).find(|idx| {
+            self.state()
+                .tab(*idx)
+                .map(|t| t.borrow().id() == tab_id)
+                .unwrap_or(false)
+        });
+        if let Some(tab_idx) = tab_idx {
+            let tab_loc = self.projects_tree.borrow().find_tab(&tab_idx);
+            if let Some(tab_loc) = tab_loc {
+                self.projects_tree.borrow_mut().set_active_tab(tab_loc);
+            }
+        }
+    }
+
+    /// Applying the messages pushed by the app to the states
+    ///
+    /// This method will run in the egui event loop. Returns `false` if a command is currently
+    /// using the client. Messages will be kept in the client until the next iteration then.
+    pub fn dispatch_pushed_messages(&self) -> bool {
+        let messages = match self.client.try_lock() {
+            Some(mut client) => client.pushed_messages(),
+            None => return false,
+        };
+
+        for message in messages {
+            match message {
+                PushMessage::ScreensChanged(changed) => {
+                    if let Some(tab) = self.state().tab_by_id(&changed.tab_id) {
+                        tab.borrow_mut().apply_screen_patches(changed.patches);
+                    }
+                }
+                PushMessage::TabRenamed(renamed) => {
+                    if let Some(tab) = self.state().tab_by_id(&renamed.tab_id) {
+                        tab.borrow_mut().set_title(renamed.tab_name);
+                    }
+                }
+                PushMessage::CommitCreated(committed) => {
+                    if let Some(tab) = self.state().tab_by_id(&committed.tab_id) {
+                        tab.borrow_mut().set_saved(true);
+                    }
+                    self.state_mut()
+                        .set_status_message(format!("Committed: {}", committed.message));
+                }
+                PushMessage::SessionResumed(resumed) => {
+                    for tab in resumed.tabs {
+                        let exists = self.state().tab_by_id(&tab.tab_id).is_some();
+                        if !exists {
+                            self.add_project(tab.tab_id, tab.tab_name, tab.zoom, tab.screens, tab.read_only);
+                        }
+                    }
+                    if let Some(current_tab) = resumed.current_tab {
+                        self.focus_tab(&current_tab);
+                    }
+                }
+                PushMessage::PreviousSessionFound(found) => {
+                    let mut message = format!(
+                        "You have {} tab(s) left open from your previous session ({})",
+                        found.tabs.len(),
+                        found.tabs.join(", ")
+                    );
+                    if found.unsaved_actions > 0 {
+                        message.push_str(&format!(
+                            " with {} unsaved change(s)",
+                            found.unsaved_actions
+                        ));
+                    }
+                    message.push_str(". Do you want to restore them?");
+
+                    let mut state_mut = self.state_mut();
+                    let dialog = state_mut.add_dialog(Severity::Info, message);
+                    let app_scope = self.clone();
+                    let session_id = found.session_id.clone();
+                    dialog
+                        .add_button(Severity::Success, "Restore")
+                        .on_click(move || Box::new(RestoreSessionCommand::new(app_scope, session_id)));
+                    let app_scope = self.clone();
+                    let session_id = found.session_id;
+                    dialog
+                        .add_button(Severity::Warning, "Discard")
+                        .on_click(move || Box::new(DiscardSessionCommand::new(app_scope, session_id)));
+                }
+                PushMessage::SessionClosing(closing) => {
+                    let mut state_mut = self.state_mut();
+                    state_mut.add_dialog(
+                        Severity::Warning,
+                        format!("Your session is closing. Reason:- {}", closing.reason),
+                    );
+                    state_mut.disable_main_ui();
+                }
+            }
+        }
+
+        true
+    }
 }
 
 pub struct CreateProjectWindowScope {
@@ -3,7 +3,7 @@ use std::{slice::Iter, rc::Rc, cell::RefCell};
 
 use transport::vo::Screen;
 
-use crate::{commands::Command, tab::TabInfo};
+use crate::{commands::Command, external::RecentProject, tab::TabInfo};
 
 /// Application wide states
 pub struct AppState {
@@ -16,7 +16,9 @@ pub struct AppState {
     /// Message to display in status bar
     status_message: Option<String>,
     opened_projects: Vec<Rc<RefCell<TabInfo>>>,
-    create_project_window: CreateProjectWindowState
+    create_project_window: CreateProjectWindowState,
+    /// Projects to list in the "Open recent" menu
+    recent_projects: RecentProjects,
 }
 
 impl AppState {
@@ -29,6 +31,7 @@ impl AppState {
             status_message: None,
             opened_projects: vec![],
             create_project_window: CreateProjectWindowState::new(),
+            recent_projects: RecentProjects::NotLoaded,
         }
     }
 
@@ -125,9 +128,20 @@ impl AppState {
         &mut self.create_project_window
     }
 
+    pub fn recent_projects(&self) -> &RecentProjects {
+        &self.recent_projects
+    }
+
+    pub fn set_recent_projects(&mut self, recent_projects: RecentProjects) {
+        self.recent_projects = recent_projects;
+    }
+
     /// Adding a project as a tab
-    pub fn add_project(&mut self, id: String, title: String, zoom: f64, screens: Vec<Screen>) {
-        self.opened_projects.push(Rc::new(RefCell::new(TabInfo::new(id, title, zoom, screens))));
+    ///
+    /// The recent projects are loaded again on the next use since the order changed.
+    pub fn add_project(&mut self, id: String, title: String, zoom: f64, screens: Vec<Screen>, read_only: bool) {
+        self.opened_projects.push(Rc::new(RefCell::new(TabInfo::new(id, title, zoom, screens, read_only))));
+        self.recent_projects = RecentProjects::NotLoaded;
     }
 
     /// Retrieving a tab by index
@@ -135,6 +149,14 @@ impl AppState {
         self.opened_projects.get(index).map(|t|t.clone()).clone()
     }
 
+    /// Retrieving a tab by the id given by the app
+    pub fn tab_by_id(&self, id: &str) -> Option<Rc<RefCell<TabInfo>>> {
+        self.opened_projects
+            .iter()
+            .find(|t| t.borrow().id() == id)
+            .map(|t| t.clone())
+    }
+
     /// Returning the opened projects count
     pub fn tab_count(&self) -> usize {
         self.opened_projects.len()
@@ -145,6 +167,15 @@ impl AppState {
     }
 }
 
+/// Loading state of the recently opened projects
+#[derive(Clone)]
+pub enum RecentProjects {
+    NotLoaded,
+    Loading,
+    Loaded(Vec<RecentProject>),
+    Failed(String),
+}
+
 /// Severity of dialogs and dialog buttons
 #[derive(Clone)]
 pub enum Severity {
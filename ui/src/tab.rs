@@ -1,18 +1,22 @@
-use transport::vo::Screen;
+use transport::vo::{Screen, ScreenPatch};
 
 pub struct TabInfo {
     id: String,
     title: String,
     _zoom: f64,
     _mode: Mode,
-    _screens: Vec<Screen>,
+    screens: Vec<Screen>,
+    /// User can only view the project
+    read_only: bool,
     saved: bool,
     closing: bool,
+    /// Screens changed after the last time canvas drew them
+    screens_changed: bool,
 }
 
 impl TabInfo {
-    pub fn new(id: String, title: String, zoom: f64, screens: Vec<Screen>) -> TabInfo {
-        TabInfo { id, title, _zoom: zoom, _mode: Mode::Design, _screens: screens, saved: false, closing: false}
+    pub fn new(id: String, title: String, zoom: f64, screens: Vec<Screen>, read_only: bool) -> TabInfo {
+        TabInfo { id, title, _zoom: zoom, _mode: Mode::Design, screens, read_only, saved: false, closing: false, screens_changed: false}
     }
 
     pub fn id(&self) -> String {
@@ -23,6 +27,46 @@ impl TabInfo {
         self.title.clone()
     }
 
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn screens(&self) -> &Vec<Screen> {
+        &self.screens
+    }
+
+    /// Applying the screen changes pushed by the app
+    pub fn apply_screen_patches(&mut self, patches: Vec<ScreenPatch>) {
+        for patch in patches {
+            match patch {
+                ScreenPatch::Added(screen) => {
+                    self.screens.push(screen);
+                }
+                ScreenPatch::Updated(screen) => {
+                    if let Some(exist) = self.screens.iter_mut().find(|s| s.index == screen.index) {
+                        *exist = screen;
+                    }
+                }
+                ScreenPatch::Removed(index) => {
+                    self.screens.retain(|s| s.index != index);
+                }
+            }
+        }
+        self.saved = false;
+        self.screens_changed = true;
+    }
+
+    /// Returning whether the screens changed since the last call
+    pub fn take_screens_changed(&mut self) -> bool {
+        let changed = self.screens_changed;
+        self.screens_changed = false;
+        changed
+    }
+
     pub fn set_saved(&mut self, saved: bool)  {
         self.saved = saved;
     }
@@ -3,7 +3,7 @@
 //! All the components defined in `components` module should be linked
 //! here.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use egui::{
     CentralPanel, Context, FontData, FontDefinitions, FontFamily, Id, SidePanel, TopBottomPanel,
@@ -23,6 +23,9 @@ use crate::external::External;
 use crate::scopes::{ApplicationScope, CreateProjectWindowScope};
 use egui_wgpu::RenderState;
 
+/// Waiting time before reading the pushed messages again when the client was busy
+const PUSHED_MESSAGES_RETRY: Duration = Duration::from_millis(100);
+
 pub struct Ui {
     scope: ApplicationScope,
     // Componentes
@@ -86,6 +89,11 @@ impl Ui {
         let main_ui_disabled = self.scope.state().is_main_ui_disabled();
 
         self.scope.update_cmd_executor();
+        // Client is repainting when a message arrived. But we can not read it while a
+        // command is using the client, so checking again shortly
+        if !self.scope.dispatch_pushed_messages() {
+            ctx.request_repaint_after(PUSHED_MESSAGES_RETRY);
+        }
 
         TopBottomPanel::top("menu-bar").show(ctx, |ui| {
             ui.add_enabled_ui(!main_ui_disabled, |ui| {
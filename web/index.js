@@ -1,6 +1,6 @@
-const API_URL = process.env.API_URL;
-
-window.fetch(API_URL + "/api/test-auth")
+window.fetch("config.json")
+    .then((response) => response.json())
+    .then((config) => window.fetch(config.api_url + "/api/test-auth"))
     .then((response) => response.json())
     .then((json) => {
         window.localStorage.setItem("_token", json.token);
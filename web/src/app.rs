@@ -15,7 +15,7 @@ pub struct WebApp {
 
 impl WebApp {
     pub fn new(cc: &CreationContext<'_>, ws: WebSocket) -> WebApp {
-        let client = Box::new(ClientImpl::new(ws));
+        let client = Box::new(ClientImpl::new(ws, &cc.egui_ctx));
         let external = Box::new(RestApi::new());
         let wgpu = cc.wgpu_render_state.as_ref().unwrap();
         WebApp {
@@ -1,6 +1,40 @@
-use dotenv_codegen::dotenv;
+//! Endpoints of the server
+//!
+//! Fetched at the startup from the `config.json` served next to the page. So the same bundle
+//! can be deployed with different servers.
 
-pub const WS_HOST: &str = dotenv!("WS_HOST");
-pub const WS_PORT: &str = dotenv!("WS_PORT");
-pub const WS_PATH: &str = dotenv!("WS_PATH");
-pub const API_URL: &str = dotenv!("API_URL");
+use once_cell::sync::OnceCell;
+use rest::system::WebConfig;
+use serde_json::from_str;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, Response};
+
+/// Path of the config file relative to the page
+const CONFIG_PATH: &str = "config.json";
+
+static CONFIG: OnceCell<WebConfig> = OnceCell::new();
+
+/// Fetching the config file. Should be called once before using `config`
+pub async fn load() -> Result<&'static WebConfig, JsValue> {
+    let win = window().unwrap();
+    let resp_value = JsFuture::from(win.fetch_with_str(CONFIG_PATH)).await?;
+    let resp: Response = resp_value.dyn_into()?;
+    if !resp.ok() {
+        return Err(JsValue::from_str(&format!(
+            "Could not fetch the {}. Status:- {}",
+            CONFIG_PATH,
+            resp.status()
+        )));
+    }
+    let body = JsFuture::from(resp.text()?).await?;
+    let config: WebConfig = from_str(&body.as_string().unwrap_or_default()).map_err(|e| {
+        JsValue::from_str(&format!("Could not parse the {}. {}", CONFIG_PATH, e))
+    })?;
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// Configuration fetched at the startup
+pub fn config() -> &'static WebConfig {
+    CONFIG.get().expect("Configuration is not loaded")
+}
@@ -1,4 +1,3 @@
-use config::{WS_HOST, WS_PATH, WS_PORT};
 use eframe::WebRunner;
 use log::Level;
 use wasm_bindgen::prelude::*;
@@ -37,8 +36,8 @@ impl WebHandle {
 
         let ticket = extract_ticket_id().expect("Ticket ID not provided");
         let web_options = eframe::WebOptions::default();
-        let ws_url = format!("ws://{}:{}{}?ticket={}", WS_HOST, WS_PORT, WS_PATH, ticket);
-        let ws_res = WebSocket::connect(&ws_url).await;
+        let config = config::load().await?;
+        let ws_res = WebSocket::connect(&config.ws_url, ticket).await;
         match ws_res {
             Ok(ws) => self.runner.start(
                 canvas_id,
@@ -2,14 +2,20 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use js_sys::Uint8Array;
-use serde::Deserialize;
+use rest::{
+    paths,
+    projects::{CurrentTabSnapshotResponse, OxdUploadSuccessResponse, ProjectPage},
+};
 use serde_json::{from_str, Error as JsonError};
-use ui::external::External;
+use ui::external::{External, RecentProject};
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{window, Blob, FormData, Headers, Request, RequestInit, Response};
 
-use crate::config::API_URL;
+use crate::config::config;
+
+/// Count of projects to list in the "Open recent" menu
+const RECENT_PROJECTS_COUNT: usize = 10;
 
 pub struct RestApi;
 
@@ -52,11 +58,6 @@ impl External for RestApi {
         buf: Vec<u8>,
         project_name: String,
     ) -> Result<String, String> {
-        #[derive(Deserialize)]
-        struct SuccessResponse {
-            id: String,
-        }
-
         let win = window().unwrap();
         let local_storage = win.local_storage().unwrap().unwrap();
         let token = local_storage.get_item("_token").unwrap();
@@ -86,7 +87,7 @@ impl External for RestApi {
                 init.headers(&headers);
 
                 let request = Request::new_with_str_and_init(
-                    &format!("{}/api/create-project", API_URL),
+                    &format!("{}{}", config().api_url, paths::CREATE_PROJECT),
                     &init,
                 )
                 .map_err(RestApiError::from)?;
@@ -108,8 +109,8 @@ impl External for RestApi {
                     return Err(RestApiError::ResponseMismatch(status, res_str).into());
                 }
 
-                let success_res =
-                    from_str::<SuccessResponse>(&res_str).map_err(RestApiError::from)?;
+                let success_res = from_str::<OxdUploadSuccessResponse>(&res_str)
+                    .map_err(RestApiError::from)?;
 
                 Ok(success_res.id)
             }
@@ -118,11 +119,6 @@ impl External for RestApi {
     }
 
     async fn save_current_snapshot(&self) -> Result<(), String> {
-        #[derive(Deserialize)]
-        struct SuccessResponse {
-            download_id: String,
-        }
-
         let win = window().unwrap();
         let local_storage = win.local_storage().map_err(RestApiError::from)?;
         let token = local_storage
@@ -134,7 +130,7 @@ impl External for RestApi {
             let mut init = RequestInit::new();
             init.method("GET");
 
-            let url = format!("{}/api/current-tab-snapshot", API_URL);
+            let url = format!("{}{}", config().api_url, paths::CURRENT_TAB_SNAPSHOT);
 
             let request =
                 Request::new_with_str_and_init(&url, &init).map_err(RestApiError::from)?;
@@ -163,16 +159,80 @@ impl External for RestApi {
                 return Err(RestApiError::ResponseMismatch(status, body_str).into());
             }
 
-            let success_res = from_str::<SuccessResponse>(&body_str).map_err(RestApiError::from)?;
+            let success_res = from_str::<CurrentTabSnapshotResponse>(&body_str)
+                .map_err(RestApiError::from)?;
 
             let download_id = success_res.download_id;
 
-            win.open_with_url(&format!("{}/api/snapshot/{}", API_URL, download_id))
-                .map_err(RestApiError::from)?;
+            win.open_with_url(&format!(
+                "{}{}",
+                config().api_url,
+                paths::with_params(paths::SNAPSHOT, &[&download_id])
+            ))
+            .map_err(RestApiError::from)?;
 
             Ok(())
         } else {
             Err(RestApiError::TokenNotSet.into())
         }
     }
+
+    async fn recent_projects(&self) -> Result<Vec<RecentProject>, String> {
+        let win = window().unwrap();
+        let local_storage = win.local_storage().map_err(RestApiError::from)?;
+        let token = local_storage
+            .unwrap()
+            .get_item("_token")
+            .map_err(RestApiError::from)?;
+
+        if let Some(token) = token {
+            let mut init = RequestInit::new();
+            init.method("GET");
+
+            let url = format!(
+                "{}{}?per_page={}",
+                config().api_url,
+                paths::PROJECTS,
+                RECENT_PROJECTS_COUNT
+            );
+
+            let request =
+                Request::new_with_str_and_init(&url, &init).map_err(RestApiError::from)?;
+
+            request
+                .headers()
+                .set("Authorization", &format!("Bearer {}", token))
+                .map_err(RestApiError::from)?;
+
+            let resp_value = JsFuture::from(win.fetch_with_request(&request))
+                .await
+                .map_err(RestApiError::from)?;
+
+            assert!(resp_value.is_instance_of::<Response>());
+            let resp: Response = resp_value.dyn_into().unwrap();
+
+            let status = resp.status();
+            let body_value = JsFuture::from(resp.text().map_err(RestApiError::from)?)
+                .await
+                .map_err(RestApiError::from)?;
+            let body_str = body_value.as_string().unwrap();
+
+            if status >= 300 || status < 200 {
+                return Err(RestApiError::ResponseMismatch(status, body_str).into());
+            }
+
+            let success_res = from_str::<ProjectPage>(&body_str).map_err(RestApiError::from)?;
+
+            Ok(success_res
+                .projects
+                .into_iter()
+                .map(|project| RecentProject {
+                    id: project.id,
+                    name: project.name,
+                })
+                .collect())
+        } else {
+            Err(RestApiError::TokenNotSet.into())
+        }
+    }
 }
@@ -1,14 +1,48 @@
-use std::task::Poll;
+use std::{collections::VecDeque, future::Future, pin::Pin, task::Poll};
 
-use futures::{Stream, Sink};
-use pin_project::pin_project;
-use ws_stream_wasm::{WsErr, WsMessage, WsMeta, WsStream};
+use bincode::{deserialize as from_bin, serialize as to_bin};
+use futures::{ready, Sink, Stream};
+use gloo_timers::future::TimeoutFuture;
+use log::{info, warn};
+use send_wrapper::SendWrapper;
+use transport::{
+    app::{ApplicationMessage, ErrorCode, ErrorMessage},
+    rpc::RpcRequest,
+    ui::UIMessage,
+};
+use ws_stream_wasm::{WsErr, WsMessage, WsMeta, WsState, WsStream};
 
-#[pin_project]
+/// Waiting time before the first reconnect attempt
+const RECONNECT_INITIAL_DELAY_MS: u32 = 500;
+/// Upper limit of the waiting time between two reconnect attempts
+const RECONNECT_MAX_DELAY_MS: u32 = 30_000;
+/// Giving up the reconnecting after this number of failed attempts
+const RECONNECT_MAX_ATTEMPTS: u32 = 10;
+
+type ReconnectFuture = SendWrapper<Pin<Box<dyn Future<Output = Option<(WsMeta, WsStream)>>>>>;
+
+enum State {
+    Connected { meta: WsMeta, stream: WsStream },
+    Reconnecting(ReconnectFuture),
+    Closed,
+}
+
+/// WebSocket connection to the server that reconnecting transparently
+///
+/// Server is sending a resumable ticket after every successful connection. When the
+/// connection dropped, we are reconnecting with exponential backoff using the last received
+/// ticket and the server will reattach us to the same session.
+///
+/// Requests that were waiting for a response when the connection dropped are answered with a
+/// retryable error. The reattached session is not answering them.
 pub struct WebSocket {
-    #[pin]
-    internal: WsStream,
-    meta: WsMeta,
+    base_url: String,
+    ticket: String,
+    state: State,
+    /// Requests sent through the current connection and waiting for the response
+    waiting: VecDeque<RpcRequest>,
+    /// Serialized error responses of the requests lost with the previous connection
+    failed: VecDeque<Vec<u8>>,
 }
 
 #[derive(Debug)]
@@ -21,18 +55,100 @@ impl From<WsErr> for WebSocketError {
 }
 
 impl WebSocket {
-    pub async fn connect(url: &str) -> Result<WebSocket, WebSocketError> {
-        let (ws, wsstream) = WsMeta::connect(url, None).await?;
+    pub async fn connect(base_url: &str, ticket: String) -> Result<WebSocket, WebSocketError> {
+        let (meta, stream) = WsMeta::connect(ticket_url(base_url, &ticket), None).await?;
         Ok(WebSocket {
-            meta: ws,
-            internal: wsstream,
+            base_url: String::from(base_url),
+            ticket,
+            state: State::Connected { meta, stream },
+            waiting: VecDeque::new(),
+            failed: VecDeque::new(),
         })
     }
 
-    pub async fn close(&self) -> Result<(), WebSocketError> {
-        self.meta.close().await?;
+    pub async fn close(&mut self) -> Result<(), WebSocketError> {
+        if let State::Connected { meta, .. } = &self.state {
+            meta.close().await?;
+        }
+        self.state = State::Closed;
         Ok(())
     }
+
+    fn start_reconnect(&mut self) {
+        warn!("WebSocket connection dropped. Reconnecting");
+        self.fail_waiting_requests();
+        let url = ticket_url(&self.base_url, &self.ticket);
+        self.state = State::Reconnecting(SendWrapper::new(Box::pin(reconnect(url))));
+    }
+
+    /// Answering the waiting requests with a retryable error
+    fn fail_waiting_requests(&mut self) {
+        while let Some(request) = self.waiting.pop_front() {
+            let error = ErrorMessage::new(
+                ErrorCode::ConnectionLost,
+                String::from("Connection dropped before the response received"),
+                Some(UIMessage::Rpc(request.clone())),
+                true,
+            );
+            let response: ApplicationMessage = request.error_response(error).into();
+            match to_bin(&response) {
+                Ok(data) => self.failed.push_back(data),
+                Err(e) => warn!("Failed to serialize the error response:- {:?}", e),
+            }
+        }
+    }
+
+    /// Driving the reconnect attempts until the connection is available again
+    fn poll_connected(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<&mut WsStream, WebSocketError>> {
+        loop {
+            match &mut self.state {
+                State::Connected { stream, .. } => match stream.ready_state() {
+                    WsState::Closing | WsState::Closed => {
+                        self.start_reconnect();
+                    }
+                    _ => break,
+                },
+                State::Reconnecting(reconnecting) => match ready!(reconnecting.as_mut().poll(cx)) {
+                    Some((meta, stream)) => {
+                        info!("WebSocket reconnected");
+                        self.state = State::Connected { meta, stream };
+                    }
+                    None => {
+                        warn!("Giving up reconnecting the WebSocket");
+                        self.state = State::Closed;
+                    }
+                },
+                State::Closed => {
+                    return Poll::Ready(Err(WebSocketError(WsErr::ConnectionNotOpen)));
+                }
+            }
+        }
+
+        match &mut self.state {
+            State::Connected { stream, .. } => Poll::Ready(Ok(stream)),
+            _ => Poll::Ready(Err(WebSocketError(WsErr::ConnectionNotOpen))),
+        }
+    }
+}
+
+fn ticket_url(base_url: &str, ticket: &str) -> String {
+    format!("{}?ticket={}", base_url, ticket)
+}
+
+async fn reconnect(url: String) -> Option<(WsMeta, WsStream)> {
+    let mut delay = RECONNECT_INITIAL_DELAY_MS;
+    for attempt in 1..=RECONNECT_MAX_ATTEMPTS {
+        TimeoutFuture::new(delay).await;
+        match WsMeta::connect(&url, None).await {
+            Ok(connection) => return Some(connection),
+            Err(e) => warn!("Reconnect attempt {} failed:- {:?}", attempt, e),
+        }
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY_MS);
+    }
+    None
 }
 
 impl Stream for WebSocket {
@@ -42,41 +158,86 @@ impl Stream for WebSocket {
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        let this = self.project();
-        let polled: Poll<Option<WsMessage>> = this.internal.poll_next(cx);
-        polled.map(|m_opt| {
-            m_opt.map(|m| match m {
-                WsMessage::Text(_) => Vec::new(),
-                WsMessage::Binary(data) => data,
-            })
-        })
+        let this = self.get_mut();
+        loop {
+            if let Some(data) = this.failed.pop_front() {
+                return Poll::Ready(Some(data));
+            }
+            let stream = match ready!(this.poll_connected(cx)) {
+                Ok(stream) => stream,
+                Err(_) => return Poll::Ready(None),
+            };
+            match ready!(Pin::new(stream).poll_next(cx)) {
+                Some(WsMessage::Binary(data)) => {
+                    match from_bin::<ApplicationMessage>(&data) {
+                        // Resume tickets are only for the connection. Not forwarding to the UI
+                        Ok(ApplicationMessage::ResumeTicket(resume)) => {
+                            this.ticket = resume.ticket;
+                            continue;
+                        }
+                        // Session is answering the requests in the order received
+                        Ok(ApplicationMessage::Rpc(_)) => {
+                            this.waiting.pop_front();
+                        }
+                        _ => {}
+                    }
+                    return Poll::Ready(Some(data));
+                }
+                Some(WsMessage::Text(_)) => {}
+                None => {
+                    if let State::Connected { .. } = this.state {
+                        this.start_reconnect();
+                    }
+                }
+            }
+        }
     }
 }
 
 impl Sink<Vec<u8>> for WebSocket {
     type Error = WebSocketError;
-    
+
     fn start_send(self: std::pin::Pin<&mut Self>, item: Vec<u8>) -> Result<(), Self::Error> {
-        let this = self.project();
-        let result: Result<(), WsErr> = this.internal.start_send(WsMessage::Binary(item));
-        result.map_err(|e|WebSocketError(e))
+        let this = self.get_mut();
+        let request = match from_bin::<UIMessage>(&item) {
+            Ok(UIMessage::Rpc(request)) => Some(request),
+            _ => None,
+        };
+        match &mut this.state {
+            State::Connected { stream, .. } => Pin::new(stream)
+                .start_send(WsMessage::Binary(item))
+                .map_err(|e| WebSocketError(e))?,
+            _ => return Err(WebSocketError(WsErr::ConnectionNotOpen)),
+        }
+        if let Some(request) = request {
+            this.waiting.push_back(request);
+        }
+        Ok(())
     }
 
     fn poll_ready(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let this = self.project();
-        let ready: Poll<Result<(), WsErr>> = this.internal.poll_ready(cx);
+        let stream = ready!(self.get_mut().poll_connected(cx))?;
+        let ready: Poll<Result<(), WsErr>> = Pin::new(stream).poll_ready(cx);
         ready.map(|r|r.map_err(|e|WebSocketError(e)))
     }
 
     fn poll_flush(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let this = self.project();
-        let flushed = this.internal.poll_flush(cx);
-        flushed.map(|r|r.map_err(|e|WebSocketError(e)))
+        match &mut self.get_mut().state {
+            State::Connected { stream, .. } => {
+                let flushed = Pin::new(stream).poll_flush(cx);
+                flushed.map(|r|r.map_err(|e|WebSocketError(e)))
+            }
+            _ => Poll::Ready(Ok(())),
+        }
     }
 
     fn poll_close(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let this = self.project();
-        let closed = this.internal.poll_close(cx);
-        closed.map(|r|r.map_err(|e|WebSocketError(e)))
+        let this = self.get_mut();
+        let closed = match &mut this.state {
+            State::Connected { stream, .. } => ready!(Pin::new(stream).poll_close(cx)),
+            _ => Ok(()),
+        };
+        this.state = State::Closed;
+        Poll::Ready(closed.map_err(|e|WebSocketError(e)))
     }
 }
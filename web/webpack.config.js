@@ -1,6 +1,5 @@
 const path = require("path");
 const WasmPackPlugin = require("@wasm-tool/wasm-pack-plugin");
-const Dotenv = require("dotenv-webpack")
 const CopyWebPackPlugin = require("copy-webpack-plugin");
 
 module.exports = {
@@ -14,13 +13,12 @@ module.exports = {
     filename: "[name].js",
   },
   plugins: [
-    new Dotenv({
-      path: path.resolve(__dirname, "..", ".env")
-    }),
     new CopyWebPackPlugin({
         patterns: [
             {from: path.resolve(__dirname, "editor.html"), to: path.resolve(__dirname, "..", "dist", "web")},
             {from: path.resolve(__dirname, "index.html"), to: path.resolve(__dirname, "..", "dist", "web")},
+            // Endpoints of the server. Copy `config.example.json` to `config.json` to create it
+            {from: path.resolve(__dirname, "config.json"), to: path.resolve(__dirname, "..", "dist", "web"), noErrorOnMissing: true},
         ]
     }),
     new WasmPackPlugin({